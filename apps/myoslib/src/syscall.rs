@@ -1,6 +1,6 @@
 // myos system calls
 
-use myosabi::svc::Function;
+use myosabi::{svc::Function, SystemInfo};
 
 #[link(wasm_import_module = "megos-canary")]
 extern "C" {
@@ -58,6 +58,19 @@ pub fn os_version() -> u32 {
     unsafe { svc1(Function::GetSystemInfo, 0) as u32 }
 }
 
+/// Get the system information.
+#[inline]
+pub fn os_get_system_info(info: &mut SystemInfo) -> usize {
+    unsafe {
+        svc3(
+            Function::GetSystemInfo,
+            1,
+            info as *mut _ as usize,
+            core::mem::size_of::<SystemInfo>(),
+        )
+    }
+}
+
 /// Create a new window.
 #[inline]
 pub fn os_new_window1(title: &str, width: usize, height: usize) -> usize {
@@ -176,6 +189,43 @@ pub fn os_refresh_window(window: usize) {
     unsafe { svc1(Function::RefreshWindow, window) };
 }
 
+/// Open a file.
+#[inline]
+pub fn os_open(name: &str) -> usize {
+    unsafe { svc2(Function::OpenFile, name.as_ptr() as usize, name.len()) }
+}
+
+/// Read data from a file.
+#[inline]
+pub fn os_read(handle: usize, buf: &mut [u8]) -> usize {
+    unsafe {
+        svc3(
+            Function::ReadFile,
+            handle,
+            buf.as_mut_ptr() as usize,
+            buf.len(),
+        )
+    }
+}
+
+/// Close a file.
+#[inline]
+pub fn os_close(handle: usize) {
+    unsafe { svc1(Function::CloseFile, handle) };
+}
+
+/// Create an off-screen surface.
+#[inline]
+pub fn os_create_surface(width: usize, height: usize, bg_color: usize, flag: usize) -> usize {
+    unsafe { svc4(Function::CreateSurface, width, height, bg_color, flag) }
+}
+
+/// Draw an off-screen surface in a window or another surface.
+#[inline]
+pub fn os_blt_surface(target: usize, x: usize, y: usize, surface: usize) {
+    unsafe { svc4(Function::BltSurface, target, x, y, surface) };
+}
+
 /// Return a random number
 #[inline]
 pub fn os_rand() -> u32 {
@@ -734,6 +734,7 @@ _initrd_size    dd 0
 _smap:
 _start_mid      dd 0x00100000
 _memsz_mid      dd 0
+_vram_base_hi   dd 0
 
 _kend           dd 0
 
@@ -8,7 +8,33 @@ use bitflags::*;
 use byteorder::*;
 use core::cell::UnsafeCell;
 use core::convert::TryFrom;
-use core::mem::transmute;
+use core::mem::{align_of, size_of, size_of_val, transmute};
+
+/// Reinterprets a slice of `T` as a slice of `U`.
+///
+/// # Safety
+///
+/// Every bit pattern of `T` must be a valid `U`, and both types must be free of padding.
+#[inline]
+const unsafe fn cast_slice<T, U>(slice: &[T]) -> &[U] {
+    assert!(align_of::<U>() <= align_of::<T>());
+    let len = size_of_val(slice);
+    debug_assert!(len.is_multiple_of(size_of::<U>()));
+    core::slice::from_raw_parts(slice.as_ptr() as *const U, len / size_of::<U>())
+}
+
+/// Reinterprets a mutable slice of `T` as a mutable slice of `U`.
+///
+/// # Safety
+///
+/// The same as `cast_slice`, and also every bit pattern of `U` must be a valid `T`.
+#[inline]
+unsafe fn cast_slice_mut<T, U>(slice: &mut [T]) -> &mut [U] {
+    assert!(align_of::<U>() <= align_of::<T>());
+    let len = size_of_val(slice);
+    debug_assert!(len.is_multiple_of(size_of::<U>()));
+    core::slice::from_raw_parts_mut(slice.as_mut_ptr() as *mut U, len / size_of::<U>())
+}
 
 pub trait Drawable
 where
@@ -27,6 +53,111 @@ where
     fn bounds(&self) -> Rect {
         Rect::from(self.size())
     }
+
+    /// Returns whether the drawable has no pixels.
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.width() == 0 || self.height() == 0
+    }
+}
+
+/// Counters of the code paths taken, so that the tests can tell which one was used.
+#[cfg(test)]
+mod probe {
+    use core::cell::Cell;
+    use std::thread::LocalKey;
+
+    std::thread_local! {
+        /// The primitives called on the `Bitmap` enum
+        pub static ENUM_PRIMITIVES: Cell<usize> = const { Cell::new(0) };
+        /// The rects filled at once because their rows are adjacent in memory
+        pub static CONTIGUOUS_FILLS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    #[inline]
+    pub fn hit(key: &'static LocalKey<Cell<usize>>) {
+        key.with(|v| v.set(v.get() + 1));
+    }
+
+    #[inline]
+    pub fn take(key: &'static LocalKey<Cell<usize>>) -> usize {
+        key.with(|v| v.replace(0))
+    }
+}
+
+/// Clamps the size of a newly allocated bitmap to at least 1x1.
+#[inline]
+fn non_empty_size(size: Size) -> Size {
+    Size::new(isize::max(size.width(), 1), isize::max(size.height(), 1))
+}
+
+/// Clips `rect` of a source of `src_size` copied to `origin` of a destination of `dest_size`.
+///
+/// Returns `(dx, dy, sx, sy, width, height)`, or `None` if nothing is left to copy.
+#[inline]
+fn clip_blt(
+    origin: Point,
+    rect: Rect,
+    src_size: Size,
+    dest_size: Size,
+) -> Option<(usize, usize, usize, usize, usize, usize)> {
+    let mut dx = origin.x;
+    let mut dy = origin.y;
+    let mut sx = rect.origin.x;
+    let mut sy = rect.origin.y;
+    let mut width = rect.width();
+    let mut height = rect.height();
+
+    if sx < 0 {
+        dx = dx.saturating_sub(sx);
+        width = width.saturating_add(sx);
+        sx = 0;
+    }
+    if sy < 0 {
+        dy = dy.saturating_sub(sy);
+        height = height.saturating_add(sy);
+        sy = 0;
+    }
+    if dx < 0 {
+        sx = sx.saturating_sub(dx);
+        width = width.saturating_add(dx);
+        dx = 0;
+    }
+    if dy < 0 {
+        sy = sy.saturating_sub(dy);
+        height = height.saturating_add(dy);
+        dy = 0;
+    }
+    let sw = src_size.width();
+    let sh = src_size.height();
+    if sx.saturating_add(width) > sw {
+        width = sw - sx;
+    }
+    if sy.saturating_add(height) > sh {
+        height = sh - sy;
+    }
+    let r = dx.saturating_add(width);
+    let b = dy.saturating_add(height);
+    let dw = dest_size.width();
+    let dh = dest_size.height();
+    if r >= dw {
+        width = dw - dx;
+    }
+    if b >= dh {
+        height = dh - dy;
+    }
+    if width <= 0 || height <= 0 {
+        return None;
+    }
+
+    Some((
+        dx as usize,
+        dy as usize,
+        sx as usize,
+        sy as usize,
+        width as usize,
+        height as usize,
+    ))
 }
 
 pub trait GetPixel: Drawable {
@@ -265,7 +396,7 @@ pub trait BasicDrawing: SetPixel {
                 self.draw_hline(c2, width, color);
             }
         } else {
-            c1.line_to(c2, |point| {
+            c1.line_to_clipped(c2, self.bounds(), |point| {
                 self.set_pixel(point, color);
             });
         }
@@ -276,56 +407,34 @@ pub trait RasterFontWriter: SetPixel {
     fn draw_font(&mut self, src: &[u8], size: Size, origin: Point, color: Self::ColorType) {
         let stride = (size.width as usize + 7) / 8;
 
-        let mut coords = match Coordinates::from_rect(Rect { origin, size }) {
+        let coords = match Coordinates::from_rect(Rect { origin, size }) {
             Ok(v) => v,
             Err(_) => return,
         };
 
+        // the visible part of the glyph
         let width = self.width() as isize;
         let height = self.height() as isize;
-        if coords.right > width {
-            coords.right = width;
-        }
-        if coords.bottom > height {
-            coords.bottom = height;
-        }
-        if coords.left < 0 || coords.left >= width || coords.top < 0 || coords.top >= height {
+        let sx0 = 0isize.saturating_sub(coords.left).max(0);
+        let sy0 = 0isize.saturating_sub(coords.top).max(0);
+        let sx1 = isize::min(coords.right, width).saturating_sub(coords.left);
+        let sy1 = isize::min(coords.bottom, height).saturating_sub(coords.top);
+        if sx0 >= sx1 || sy0 >= sy1 {
             return;
         }
 
-        let new_rect = Rect::from(coords);
-        let width = new_rect.width() as usize;
-        let height = new_rect.height();
-        let w8 = width / 8;
-        let w7 = width & 7;
-        let mut cursor = 0;
-        for y in 0..height {
-            for i in 0..w8 {
-                let data = unsafe { src.get_unchecked(cursor + i) };
-                for j in 0..8 {
-                    let position = 0x80u8 >> j;
-                    if (data & position) != 0 {
-                        let x = (i * 8 + j) as isize;
-                        let y = y;
-                        let point = Point::new(origin.x + x, origin.y + y);
-                        self.set_pixel(point, color);
-                    }
-                }
-            }
-            if w7 > 0 {
-                let data = unsafe { src.get_unchecked(cursor + w8) };
-                let base_x = w8 * 8;
-                for i in 0..w7 {
-                    let position = 0x80u8 >> i;
-                    if (data & position) != 0 {
-                        let x = (i + base_x) as isize;
-                        let y = y;
-                        let point = Point::new(origin.x + x, origin.y + y);
-                        self.set_pixel(point, color);
-                    }
+        for y in sy0..sy1 {
+            let cursor = y as usize * stride;
+            for x in sx0..sx1 {
+                let data = match src.get(cursor + x as usize / 8) {
+                    Some(v) => *v,
+                    None => return,
+                };
+                if (data & (0x80u8 >> (x & 7))) != 0 {
+                    let point = Point::new(coords.left + x, coords.top + y);
+                    self.set_pixel(point, color);
                 }
             }
-            cursor += stride;
         }
     }
 }
@@ -336,52 +445,16 @@ pub trait BltConverter<T: ColorTrait>: MutableRasterImage {
         U: RasterImage<ColorType = T>,
         F: FnMut(T) -> Self::ColorType,
     {
-        let mut dx = origin.x;
-        let mut dy = origin.y;
-        let mut sx = rect.origin.x;
-        let mut sy = rect.origin.y;
-        let mut width = rect.width();
-        let mut height = rect.height();
-
-        if dx < 0 {
-            sx -= dx;
-            width += dx;
-            dx = 0;
-        }
-        if dy < 0 {
-            sy -= dy;
-            height += dy;
-            dy = 0;
-        }
-        let sw = src.width() as isize;
-        let sh = src.height() as isize;
-        if width > sx + sw {
-            width = sw - sx;
-        }
-        if height > sy + sh {
-            height = sh - sy;
-        }
-        let r = dx + width;
-        let b = dy + height;
-        let dw = self.width() as isize;
-        let dh = self.height() as isize;
-        if r >= dw {
-            width = dw - dx;
-        }
-        if b >= dh {
-            height = dh - dy;
-        }
-        if width <= 0 || height <= 0 {
-            return;
-        }
-
-        let width = width as usize;
-        let height = height as usize;
+        let (dx, dy, sx, sy, width, height) = match clip_blt(origin, rect, src.size(), self.size())
+        {
+            Some(v) => v,
+            None => return,
+        };
 
         let ds = self.stride();
         let ss = src.stride();
-        let mut dest_cursor = dx as usize + dy as usize * ds;
-        let mut src_cursor = sx as usize + sy as usize * ss;
+        let mut dest_cursor = dx + dy * ds;
+        let mut src_cursor = sx + sy * ss;
         let dest_fb = self.slice_mut();
         let src_fb = src.slice();
 
@@ -417,52 +490,16 @@ pub trait BltConverter<T: ColorTrait>: MutableRasterImage {
         U: RasterImage<ColorType = T>,
         F: FnMut(T) -> Option<Self::ColorType>,
     {
-        let mut dx = origin.x;
-        let mut dy = origin.y;
-        let mut sx = rect.origin.x;
-        let mut sy = rect.origin.y;
-        let mut width = rect.width();
-        let mut height = rect.height();
-
-        if dx < 0 {
-            sx -= dx;
-            width += dx;
-            dx = 0;
-        }
-        if dy < 0 {
-            sy -= dy;
-            height += dy;
-            dy = 0;
-        }
-        let sw = src.width() as isize;
-        let sh = src.height() as isize;
-        if width > sx + sw {
-            width = sw - sx;
-        }
-        if height > sy + sh {
-            height = sh - sy;
-        }
-        let r = dx + width;
-        let b = dy + height;
-        let dw = self.width() as isize;
-        let dh = self.height() as isize;
-        if r >= dw {
-            width = dw - dx;
-        }
-        if b >= dh {
-            height = dh - dy;
-        }
-        if width <= 0 || height <= 0 {
-            return;
-        }
-
-        let width = width as usize;
-        let height = height as usize;
+        let (dx, dy, sx, sy, width, height) = match clip_blt(origin, rect, src.size(), self.size())
+        {
+            Some(v) => v,
+            None => return,
+        };
 
         let ds = self.stride();
         let ss = src.stride();
-        let mut dest_cursor = dx as usize + dy as usize * ds;
-        let mut src_cursor = sx as usize + sy as usize * ss;
+        let mut dest_cursor = dx + dy * ds;
+        let mut src_cursor = sx + sy * ss;
         let dest_fb = self.slice_mut();
         let src_fb = src.slice();
 
@@ -547,7 +584,7 @@ impl<'a> ConstBitmap8<'a> {
             width: size.width() as usize,
             height: size.height() as usize,
             stride: size.width() as usize,
-            slice: unsafe { transmute(bytes) },
+            slice: unsafe { cast_slice(bytes) },
         }
     }
 
@@ -560,6 +597,30 @@ impl<'a> ConstBitmap8<'a> {
             slice: self.slice(),
         }
     }
+
+    /// Returns a true color copy of the bitmap, converted with `palette`.
+    pub fn to_argb(&self, palette: &[u32; 256]) -> BoxedBitmap32<'static> {
+        let width = self.width();
+        let mut vec = Vec::with_capacity(width * self.height());
+        for row in self
+            .slice
+            .chunks(usize::max(self.stride, 1))
+            .take(self.height())
+        {
+            vec.extend(
+                row[..width]
+                    .iter()
+                    .map(|c| TrueColor::from_argb(palette[c.0 as usize])),
+            );
+        }
+        BoxedBitmap32::from_vec(vec, self.size())
+    }
+
+    /// Returns a true color copy of the bitmap in the default palette.
+    #[inline]
+    pub fn to_argb_default(&self) -> BoxedBitmap32<'static> {
+        self.to_argb(&IndexedColor::COLOR_PALETTE)
+    }
 }
 
 impl<'a> AsRef<ConstBitmap8<'a>> for ConstBitmap8<'a> {
@@ -621,7 +682,7 @@ impl<'a> Bitmap8<'a> {
             width: size.width() as usize,
             height: size.height() as usize,
             stride: size.width() as usize,
-            slice: unsafe { transmute(bytes) },
+            slice: UnsafeCell::new(unsafe { cast_slice_mut(bytes) }),
         }
     }
 
@@ -679,52 +740,16 @@ impl<'a> Bitmap8<'a> {
         color_key: Option<<Self as Drawable>::ColorType>,
     ) {
         let src = src.as_ref();
-        let mut dx = origin.x;
-        let mut dy = origin.y;
-        let mut sx = rect.origin.x;
-        let mut sy = rect.origin.y;
-        let mut width = rect.width();
-        let mut height = rect.height();
-
-        if dx < 0 {
-            sx -= dx;
-            width += dx;
-            dx = 0;
-        }
-        if dy < 0 {
-            sy -= dy;
-            height += dy;
-            dy = 0;
-        }
-        let sw = src.width() as isize;
-        let sh = src.height() as isize;
-        if width > sx + sw {
-            width = sw - sx;
-        }
-        if height > sy + sh {
-            height = sh - sy;
-        }
-        let r = dx + width;
-        let b = dy + height;
-        let dw = self.width() as isize;
-        let dh = self.height() as isize;
-        if r >= dw {
-            width = dw - dx;
-        }
-        if b >= dh {
-            height = dh - dy;
-        }
-        if width <= 0 || height <= 0 {
-            return;
-        }
-
-        let width = width as usize;
-        let height = height as usize;
+        let (dx, dy, sx, sy, width, height) = match clip_blt(origin, rect, src.size(), self.size())
+        {
+            Some(v) => v,
+            None => return,
+        };
 
         let ds = self.stride();
         let ss = src.stride();
-        let mut dest_cursor = dx as usize + dy as usize * ds;
-        let mut src_cursor = sx as usize + sy as usize * ss;
+        let mut dest_cursor = dx + dy * ds;
+        let mut src_cursor = sx + sy * ss;
         let dest_fb = self.slice_mut();
         let src_fb = src.slice();
 
@@ -739,6 +764,12 @@ impl<'a> Bitmap8<'a> {
                 dest_cursor += ds;
                 src_cursor += ss;
             }
+        } else if is_overlapped(dest_fb, src_fb) {
+            unsafe {
+                let ptr_d = dest_fb.as_mut_ptr().add(dest_cursor);
+                let ptr_s = src_fb.as_ptr().add(src_cursor);
+                memmove_rows(ptr_d, ds, ptr_s, ss, width, height);
+            }
         } else {
             if ds == width && ss == width {
                 memcpy_colors8(dest_fb, dest_cursor, src_fb, src_cursor, width * height);
@@ -752,6 +783,38 @@ impl<'a> Bitmap8<'a> {
         }
     }
 
+    /// Copies the source pixels only on the even cells of a checkerboard in the destination,
+    /// which gives a cheap 50% translucency.
+    pub fn blt_stipple<'b, T: AsRef<ConstBitmap8<'b>>>(
+        &mut self,
+        src: &'b T,
+        origin: Point,
+        rect: Rect,
+    ) {
+        let src = src.as_ref();
+        let (dx, dy, sx, sy, width, height) = match clip_blt(origin, rect, src.size(), self.size())
+        {
+            Some(v) => v,
+            None => return,
+        };
+
+        let ds = self.stride();
+        let ss = src.stride();
+        let mut dest_cursor = dx + dy * ds;
+        let mut src_cursor = sx + sy * ss;
+        let dest_fb = self.slice_mut();
+        let src_fb = src.slice();
+
+        for y in 0..height {
+            let phase = (dx + dy + y) & 1;
+            for i in (phase..width).step_by(2) {
+                dest_fb[dest_cursor + i] = src_fb[src_cursor + i];
+            }
+            dest_cursor += ds;
+            src_cursor += ss;
+        }
+    }
+
     pub fn blt32<'b, T: AsRef<ConstBitmap32<'b>>>(
         &mut self,
         src: &'b T,
@@ -762,6 +825,51 @@ impl<'a> Bitmap8<'a> {
             IndexedColor::from_rgb(c.rgb())
         });
     }
+
+    /// Approximates alpha blending of a true color source with a stipple pattern.
+    ///
+    /// Mostly opaque pixels are copied, mostly transparent ones are skipped,
+    /// and the others are written where `(x + y)` of the destination is even.
+    pub fn blt_blend32<'b, T: AsRef<ConstBitmap32<'b>>>(
+        &mut self,
+        src: &'b T,
+        origin: Point,
+        rect: Rect,
+    ) {
+        let src = src.as_ref();
+        let rect = match rect.intersection(src.size().into()) {
+            Some(v) => v,
+            None => return,
+        };
+        let dest = Rect {
+            origin,
+            size: rect.size(),
+        };
+        let dest = match dest.intersection(self.size().into()) {
+            Some(v) => v,
+            None => return,
+        };
+
+        let ds = self.stride();
+        let ss = src.stride();
+        let src_fb = src.slice();
+        let dest_fb = self.slice_mut();
+        for y in dest.y()..dest.y() + dest.height() {
+            let sy = (rect.y() + y - origin.y) as usize;
+            for x in dest.x()..dest.x() + dest.width() {
+                let sx = (rect.x() + x - origin.x) as usize;
+                let c = src_fb[sx + sy * ss];
+                let draw = match c.opacity() {
+                    0..=0x3F => false,
+                    0xC0..=0xFF => true,
+                    _ => (x + y) & 1 == 0,
+                };
+                if draw {
+                    dest_fb[x as usize + y as usize * ds] = IndexedColor::from_rgb(c.rgb());
+                }
+            }
+        }
+    }
 }
 
 impl Bitmap8<'_> {
@@ -812,15 +920,15 @@ impl BasicDrawing for Bitmap8<'_> {
         let mut dy = rect.y();
 
         if dx < 0 {
-            width += dx;
+            width = width.saturating_add(dx);
             dx = 0;
         }
         if dy < 0 {
-            height += dy;
+            height = height.saturating_add(dy);
             dy = 0;
         }
-        let r = dx + width;
-        let b = dy + height;
+        let r = dx.saturating_add(width);
+        let b = dy.saturating_add(height);
         if r >= self.width as isize {
             width = self.width as isize - dx;
         }
@@ -835,8 +943,10 @@ impl BasicDrawing for Bitmap8<'_> {
         let height = height as usize;
         let stride = self.stride;
         let mut cursor = dx as usize + dy as usize * stride;
-        if stride == width {
-            memset_colors8(self.slice_mut(), cursor, width * height, color);
+        if let Some(len) = contiguous_len(width, height, stride) {
+            #[cfg(test)]
+            probe::hit(&probe::CONTIGUOUS_FILLS);
+            memset_colors8(self.slice_mut(), cursor, len, color);
         } else {
             for _ in 0..height {
                 memset_colors8(self.slice_mut(), cursor, width, color);
@@ -854,10 +964,10 @@ impl BasicDrawing for Bitmap8<'_> {
             return;
         }
         if dx < 0 {
-            w += dx;
+            w = w.saturating_add(dx);
             dx = 0;
         }
-        let r = dx + w;
+        let r = dx.saturating_add(w);
         if r >= (self.width as isize) {
             w = (self.width as isize) - dx;
         }
@@ -878,10 +988,10 @@ impl BasicDrawing for Bitmap8<'_> {
             return;
         }
         if dy < 0 {
-            h += dy;
+            h = h.saturating_add(dy);
             dy = 0;
         }
-        let b = dy + h;
+        let b = dy.saturating_add(h);
         if b >= (self.height as isize) {
             h = (self.height as isize) - dy;
         }
@@ -940,8 +1050,10 @@ impl MutableRasterImage for BoxedBitmap8<'_> {
 }
 
 impl<'a> BoxedBitmap8<'a> {
+    /// Allocates a bitmap filled with `bg_color`; zero or negative dimensions are clamped to 1.
     #[inline]
     pub fn new(size: Size, bg_color: IndexedColor) -> BoxedBitmap8<'a> {
+        let size = non_empty_size(size);
         let len = size.width() as usize * size.height() as usize;
         let mut vec = Vec::with_capacity(len);
         vec.resize_with(len, || bg_color);
@@ -975,6 +1087,19 @@ impl<'a> AsRef<ConstBitmap8<'a>> for BoxedBitmap8<'a> {
     }
 }
 
+/// Returns the number of pixels to fill at once if the rows of the area are adjacent in memory.
+///
+/// Rows of a view are not adjacent even if it spans the full width,
+/// because the pixels of the parent bitmap lie between them.
+#[inline]
+const fn contiguous_len(width: usize, height: usize, stride: usize) -> Option<usize> {
+    if height == 1 || width == stride {
+        Some(width * height)
+    } else {
+        None
+    }
+}
+
 /// Fast fill
 #[inline]
 fn memset_colors8(slice: &mut [IndexedColor], cursor: usize, size: usize, color: IndexedColor) {
@@ -985,7 +1110,7 @@ fn memset_colors8(slice: &mut [IndexedColor], cursor: usize, size: usize, color:
         let mut ptr: *mut u8 = transmute(slice);
         let mut remain = size;
 
-        let prologue = usize::min(ptr as usize & 0x0F, remain);
+        let prologue = usize::min((16 - (ptr as usize & 0x0F)) & 0x0F, remain);
         remain -= prologue;
         for _ in 0..prologue {
             ptr.write_volatile(color);
@@ -1129,7 +1254,7 @@ impl<'a> ConstBitmap32<'a> {
             width: size.width() as usize,
             height: size.height() as usize,
             stride: size.width() as usize,
-            slice: unsafe { transmute(bytes) },
+            slice: unsafe { cast_slice(bytes) },
         }
     }
 
@@ -1142,6 +1267,104 @@ impl<'a> ConstBitmap32<'a> {
             slice: self.slice(),
         }
     }
+
+    /// Returns a read-only view of the area, sharing the pixels with this bitmap.
+    ///
+    /// Returns `None` if the rect is empty or not entirely inside the bitmap.
+    pub fn sub(&self, rect: Rect) -> Option<ConstBitmap32<'a>> {
+        let coords = Coordinates::try_from(rect).ok()?;
+        if coords.left < 0
+            || coords.top < 0
+            || coords.right > self.width() as isize
+            || coords.bottom > self.height() as isize
+        {
+            return None;
+        }
+
+        let width = rect.width() as usize;
+        let height = rect.height() as usize;
+        let offset = rect.x() as usize + rect.y() as usize * self.stride;
+        let len = (height - 1) * self.stride + width;
+        let slice = self.slice.get(offset..offset + len)?;
+        Some(ConstBitmap32 {
+            width,
+            height,
+            stride: self.stride,
+            slice,
+        })
+    }
+
+    #[inline]
+    fn rows(&self) -> impl Iterator<Item = &[TrueColor]> {
+        let width = self.width();
+        self.slice
+            .chunks(usize::max(self.stride, 1))
+            .take(self.height())
+            .map(move |row| &row[..width])
+    }
+
+    /// Returns the alpha-weighted average color of all pixels.
+    pub fn average_color(&self) -> TrueColor {
+        let count = (self.width() * self.height()) as u64;
+        if count == 0 {
+            return TrueColor::TRANSPARENT;
+        }
+        let mut acc_a = 0u64;
+        let mut acc_r = 0u64;
+        let mut acc_g = 0u64;
+        let mut acc_b = 0u64;
+        for row in self.rows() {
+            for pixel in row {
+                let cc = pixel.components();
+                let a = cc.a as u64;
+                acc_a += a;
+                acc_r += cc.r as u64 * a;
+                acc_g += cc.g as u64 * a;
+                acc_b += cc.b as u64 * a;
+            }
+        }
+        if acc_a == 0 {
+            return TrueColor::TRANSPARENT;
+        }
+        let half = acc_a / 2;
+        ColorComponents {
+            a: ((acc_a + count / 2) / count) as u8,
+            r: ((acc_r + half) / acc_a) as u8,
+            g: ((acc_g + half) / acc_a) as u8,
+            b: ((acc_b + half) / acc_a) as u8,
+        }
+        .into()
+    }
+
+    /// Returns the number of pixels for each brightness.
+    pub fn histogram(&self) -> [u32; 256] {
+        let mut result = [0u32; 256];
+        for row in self.rows() {
+            for pixel in row {
+                result[pixel.brightness() as usize] += 1;
+            }
+        }
+        result
+    }
+
+    /// Returns whether both bitmaps have the same size and the same pixels, ignoring the stride.
+    pub fn pixels_eq(&self, other: &ConstBitmap32) -> bool {
+        self.size() == other.size() && self.rows().eq(other.rows())
+    }
+
+    /// Returns the 64-bit FNV-1a hash of the size and pixels, ignoring the stride.
+    pub fn hash_fnv(&self) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+        let size = [self.width() as u32, self.height() as u32];
+        let bytes = size
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .chain(self.rows().flatten().flat_map(|v| v.to_le_bytes()));
+        bytes.fold(OFFSET_BASIS, |acc, byte| {
+            (acc ^ byte as u64).wrapping_mul(PRIME)
+        })
+    }
 }
 
 impl<'a> AsRef<ConstBitmap32<'a>> for ConstBitmap32<'a> {
@@ -1203,7 +1426,7 @@ impl<'a> Bitmap32<'a> {
             width: size.width() as usize,
             height: size.height() as usize,
             stride: size.width() as usize,
-            slice: unsafe { transmute(bytes) },
+            slice: UnsafeCell::new(unsafe { cast_slice_mut(bytes) }),
         }
     }
 
@@ -1237,15 +1460,15 @@ impl Bitmap32<'_> {
         let mut dy = rect.origin.y;
 
         if dx < 0 {
-            width += dx;
+            width = width.saturating_add(dx);
             dx = 0;
         }
         if dy < 0 {
-            height += dy;
+            height = height.saturating_add(dy);
             dy = 0;
         }
-        let r = dx + width;
-        let b = dy + height;
+        let r = dx.saturating_add(width);
+        let b = dy.saturating_add(height);
         if r >= self.size().width {
             width = self.size().width - dx;
         }
@@ -1310,15 +1533,15 @@ impl BasicDrawing for Bitmap32<'_> {
         let mut dy = rect.y();
 
         if dx < 0 {
-            width += dx;
+            width = width.saturating_add(dx);
             dx = 0;
         }
         if dy < 0 {
-            height += dy;
+            height = height.saturating_add(dy);
             dy = 0;
         }
-        let r = dx + width;
-        let b = dy + height;
+        let r = dx.saturating_add(width);
+        let b = dy.saturating_add(height);
         if r >= self.width as isize {
             width = self.width as isize - dx;
         }
@@ -1333,8 +1556,10 @@ impl BasicDrawing for Bitmap32<'_> {
         let height = height as usize;
         let stride = self.stride;
         let mut cursor = dx as usize + dy as usize * stride;
-        if stride == width {
-            memset_colors32(self.slice_mut(), cursor, width * height, color);
+        if let Some(len) = contiguous_len(width, height, stride) {
+            #[cfg(test)]
+            probe::hit(&probe::CONTIGUOUS_FILLS);
+            memset_colors32(self.slice_mut(), cursor, len, color);
         } else {
             for _ in 0..height {
                 memset_colors32(self.slice_mut(), cursor, width, color);
@@ -1352,10 +1577,10 @@ impl BasicDrawing for Bitmap32<'_> {
             return;
         }
         if dx < 0 {
-            w += dx;
+            w = w.saturating_add(dx);
             dx = 0;
         }
-        let r = dx + w;
+        let r = dx.saturating_add(w);
         if r >= (self.width as isize) {
             w = (self.width as isize) - dx;
         }
@@ -1376,10 +1601,10 @@ impl BasicDrawing for Bitmap32<'_> {
             return;
         }
         if dy < 0 {
-            h += dy;
+            h = h.saturating_add(dy);
             dy = 0;
         }
-        let b = dy + h;
+        let b = dy.saturating_add(h);
         if b >= (self.height as isize) {
             h = (self.height as isize) - dy;
         }
@@ -1412,6 +1637,15 @@ pub enum BltMode {
     Copy,
 }
 
+/// Clockwise rotation in steps of 90 degrees
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    Deg0,
+    Deg90,
+    Deg180,
+    Deg270,
+}
+
 impl<'a> Bitmap32<'a> {
     pub fn blt<'b, T: AsRef<ConstBitmap32<'b>>>(&mut self, src: &'b T, origin: Point, rect: Rect) {
         self.blt_main(src, origin, rect, BltMode::Copy);
@@ -1435,58 +1669,28 @@ impl<'a> Bitmap32<'a> {
         mode: BltMode,
     ) {
         let src = src.as_ref();
-        let mut dx = origin.x;
-        let mut dy = origin.y;
-        let mut sx = rect.origin.x;
-        let mut sy = rect.origin.y;
-        let mut width = rect.width();
-        let mut height = rect.height();
-
-        if dx < 0 {
-            sx -= dx;
-            width += dx;
-            dx = 0;
-        }
-        if dy < 0 {
-            sy -= dy;
-            height += dy;
-            dy = 0;
-        }
-        let sw = src.width() as isize;
-        let sh = src.height() as isize;
-        if width > sx + sw {
-            width = sw - sx;
-        }
-        if height > sy + sh {
-            height = sh - sy;
-        }
-        let r = dx + width;
-        let b = dy + height;
-        let dw = self.width() as isize;
-        let dh = self.height() as isize;
-        if r >= dw {
-            width = dw - dx;
-        }
-        if b >= dh {
-            height = dh - dy;
-        }
-        if width <= 0 || height <= 0 {
-            return;
-        }
-
-        let width = width as usize;
-        let height = height as usize;
+        let (dx, dy, sx, sy, width, height) = match clip_blt(origin, rect, src.size(), self.size())
+        {
+            Some(v) => v,
+            None => return,
+        };
 
         let ds = self.stride();
         let ss = src.stride();
-        let mut dest_cursor = dx as usize + dy as usize * ds;
-        let mut src_cursor = sx as usize + sy as usize * ss;
+        let mut dest_cursor = dx + dy * ds;
+        let mut src_cursor = sx + sy * ss;
         let dest_fb = self.slice_mut();
         let src_fb = src.slice();
 
         match mode {
             BltMode::Copy => {
-                if ds == width && ss == width {
+                if is_overlapped(dest_fb, src_fb) {
+                    unsafe {
+                        let ptr_d = dest_fb.as_mut_ptr().add(dest_cursor);
+                        let ptr_s = src_fb.as_ptr().add(src_cursor);
+                        memmove_rows(ptr_d, ds, ptr_s, ss, width, height);
+                    }
+                } else if ds == width && ss == width {
                     memcpy_colors32(dest_fb, dest_cursor, src_fb, src_cursor, width * height);
                 } else {
                     for _ in 0..height {
@@ -1506,6 +1710,51 @@ impl<'a> Bitmap32<'a> {
         }
     }
 
+    /// Copies `rect` of `src` rotated clockwise, with its top-left corner placed at `origin`.
+    pub fn blt_rotated<'b, T: AsRef<ConstBitmap32<'b>>>(
+        &mut self,
+        src: &'b T,
+        origin: Point,
+        rect: Rect,
+        rotation: Rotation,
+    ) {
+        let src = src.as_ref();
+        let rect = match rect.intersection(src.size().into()) {
+            Some(v) => v,
+            None => return,
+        };
+        let sw = rect.width();
+        let sh = rect.height();
+        let (rw, rh) = match rotation {
+            Rotation::Deg0 | Rotation::Deg180 => (sw, sh),
+            Rotation::Deg90 | Rotation::Deg270 => (sh, sw),
+        };
+        let dest = match Rect::new(origin.x, origin.y, rw, rh).intersection(self.size().into()) {
+            Some(v) => v,
+            None => return,
+        };
+
+        let ds = self.stride();
+        let ss = src.stride();
+        let src_fb = src.slice();
+        let dest_fb = self.slice_mut();
+        for y in dest.y()..dest.y() + dest.height() {
+            let v = y - origin.y;
+            for x in dest.x()..dest.x() + dest.width() {
+                let u = x - origin.x;
+                let (sx, sy) = match rotation {
+                    Rotation::Deg0 => (u, v),
+                    Rotation::Deg90 => (v, sh - 1 - u),
+                    Rotation::Deg180 => (sw - 1 - u, sh - 1 - v),
+                    Rotation::Deg270 => (sw - 1 - v, u),
+                };
+                let sx = (rect.x() + sx) as usize;
+                let sy = (rect.y() + sy) as usize;
+                dest_fb[x as usize + y as usize * ds] = src_fb[sx + sy * ss];
+            }
+        }
+    }
+
     pub fn blt8<'b, T: AsRef<ConstBitmap8<'b>>>(
         &mut self,
         src: &'b T,
@@ -1599,8 +1848,10 @@ impl MutableRasterImage for BoxedBitmap32<'_> {
 }
 
 impl<'a> BoxedBitmap32<'a> {
+    /// Allocates a bitmap filled with `bg_color`; zero or negative dimensions are clamped to 1.
     #[inline]
     pub fn new(size: Size, bg_color: TrueColor) -> BoxedBitmap32<'a> {
+        let size = non_empty_size(size);
         let len = size.width() as usize * size.height() as usize;
         let mut vec = Vec::with_capacity(len);
         vec.resize_with(len, || bg_color);
@@ -1624,6 +1875,51 @@ impl<'a> BoxedBitmap32<'a> {
         Self { inner, slice }
     }
 
+    /// Reallocates the bitmap, keeping the overlapping top-left pixels and filling the rest with `bg_color`.
+    ///
+    /// The new size is clamped to at least 1x1, the same as `new`.
+    pub fn resize(&mut self, new_size: Size, bg_color: TrueColor) {
+        let new_size = non_empty_size(new_size);
+        let new_width = new_size.width() as usize;
+        let len = new_width * new_size.height() as usize;
+        let mut vec = Vec::with_capacity(len);
+        vec.resize_with(len, || bg_color);
+
+        let width = usize::min(self.width(), new_width);
+        let height = usize::min(self.height(), new_size.height() as usize);
+        let stride = self.stride();
+        let slice = self.slice();
+        for y in 0..height {
+            vec[y * new_width..y * new_width + width]
+                .copy_from_slice(&slice[y * stride..y * stride + width]);
+        }
+
+        *self = Self::from_vec(vec, new_size);
+    }
+
+    /// Blurs the bitmap with a separable box blur of `2 * radius + 1` pixels,
+    /// repeating the pixels at the edges.
+    pub fn box_blur(&mut self, radius: usize) {
+        let width = self.width();
+        let height = self.height();
+        if radius == 0 || self.is_empty() {
+            return;
+        }
+        let stride = self.stride();
+        let len = usize::max(width, height);
+        let radius = usize::min(radius, len);
+        let mut scratch = Vec::with_capacity(len);
+        scratch.resize(len, TrueColor::TRANSPARENT);
+
+        let slice = self.slice_mut();
+        for y in 0..height {
+            box_blur_line(slice, y * stride, 1, width, radius, &mut scratch);
+        }
+        for x in 0..width {
+            box_blur_line(slice, x, stride, height, radius, &mut scratch);
+        }
+    }
+
     #[inline]
     pub fn inner(&'a mut self) -> &mut Bitmap32<'a> {
         &mut self.inner
@@ -1655,7 +1951,7 @@ fn memset_colors32(slice: &mut [TrueColor], cursor: usize, count: usize, color:
         let mut ptr: *mut u32 = core::mem::transmute(slice);
         let mut remain = count;
 
-        let prologue = usize::min(ptr as usize & 0x0F / 4, remain);
+        let prologue = usize::min(((16 - (ptr as usize & 0x0F)) & 0x0F) / 4, remain);
         remain -= prologue;
         for _ in 0..prologue {
             ptr.write_volatile(color32);
@@ -1743,6 +2039,37 @@ fn memcpy_colors32(
     }
 }
 
+/// Returns whether two slices share any memory
+#[inline]
+fn is_overlapped<T>(dest: &[T], src: &[T]) -> bool {
+    let dest = dest.as_ptr_range();
+    let src = src.as_ptr_range();
+    dest.start < src.end && src.start < dest.end
+}
+
+/// Copy rows between possibly overlapping buffers (memmove semantics)
+///
+/// SAFETY: The whole area must be within the range of each buffer.
+#[inline]
+unsafe fn memmove_rows<T: Copy>(
+    ptr_d: *mut T,
+    dest_stride: usize,
+    ptr_s: *const T,
+    src_stride: usize,
+    width: usize,
+    height: usize,
+) {
+    if (ptr_d as usize) > (ptr_s as usize) {
+        for y in (0..height).rev() {
+            core::ptr::copy(ptr_s.add(y * src_stride), ptr_d.add(y * dest_stride), width);
+        }
+    } else {
+        for y in 0..height {
+            core::ptr::copy(ptr_s.add(y * src_stride), ptr_d.add(y * dest_stride), width);
+        }
+    }
+}
+
 #[inline]
 fn blend_line32(
     dest: &mut [TrueColor],
@@ -1758,9 +2085,154 @@ fn blend_line32(
     }
 }
 
-pub enum ConstBitmap<'a> {
-    Indexed(&'a ConstBitmap8<'a>),
-    Argb32(&'a ConstBitmap32<'a>),
+/// A 1bpp mask whose rows are packed MSB first, the same layout as the font data.
+#[repr(C)]
+pub struct ConstBitmap1<'a> {
+    width: usize,
+    height: usize,
+    stride: usize,
+    slice: &'a [u8],
+}
+
+impl<'a> ConstBitmap1<'a> {
+    #[inline]
+    pub const fn from_slice(slice: &'a [u8], size: Size, stride: usize) -> Self {
+        Self {
+            width: size.width() as usize,
+            height: size.height() as usize,
+            stride,
+            slice,
+        }
+    }
+
+    #[inline]
+    pub const fn from_bytes(bytes: &'a [u8], size: Size) -> Self {
+        Self::from_slice(bytes, size, (size.width() as usize).div_ceil(8))
+    }
+
+    #[inline]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    #[inline]
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    #[inline]
+    pub const fn stride(&self) -> usize {
+        self.stride
+    }
+
+    #[inline]
+    pub const fn size(&self) -> Size {
+        Size::new(self.width as isize, self.height as isize)
+    }
+
+    /// Returns whether the bit at `point` is set; out of range bits are clear.
+    #[inline]
+    pub fn get_bit(&self, point: Point) -> bool {
+        if point.is_within(Rect::from(self.size())) {
+            let x = point.x as usize;
+            self.slice
+                .get(point.y as usize * self.stride + x / 8)
+                .map(|v| (v & (0x80u8 >> (x & 7))) != 0)
+                .unwrap_or(false)
+        } else {
+            false
+        }
+    }
+
+    /// Expands the mask at `origin`, drawing set bits in `fg` and clear bits in `bg` if any.
+    #[inline]
+    pub fn blt_to<T: SetPixel>(
+        &self,
+        to: &mut T,
+        origin: Point,
+        fg: T::ColorType,
+        bg: Option<T::ColorType>,
+    ) {
+        self.blt_scaled(to, origin, fg, bg, 1)
+    }
+
+    /// The same as `blt_to`, but each bit is drawn as a `scale` x `scale` block.
+    pub fn blt_scaled<T: SetPixel>(
+        &self,
+        to: &mut T,
+        origin: Point,
+        fg: T::ColorType,
+        bg: Option<T::ColorType>,
+        scale: usize,
+    ) {
+        let scale = scale as isize;
+        let rect = Rect {
+            origin,
+            size: Size::new(
+                (self.width as isize).saturating_mul(scale),
+                (self.height as isize).saturating_mul(scale),
+            ),
+        };
+        let clipped = match rect.intersection(to.bounds()) {
+            Some(v) => v,
+            None => return,
+        };
+        for y in clipped.y()..clipped.y() + clipped.height() {
+            for x in clipped.x()..clipped.x() + clipped.width() {
+                let bit = Point::new((x - origin.x) / scale, (y - origin.y) / scale);
+                let color = if self.get_bit(bit) { Some(fg) } else { bg };
+                if let Some(color) = color {
+                    unsafe {
+                        to.set_pixel_unchecked(Point::new(x, y), color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Blurs `count` pixels from `base`, `step` pixels apart, with a running sum of each channel.
+fn box_blur_line(
+    slice: &mut [TrueColor],
+    base: usize,
+    step: usize,
+    count: usize,
+    radius: usize,
+    scratch: &mut [TrueColor],
+) {
+    let line = &mut scratch[..count];
+    for (index, pixel) in line.iter_mut().enumerate() {
+        *pixel = slice[base + index * step];
+    }
+    let at = |index: isize| line[index.clamp(0, count as isize - 1) as usize].to_le_bytes();
+    let radius = radius as isize;
+    let window = 2 * radius as u32 + 1;
+
+    let mut sum = [0u32; 4];
+    for index in -radius..=radius {
+        for (acc, value) in sum.iter_mut().zip(at(index).iter()) {
+            *acc += *value as u32;
+        }
+    }
+    for index in 0..count {
+        let mut bytes = [0u8; 4];
+        for (byte, acc) in bytes.iter_mut().zip(sum.iter()) {
+            *byte = ((*acc + window / 2) / window) as u8;
+        }
+        slice[base + index * step] = TrueColor::from_le_bytes(bytes);
+
+        let index = index as isize;
+        let leaving = at(index - radius);
+        let entering = at(index + radius + 1);
+        for c in 0..4 {
+            sum[c] = sum[c] + entering[c] as u32 - leaving[c] as u32;
+        }
+    }
+}
+
+pub enum ConstBitmap<'a> {
+    Indexed(&'a ConstBitmap8<'a>),
+    Argb32(&'a ConstBitmap32<'a>),
 }
 
 impl Drawable for ConstBitmap<'_> {
@@ -1783,6 +2255,16 @@ impl Drawable for ConstBitmap<'_> {
     }
 }
 
+impl GetPixel for ConstBitmap<'_> {
+    #[inline]
+    unsafe fn get_pixel_unchecked(&self, point: Point) -> Self::ColorType {
+        match self {
+            Self::Indexed(v) => v.get_pixel_unchecked(point).into(),
+            Self::Argb32(v) => v.get_pixel_unchecked(point).into(),
+        }
+    }
+}
+
 impl<'a> From<&'a ConstBitmap8<'a>> for ConstBitmap<'a> {
     #[inline]
     fn from(val: &'a ConstBitmap8<'a>) -> ConstBitmap<'a> {
@@ -1855,6 +2337,8 @@ impl GetPixel for Bitmap<'_> {
 impl SetPixel for Bitmap<'_> {
     #[inline]
     unsafe fn set_pixel_unchecked(&mut self, point: Point, pixel: Self::ColorType) {
+        #[cfg(test)]
+        probe::hit(&probe::ENUM_PRIMITIVES);
         match self {
             Bitmap::Indexed(ref mut v) => v.set_pixel_unchecked(point, pixel.into()),
             Bitmap::Argb32(ref mut v) => v.set_pixel_unchecked(point, pixel.into()),
@@ -1875,6 +2359,8 @@ impl RasterFontWriter for Bitmap<'_> {
 impl BasicDrawing for Bitmap<'_> {
     #[inline]
     fn fill_rect(&mut self, rect: Rect, color: Self::ColorType) {
+        #[cfg(test)]
+        probe::hit(&probe::ENUM_PRIMITIVES);
         match self {
             Bitmap::Indexed(ref mut v) => v.fill_rect(rect, color.into()),
             Bitmap::Argb32(ref mut v) => v.fill_rect(rect, color.into()),
@@ -1883,6 +2369,8 @@ impl BasicDrawing for Bitmap<'_> {
 
     #[inline]
     fn draw_hline(&mut self, origin: Point, width: isize, color: Self::ColorType) {
+        #[cfg(test)]
+        probe::hit(&probe::ENUM_PRIMITIVES);
         match self {
             Bitmap::Indexed(ref mut v) => v.draw_hline(origin, width, color.into()),
             Bitmap::Argb32(ref mut v) => v.draw_hline(origin, width, color.into()),
@@ -1891,11 +2379,45 @@ impl BasicDrawing for Bitmap<'_> {
 
     #[inline]
     fn draw_vline(&mut self, origin: Point, height: isize, color: Self::ColorType) {
+        #[cfg(test)]
+        probe::hit(&probe::ENUM_PRIMITIVES);
         match self {
             Bitmap::Indexed(ref mut v) => v.draw_vline(origin, height, color.into()),
             Bitmap::Argb32(ref mut v) => v.draw_vline(origin, height, color.into()),
         }
     }
+
+    #[inline]
+    fn draw_circle(&mut self, origin: Point, radius: isize, color: Self::ColorType) {
+        match self {
+            Bitmap::Indexed(ref mut v) => v.draw_circle(origin, radius, color.into()),
+            Bitmap::Argb32(ref mut v) => v.draw_circle(origin, radius, color.into()),
+        }
+    }
+
+    #[inline]
+    fn fill_circle(&mut self, origin: Point, radius: isize, color: Self::ColorType) {
+        match self {
+            Bitmap::Indexed(ref mut v) => v.fill_circle(origin, radius, color.into()),
+            Bitmap::Argb32(ref mut v) => v.fill_circle(origin, radius, color.into()),
+        }
+    }
+
+    #[inline]
+    fn fill_round_rect(&mut self, rect: Rect, radius: isize, color: Self::ColorType) {
+        match self {
+            Bitmap::Indexed(ref mut v) => v.fill_round_rect(rect, radius, color.into()),
+            Bitmap::Argb32(ref mut v) => v.fill_round_rect(rect, radius, color.into()),
+        }
+    }
+
+    #[inline]
+    fn draw_round_rect(&mut self, rect: Rect, radius: isize, color: Self::ColorType) {
+        match self {
+            Bitmap::Indexed(ref mut v) => v.draw_round_rect(rect, radius, color.into()),
+            Bitmap::Argb32(ref mut v) => v.draw_round_rect(rect, radius, color.into()),
+        }
+    }
 }
 
 impl Bitmap<'_> {
@@ -1917,6 +2439,40 @@ impl Bitmap<'_> {
 }
 
 impl<'a> Bitmap<'a> {
+    /// Copies `src_rect` of the source to `dest_origin` of this bitmap,
+    /// converting the colors if the formats differ.
+    #[inline]
+    pub fn blt_rect<'b, T: AsRef<ConstBitmap<'b>>>(
+        &mut self,
+        src: &'b T,
+        dest_origin: Point,
+        src_rect: Rect,
+    ) {
+        self.blt(src.as_ref(), dest_origin, src_rect)
+    }
+
+    /// Alpha-blends `rect` of the source, or stipples it on indexed bitmaps.
+    pub fn blt_blend<'b, T: AsRef<ConstBitmap<'b>>>(
+        &mut self,
+        src: &'b T,
+        origin: Point,
+        rect: Rect,
+    ) {
+        let src = src.as_ref();
+        match self {
+            Bitmap::Indexed(bitmap) => match src {
+                ConstBitmap::Indexed(src) => bitmap.blt_stipple(src, origin, rect),
+                ConstBitmap::Argb32(src) => bitmap.blt_blend32(src, origin, rect),
+            },
+            Bitmap::Argb32(bitmap) => match src {
+                ConstBitmap::Indexed(src) => {
+                    bitmap.blt8(src, origin, rect, &IndexedColor::COLOR_PALETTE)
+                }
+                ConstBitmap::Argb32(src) => bitmap.blt_main(src, origin, rect, BltMode::Blend),
+            },
+        }
+    }
+
     #[inline]
     pub fn blt_transparent<'b, T: AsRef<ConstBitmap<'b>>>(
         &mut self,
@@ -2091,6 +2647,25 @@ impl<'a> BoxedBitmap<'a> {
         }
     }
 
+    /// Returns a copy of `rect` of the source in the same format, clipped to the source.
+    pub fn from_region(src: &ConstBitmap, rect: Rect) -> Option<BoxedBitmap<'a>> {
+        let rect = rect.intersection(src.bounds())?;
+        let origin = Point::new(0, 0);
+        let bitmap = match src {
+            ConstBitmap::Indexed(src) => {
+                let mut bitmap = BoxedBitmap8::new(rect.size(), IndexedColor::BLACK);
+                bitmap.draw(|bitmap| bitmap.blt(src, origin, rect));
+                bitmap.into()
+            }
+            ConstBitmap::Argb32(src) => {
+                let mut bitmap = BoxedBitmap32::new(rect.size(), TrueColor::TRANSPARENT);
+                bitmap.draw(|bitmap| bitmap.blt(src, origin, rect));
+                bitmap.into()
+            }
+        };
+        Some(bitmap)
+    }
+
     pub fn as_bitmap(&'a mut self) -> Bitmap<'a> {
         match self {
             BoxedBitmap::Indexed(ref mut v) => v.inner().into(),
@@ -2282,3 +2857,829 @@ impl ImageLoader {
         Some(BoxedBitmap32::from_vec(vec, Size::new(width as isize, height as isize)).into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn average_color() {
+        let color1 = TrueColor::from_rgb(0x204060);
+        let color2 = TrueColor::from_rgb(0x6080A0);
+        let mut buf = vec![color1; 16];
+        for pixel in buf.iter_mut().skip(8) {
+            *pixel = color2;
+        }
+        let bitmap = ConstBitmap32::from_slice(&buf, Size::new(4, 4), 4);
+        assert_eq!(bitmap.average_color(), TrueColor::from_rgb(0x406080));
+
+        let histogram = bitmap.histogram();
+        assert_eq!(histogram[color1.brightness() as usize], 8);
+        assert_eq!(histogram[color2.brightness() as usize], 8);
+        assert_eq!(histogram.iter().sum::<u32>(), 16);
+
+        // transparent pixels don't affect the color
+        buf[0] = TrueColor::TRANSPARENT;
+        let bitmap = ConstBitmap32::from_slice(&buf, Size::new(1, 1), 4);
+        assert_eq!(bitmap.average_color(), TrueColor::TRANSPARENT);
+
+        let bitmap = ConstBitmap32::from_slice(&[], Size::new(0, 0), 0);
+        assert_eq!(bitmap.average_color(), TrueColor::TRANSPARENT);
+        assert_eq!(bitmap.histogram().iter().sum::<u32>(), 0);
+    }
+
+    #[test]
+    fn blt_rect() {
+        let src_size = Size::new(3, 3);
+        let dest_size = Size::new(4, 4);
+        let src_rect = Rect::new(1, 1, 2, 2);
+        let dest_origin = Point::new(0, 2);
+        let is_copied = |i: usize| i % 4 < 2 && i / 4 >= 2;
+        // the second row and column of the source is red
+        let is_red = |i: usize| i % 3 >= 1 && i / 3 >= 1;
+        // colors of the color cube survive the conversion from 32bpp
+        let red = IndexedColor::from_rgb(0xFF0000);
+        let blue = IndexedColor::from_rgb(0x0000FF);
+
+        let src8 = (0..9)
+            .map(|i| if is_red(i) { red } else { blue })
+            .collect::<Vec<_>>();
+        let src8 = ConstBitmap8::from_slice(&src8, src_size, 3);
+        let src8 = ConstBitmap::from(&src8);
+        let src32 = (0..9)
+            .map(|i| {
+                if is_red(i) {
+                    red.as_true_color()
+                } else {
+                    blue.as_true_color()
+                }
+            })
+            .collect::<Vec<_>>();
+        let src32 = ConstBitmap32::from_slice(&src32, src_size, 3);
+        let src32 = ConstBitmap::from(&src32);
+
+        for src in [&src8, &src32] {
+            let mut buf = vec![IndexedColor::WHITE; 16];
+            let mut bitmap8 = Bitmap8::from_slice(&mut buf, dest_size, 4);
+            Bitmap::from(&mut bitmap8).blt_rect(src, dest_origin, src_rect);
+            for (i, &c) in buf.iter().enumerate() {
+                let expected = if is_copied(i) {
+                    red
+                } else {
+                    IndexedColor::WHITE
+                };
+                assert_eq!(c, expected, "8bpp at {}", i);
+            }
+
+            let white = IndexedColor::WHITE.as_true_color();
+            let mut buf = vec![white; 16];
+            let mut bitmap32 = Bitmap32::from_slice(&mut buf, dest_size, 4);
+            Bitmap::from(&mut bitmap32).blt_rect(src, dest_origin, src_rect);
+            for (i, &c) in buf.iter().enumerate() {
+                let expected = if is_copied(i) {
+                    red.as_true_color()
+                } else {
+                    white
+                };
+                assert_eq!(c, expected, "32bpp at {}", i);
+            }
+        }
+    }
+
+    #[test]
+    fn from_bytes() {
+        let size = Size::new(2, 2);
+        let bytes = [0, 1, 2, 3];
+        let bitmap = ConstBitmap8::from_bytes(&bytes, size);
+        assert_eq!(bitmap.slice().len(), 4);
+        assert_eq!(bitmap.get_pixel(Point::new(1, 1)), Some(IndexedColor(3)));
+
+        let mut bytes = [0u8; 4];
+        {
+            let mut bitmap = Bitmap8::from_bytes(&mut bytes, size);
+            bitmap.set_pixel(Point::new(1, 0), IndexedColor::RED);
+        }
+        assert_eq!(bytes, [0, IndexedColor::RED.0, 0, 0]);
+
+        let words = [0xFF000000, 0xFF0000FF, 0xFF00FF00, 0xFFFF0000];
+        let bitmap = ConstBitmap32::from_bytes(&words, size);
+        assert_eq!(bitmap.slice().len(), 4);
+        assert_eq!(
+            bitmap.get_pixel(Point::new(0, 1)),
+            Some(TrueColor::from_rgb(0x00FF00))
+        );
+
+        let mut words = [0u32; 4];
+        {
+            let mut bitmap = Bitmap32::from_bytes(&mut words, size);
+            bitmap.set_pixel(Point::new(1, 1), TrueColor::WHITE);
+        }
+        assert_eq!(words, [0, 0, 0, 0xFFFFFFFF]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    fn cast_slice_length() {
+        let bytes = [0u8; 3];
+        let _: &[[u8; 2]] = unsafe { cast_slice(&bytes) };
+    }
+
+    #[test]
+    fn draw_font_clipping() {
+        let size = Size::new(8, 4);
+        let glyph = [0b1010_1111, 0b0000_0001];
+        let glyph_size = Size::new(8, 2);
+        let mut buf = vec![IndexedColor::BLACK; 32];
+        {
+            let mut bitmap = Bitmap8::from_slice(&mut buf, size, 8);
+
+            // partly off the left edge
+            bitmap.draw_font(&glyph, glyph_size, Point::new(-2, 0), IndexedColor::WHITE);
+            // partly off the top edge
+            bitmap.draw_font(&glyph, glyph_size, Point::new(0, -1), IndexedColor::RED);
+        }
+
+        let white = IndexedColor::WHITE;
+        let red = IndexedColor::RED;
+        let black = IndexedColor::BLACK;
+        // the right columns of the first glyph and the last row of the second glyph
+        assert_eq!(
+            buf[..8],
+            [white, black, white, white, white, white, black, red]
+        );
+        assert_eq!(
+            buf[8..16],
+            [black, black, black, black, black, white, black, black]
+        );
+        assert!(buf[16..].iter().all(|&v| v == black));
+    }
+
+    #[test]
+    fn bitmap_blt_blend() {
+        let src_buf = [
+            TrueColor::from_argb(0x80FF0000),
+            TrueColor::from_argb(0xFF00FF00),
+            TrueColor::from_argb(0x80FFFFFF),
+            TrueColor::from_argb(0x800000FF),
+        ];
+        let src32 = ConstBitmap32::from_slice(&src_buf, Size::new(2, 2), 2);
+        let src = ConstBitmap::from(&src32);
+        let bg = TrueColor::from_rgb(0x000000);
+
+        let mut buf = [bg; 4];
+        {
+            let mut dest32 = Bitmap32::from_slice(&mut buf, Size::new(2, 2), 2);
+            let mut dest = Bitmap::from(&mut dest32);
+            dest.blt_blend(&src, Point::new(0, 0), Rect::new(0, 0, 2, 2));
+        }
+        assert_eq!(
+            buf,
+            [
+                bg.blend(src_buf[0]),
+                bg.blend(src_buf[1]),
+                bg.blend(src_buf[2]),
+                bg.blend(src_buf[3]),
+            ]
+        );
+        assert_eq!(buf[0].rgb(), 0x800000);
+        assert_eq!(buf[1].rgb(), 0x00FF00);
+        assert_eq!(buf[2].rgb(), 0x808080);
+
+        // stippled on indexed bitmaps, translucent pixels only at even (x + y)
+        let mut buf = [IndexedColor::BLACK; 4];
+        {
+            let mut dest8 = Bitmap8::from_slice(&mut buf, Size::new(2, 2), 2);
+            let mut dest = Bitmap::from(&mut dest8);
+            dest.blt_blend(&src, Point::new(0, 0), Rect::new(0, 0, 2, 2));
+        }
+        assert_eq!(
+            buf,
+            [
+                IndexedColor::from_rgb(0xFF0000),
+                IndexedColor::from_rgb(0x00FF00),
+                IndexedColor::BLACK,
+                IndexedColor::from_rgb(0x0000FF),
+            ]
+        );
+    }
+
+    #[test]
+    fn boxed_bitmap_from_region() {
+        let size = Size::new(8, 6);
+        let mut buf = vec![TrueColor::from_rgb(0x000000); 8 * 6];
+        let mut screen32 = Bitmap32::from_slice(&mut buf, size, 8);
+        let mut screen = Bitmap::from(&mut screen32);
+        let red = AmbiguousColor::from_rgb(0xFF0000);
+        screen.fill_rect(Rect::new(2, 1, 3, 2), red);
+
+        let src = screen.as_ref();
+        let captured = BoxedBitmap::from_region(src, Rect::new(1, 1, 4, 3)).unwrap();
+        assert_eq!(captured.size(), Size::new(4, 3));
+        match captured {
+            BoxedBitmap::Argb32(ref bitmap) => {
+                for y in 0..3 {
+                    for x in 0..4 {
+                        let expected = if x >= 1 && y < 2 {
+                            red.into()
+                        } else {
+                            TrueColor::from_rgb(0)
+                        };
+                        assert_eq!(bitmap.get_pixel(Point::new(x, y)), Some(expected));
+                    }
+                }
+            }
+            BoxedBitmap::Indexed(_) => unreachable!(),
+        }
+
+        // clipped to the source
+        let captured = BoxedBitmap::from_region(src, Rect::new(6, 4, 10, 10)).unwrap();
+        assert_eq!(captured.size(), Size::new(2, 2));
+        assert!(BoxedBitmap::from_region(src, Rect::new(8, 0, 4, 4)).is_none());
+
+        let mut buf = vec![IndexedColor::BLACK; 8 * 6];
+        let mut screen8 = Bitmap8::from_slice(&mut buf, size, 8);
+        screen8.fill_rect(Rect::new(2, 1, 3, 2), IndexedColor::RED);
+        let src = ConstBitmap::from(screen8.as_ref());
+        match BoxedBitmap::from_region(&src, Rect::new(2, 1, 3, 2)) {
+            Some(BoxedBitmap::Indexed(bitmap)) => {
+                assert!(bitmap.slice().iter().all(|&v| v == IndexedColor::RED))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn to_argb() {
+        // 2x2 pixels with a padding at the end of the first row
+        let buf = [
+            IndexedColor::BLACK,
+            IndexedColor::RED,
+            IndexedColor::DEFAULT_KEY,
+            IndexedColor::WHITE,
+            IndexedColor::BLUE,
+        ];
+        let src = ConstBitmap8::from_slice(&buf, Size::new(2, 2), 3);
+
+        let bitmap = src.to_argb_default();
+        assert_eq!(bitmap.size(), Size::new(2, 2));
+        assert_eq!(
+            bitmap.slice(),
+            [
+                IndexedColor::BLACK.as_true_color(),
+                IndexedColor::RED.as_true_color(),
+                TrueColor::from_argb(0xFFFFFFFF),
+                IndexedColor::BLUE.as_true_color(),
+            ]
+        );
+
+        let mut palette = [0u32; 256];
+        palette[IndexedColor::RED.0 as usize] = 0x80FF0000;
+        let bitmap = src.to_argb(&palette);
+        assert_eq!(
+            bitmap.slice(),
+            [
+                TrueColor::from_argb(0),
+                TrueColor::from_argb(0x80FF0000),
+                TrueColor::from_argb(0),
+                TrueColor::from_argb(0),
+            ]
+        );
+    }
+
+    #[test]
+    fn blt_rotated() {
+        // 3x2 source
+        // A B C
+        // D E F
+        let colors = [0xA, 0xB, 0xC, 0xD, 0xE, 0xF];
+        let src_buf: Vec<TrueColor> = colors.iter().map(|&v| TrueColor::from_rgb(v)).collect();
+        let src = ConstBitmap32::from_slice(&src_buf, Size::new(3, 2), 3);
+        let c = |v: u32| TrueColor::from_rgb(v);
+        let bg = TrueColor::TRANSPARENT;
+
+        // rotated 90 degrees clockwise
+        // D A
+        // E B
+        // F C
+        let mut buf = vec![bg; 4 * 4];
+        let mut dest = Bitmap32::from_slice(&mut buf, Size::new(4, 4), 4);
+        dest.blt_rotated(&src, Point::new(1, 0), src.size().into(), Rotation::Deg90);
+        #[rustfmt::skip]
+        assert_eq!(buf, [
+            bg, c(0xD), c(0xA), bg,
+            bg, c(0xE), c(0xB), bg,
+            bg, c(0xF), c(0xC), bg,
+            bg, bg, bg, bg,
+        ]);
+
+        // rotated 180 degrees and clipped at the top-left corner
+        let mut buf = vec![bg; 3 * 2];
+        let mut dest = Bitmap32::from_slice(&mut buf, Size::new(3, 2), 3);
+        dest.blt_rotated(
+            &src,
+            Point::new(-1, -1),
+            src.size().into(),
+            Rotation::Deg180,
+        );
+        assert_eq!(buf, [c(0xB), c(0xA), bg, bg, bg, bg]);
+
+        // rotated 270 degrees from a part of the source
+        let mut buf = vec![bg; 2 * 2];
+        let mut dest = Bitmap32::from_slice(&mut buf, Size::new(2, 2), 2);
+        dest.blt_rotated(
+            &src,
+            Point::new(0, 0),
+            Rect::new(1, 0, 2, 2),
+            Rotation::Deg270,
+        );
+        assert_eq!(buf, [c(0xC), c(0xF), c(0xB), c(0xE)]);
+    }
+
+    #[test]
+    fn pixels_eq() {
+        let pixels = [
+            TrueColor::from_rgb(0x123456),
+            TrueColor::from_rgb(0x654321),
+            TrueColor::WHITE,
+            TrueColor::TRANSPARENT,
+        ];
+        let a = ConstBitmap32::from_slice(&pixels, Size::new(2, 2), 2);
+        let padded = [pixels[0], pixels[1], TrueColor::WHITE, pixels[2], pixels[3]];
+        let b = ConstBitmap32::from_slice(&padded, Size::new(2, 2), 3);
+        assert!(a.pixels_eq(&b));
+        assert_eq!(a.hash_fnv(), b.hash_fnv());
+
+        let mut modified = pixels;
+        modified[3] = TrueColor::from_rgb(0x000001);
+        let c = ConstBitmap32::from_slice(&modified, Size::new(2, 2), 2);
+        assert!(!a.pixels_eq(&c));
+        assert_ne!(a.hash_fnv(), c.hash_fnv());
+
+        let d = ConstBitmap32::from_slice(&pixels, Size::new(4, 1), 4);
+        assert!(!a.pixels_eq(&d));
+        assert_ne!(a.hash_fnv(), d.hash_fnv());
+    }
+
+    #[test]
+    fn fill_rect_full_width() {
+        assert_eq!(contiguous_len(4, 3, 4), Some(12));
+        assert_eq!(contiguous_len(4, 1, 6), Some(4));
+        assert_eq!(contiguous_len(4, 3, 6), None);
+
+        // a view spanning the full width of its parent is filled row by row
+        let size = Size::new(40, 3);
+        let stride = 48;
+        let mut buf = vec![IndexedColor::BLACK; stride * 3];
+        {
+            let mut bitmap = Bitmap8::from_slice(&mut buf, size, stride);
+            bitmap.fill_rect(Rect::new(0, 1, 40, 2), IndexedColor::YELLOW);
+            bitmap.view(Rect::new(0, 0, 40, 3), |mut view| {
+                view.fill_rect(Rect::new(-5, 0, 50, 1), IndexedColor::BLUE.into());
+            });
+        }
+        for (y, row) in buf.chunks(stride).enumerate() {
+            let expected = if y == 0 {
+                IndexedColor::BLUE
+            } else {
+                IndexedColor::YELLOW
+            };
+            assert!(row[..40].iter().all(|v| *v == expected));
+            assert!(row[40..].iter().all(|v| *v == IndexedColor::BLACK));
+        }
+
+        // starting at an unaligned address
+        let mut buf = [IndexedColor::BLACK; 64];
+        {
+            let mut bitmap = Bitmap8::from_slice(&mut buf[1..], Size::new(63, 1), 63);
+            bitmap.fill_rect(Rect::new(0, 0, 63, 1), IndexedColor::RED);
+        }
+        assert_eq!(buf[0], IndexedColor::BLACK);
+        assert!(buf[1..].iter().all(|v| *v == IndexedColor::RED));
+
+        // the same pixels as filling one by one, taking the contiguous path only if possible
+        let size = Size::new(17, 6);
+        // (rect, whether it is contiguous without and with the padding of the stride)
+        let rects = [
+            (Rect::new(0, 0, 17, 6), [true, false]),
+            (Rect::new(0, 2, 17, 3), [true, false]),
+            (Rect::new(-4, 1, 30, 2), [true, false]),
+            (Rect::new(3, 4, 9, 1), [true, true]),
+            (Rect::new(3, 1, 9, 4), [false, false]),
+            (Rect::new(-2, -3, 5, 20), [false, false]),
+        ];
+        for (padded, &stride) in [17, 21].iter().enumerate() {
+            for &(rect, contiguous) in &rects {
+                let len = stride * size.height() as usize;
+                let mut fast8 = vec![IndexedColor::BLACK; len];
+                let mut slow8 = fast8.clone();
+                let mut fast32 = vec![TrueColor::TRANSPARENT; len];
+                let mut slow32 = fast32.clone();
+                probe::take(&probe::CONTIGUOUS_FILLS);
+                Bitmap8::from_slice(&mut fast8, size, stride).fill_rect(rect, IndexedColor::RED);
+                Bitmap32::from_slice(&mut fast32, size, stride).fill_rect(rect, TrueColor::WHITE);
+                let fills = probe::take(&probe::CONTIGUOUS_FILLS);
+
+                let mut bitmap8 = Bitmap8::from_slice(&mut slow8, size, stride);
+                let mut bitmap32 = Bitmap32::from_slice(&mut slow32, size, stride);
+                for y in rect.y()..rect.y() + rect.height() {
+                    for x in rect.x()..rect.x() + rect.width() {
+                        bitmap8.set_pixel(Point::new(x, y), IndexedColor::RED);
+                        bitmap32.set_pixel(Point::new(x, y), TrueColor::WHITE);
+                    }
+                }
+                assert_eq!(fast8, slow8, "{} {:?}", stride, rect);
+                assert_eq!(fast32, slow32, "{} {:?}", stride, rect);
+                let expected = if contiguous[padded] { 2 } else { 0 };
+                assert_eq!(fills, expected, "{} {:?}", stride, rect);
+            }
+        }
+    }
+
+    #[test]
+    fn const_bitmap_sub() {
+        let size = Size::new(5, 4);
+        let pixels = (0..20).map(TrueColor::from_rgb).collect::<Vec<_>>();
+        let bitmap = ConstBitmap32::from_slice(&pixels, size, 5);
+
+        let rect = Rect::new(1, 2, 4, 2);
+        let sub = bitmap.sub(rect).unwrap();
+        assert_eq!(sub.size(), rect.size());
+        assert_eq!(sub.stride(), 5);
+        for y in 0..2 {
+            for x in 0..4 {
+                let point = Point::new(x, y);
+                assert_eq!(
+                    sub.get_pixel(point),
+                    bitmap.get_pixel(point + rect.origin())
+                );
+            }
+        }
+        assert_eq!(sub.get_pixel(Point::new(4, 0)), None);
+
+        let whole = bitmap.sub(size.into()).unwrap();
+        assert!(whole.pixels_eq(&bitmap));
+        let nested = sub.sub(Rect::new(3, 1, 1, 1)).unwrap();
+        assert_eq!(nested.get_pixel(Point::new(0, 0)), Some(pixels[19]));
+
+        // out of bounds or empty
+        assert!(bitmap.sub(Rect::new(2, 2, 4, 2)).is_none());
+        assert!(bitmap.sub(Rect::new(-1, 0, 2, 2)).is_none());
+        assert!(bitmap.sub(Rect::new(0, 3, 1, 2)).is_none());
+        assert!(bitmap.sub(Rect::new(0, 0, 0, 2)).is_none());
+    }
+
+    #[test]
+    fn boxed_bitmap_resize() {
+        let bg = TrueColor::from_rgb(0x123456);
+        let mut bitmap = BoxedBitmap32::new(Size::new(3, 2), TrueColor::WHITE);
+        bitmap.draw(|bitmap| {
+            for y in 0..2 {
+                for x in 0..3 {
+                    bitmap.set_pixel(Point::new(x, y), TrueColor::from_rgb((y * 3 + x) as u32));
+                }
+            }
+        });
+
+        bitmap.resize(Size::new(5, 4), bg);
+        assert_eq!(bitmap.size(), Size::new(5, 4));
+        assert_eq!(bitmap.stride(), 5);
+        for y in 0..4 {
+            for x in 0..5 {
+                let expected = if x < 3 && y < 2 {
+                    TrueColor::from_rgb((y * 3 + x) as u32)
+                } else {
+                    bg
+                };
+                assert_eq!(bitmap.get_pixel(Point::new(x, y)), Some(expected));
+            }
+        }
+
+        bitmap.resize(Size::new(2, 1), bg);
+        assert_eq!(bitmap.size(), Size::new(2, 1));
+        assert_eq!(
+            bitmap.slice(),
+            [TrueColor::from_rgb(0), TrueColor::from_rgb(1)]
+        );
+
+        // empty or negative sizes are clamped to 1x1
+        bitmap.resize(Size::new(0, -3), bg);
+        assert_eq!(bitmap.size(), Size::new(1, 1));
+        assert_eq!(bitmap.slice(), [TrueColor::from_rgb(0)]);
+    }
+
+    #[test]
+    fn blt_stipple() {
+        let size = Size::new(6, 5);
+        let stride = size.width() as usize;
+        let src = vec![IndexedColor::BLACK; 16];
+        let src = ConstBitmap8::from_slice(&src, Size::new(4, 4), 4);
+        let mut buf = vec![IndexedColor::WHITE; stride * size.height() as usize];
+        {
+            let mut bitmap = Bitmap8::from_slice(&mut buf, size, stride);
+
+            // the source is clipped by the right and bottom edges
+            bitmap.blt_stipple(&src, Point::new(3, 2), src.bounds());
+        }
+        for y in 0..size.height() as usize {
+            for x in 0..stride {
+                let expected = if x >= 3 && y >= 2 && (x + y) % 2 == 0 {
+                    IndexedColor::BLACK
+                } else {
+                    IndexedColor::WHITE
+                };
+                assert_eq!(buf[x + y * stride], expected, "at ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn const_bitmap_get_pixel() {
+        let mut buf = vec![IndexedColor::BLACK; 16];
+        buf[6] = IndexedColor::WHITE;
+        let bitmap8 = ConstBitmap8::from_slice(&buf, Size::new(4, 4), 4);
+        let bitmap = ConstBitmap::from(&bitmap8);
+        assert_eq!(
+            bitmap.get_pixel(Point::new(2, 1)),
+            Some(AmbiguousColor::Indexed(IndexedColor::WHITE))
+        );
+        assert_eq!(
+            bitmap.get_pixel(Point::new(0, 0)),
+            Some(AmbiguousColor::Indexed(IndexedColor::BLACK))
+        );
+        assert_eq!(bitmap.get_pixel(Point::new(4, 0)), None);
+
+        let mut buf = vec![TrueColor::from_rgb(0); 16];
+        buf[13] = TrueColor::from_rgb(0x123456);
+        let bitmap32 = ConstBitmap32::from_slice(&buf, Size::new(4, 4), 4);
+        let bitmap = ConstBitmap::from(&bitmap32);
+        assert_eq!(
+            bitmap.get_pixel(Point::new(1, 3)),
+            Some(AmbiguousColor::Argb32(TrueColor::from_rgb(0x123456)))
+        );
+        assert_eq!(bitmap.get_pixel(Point::new(0, 4)), None);
+    }
+
+    #[test]
+    fn clip_huge_coordinates() {
+        let mut buf = vec![IndexedColor::BLACK; 16];
+        let mut bitmap = Bitmap8::from_slice(&mut buf, Size::new(4, 4), 4);
+        bitmap.fill_rect(Rect::new(2, 2, isize::MAX, isize::MAX), IndexedColor::WHITE);
+        bitmap.fill_rect(
+            Rect::new(isize::MIN, isize::MIN, isize::MAX, isize::MAX),
+            IndexedColor::WHITE,
+        );
+        bitmap.draw_hline(
+            Point::new(isize::MAX - 1, 0),
+            isize::MAX,
+            IndexedColor::WHITE,
+        );
+        bitmap.draw_vline(
+            Point::new(0, isize::MAX - 1),
+            isize::MAX,
+            IndexedColor::WHITE,
+        );
+        bitmap.draw_font(
+            &[0xFF; 16],
+            Size::new(8, 16),
+            Point::new(isize::MAX - 1, isize::MAX - 1),
+            IndexedColor::WHITE,
+        );
+        let white = buf.iter().filter(|v| **v == IndexedColor::WHITE).count();
+        assert_eq!(white, 4);
+
+        let src_buf = vec![IndexedColor::WHITE; 16];
+        let src = ConstBitmap8::from_slice(&src_buf, Size::new(4, 4), 4);
+        let mut buf = vec![IndexedColor::BLACK; 16];
+        let mut bitmap = Bitmap8::from_slice(&mut buf, Size::new(4, 4), 4);
+        bitmap.blt(
+            &src,
+            Point::new(isize::MIN, isize::MIN),
+            Rect::new(isize::MAX, isize::MAX, isize::MAX, isize::MAX),
+        );
+        bitmap.blt(
+            &src,
+            Point::new(isize::MAX, isize::MAX),
+            Rect::new(0, 0, isize::MAX, isize::MAX),
+        );
+        bitmap.blt(
+            &src,
+            Point::new(3, 3),
+            Rect::new(-2, -2, isize::MAX, isize::MAX),
+        );
+        let white = buf.iter().filter(|v| **v == IndexedColor::WHITE).count();
+        assert_eq!(white, 0);
+
+        let mut buf = vec![IndexedColor::BLACK; 16];
+        let mut bitmap = Bitmap8::from_slice(&mut buf, Size::new(4, 4), 4);
+        bitmap.blt(
+            &src,
+            Point::new(2, 2),
+            Rect::new(0, 0, isize::MAX, isize::MAX),
+        );
+        let white = buf.iter().filter(|v| **v == IndexedColor::WHITE).count();
+        assert_eq!(white, 4);
+    }
+
+    #[test]
+    fn blt_itself_overlapped8() {
+        let mut buf = vec![IndexedColor::BLACK; 16];
+        let mut bitmap = Bitmap8::from_slice(&mut buf, Size::new(4, 4), 4);
+        for y in 0..4 {
+            bitmap.fill_rect(Rect::new(0, y, 4, 1), IndexedColor(y as u8));
+        }
+        let mut bitmap = Bitmap::from(&mut bitmap);
+
+        // scroll down
+        bitmap.blt_itself(Point::new(0, 1), Rect::new(0, 0, 4, 3));
+        for (y, expected) in [0, 0, 1, 2].iter().enumerate() {
+            for x in 0..4 {
+                let color = bitmap.get_pixel(Point::new(x, y as isize)).unwrap();
+                assert_eq!(color, IndexedColor(*expected).into());
+            }
+        }
+
+        // scroll up
+        bitmap.blt_itself(Point::new(0, 0), Rect::new(0, 1, 4, 3));
+        for (y, expected) in [0, 1, 2, 2].iter().enumerate() {
+            for x in 0..4 {
+                let color = bitmap.get_pixel(Point::new(x, y as isize)).unwrap();
+                assert_eq!(color, IndexedColor(*expected).into());
+            }
+        }
+    }
+
+    #[test]
+    fn blt_itself_overlapped32() {
+        let mut buf = vec![TrueColor::TRANSPARENT; 8];
+        let mut bitmap = Bitmap32::from_slice(&mut buf, Size::new(8, 1), 8);
+        for x in 0..8 {
+            bitmap.set_pixel(Point::new(x, 0), TrueColor::from_rgb(x as u32));
+        }
+        let mut bitmap = Bitmap::from(&mut bitmap);
+
+        // scroll right
+        bitmap.blt_itself(Point::new(2, 0), Rect::new(0, 0, 6, 1));
+        for (x, expected) in [0, 1, 0, 1, 2, 3, 4, 5].iter().enumerate() {
+            let color = bitmap.get_pixel(Point::new(x as isize, 0)).unwrap();
+            assert_eq!(color, TrueColor::from_rgb(*expected).into());
+        }
+
+        // scroll left
+        bitmap.blt_itself(Point::new(0, 0), Rect::new(2, 0, 6, 1));
+        for (x, expected) in [0, 1, 2, 3, 4, 5, 4, 5].iter().enumerate() {
+            let color = bitmap.get_pixel(Point::new(x as isize, 0)).unwrap();
+            assert_eq!(color, TrueColor::from_rgb(*expected).into());
+        }
+    }
+
+    #[test]
+    fn bitmap1_expand() {
+        #[rustfmt::skip]
+        let smiley = [
+            0b0011_1100,
+            0b0100_0010,
+            0b1010_0101,
+            0b1000_0001,
+            0b1010_0101,
+            0b1001_1001,
+            0b0100_0010,
+            0b0011_1100,
+        ];
+        let mask = ConstBitmap1::from_bytes(&smiley, Size::new(8, 8));
+        assert_eq!(mask.stride(), 1);
+        let fg = IndexedColor::YELLOW;
+        let bg = IndexedColor::BLUE;
+
+        // clear bits are transparent
+        let mut buf = [IndexedColor::BLACK; 64];
+        let mut bitmap = Bitmap8::from_slice(&mut buf, Size::new(8, 8), 8);
+        mask.blt_to(&mut bitmap, Point::new(0, 0), fg, None);
+        for (y, row) in buf.chunks(8).enumerate() {
+            for (x, pixel) in row.iter().enumerate() {
+                let set = (smiley[y] & (0x80 >> x)) != 0;
+                assert_eq!(*pixel, if set { fg } else { IndexedColor::BLACK });
+            }
+        }
+
+        // clear bits in the background color, clipped at the edges
+        let mut buf = [IndexedColor::BLACK; 64];
+        let mut bitmap = Bitmap8::from_slice(&mut buf, Size::new(8, 8), 8);
+        mask.blt_to(&mut bitmap, Point::new(-2, 4), fg, Some(bg));
+        for (y, row) in buf.chunks(8).enumerate() {
+            for (x, pixel) in row.iter().enumerate() {
+                let expected = if y < 4 || x >= 6 {
+                    IndexedColor::BLACK
+                } else if (smiley[y - 4] & (0x80 >> (x + 2))) != 0 {
+                    fg
+                } else {
+                    bg
+                };
+                assert_eq!(*pixel, expected);
+            }
+        }
+
+        // scaled
+        let mut buf = [IndexedColor::BLACK; 256];
+        let mut bitmap = Bitmap8::from_slice(&mut buf, Size::new(16, 16), 16);
+        mask.blt_scaled(&mut bitmap, Point::new(0, 0), fg, Some(bg), 2);
+        assert_eq!(buf[2], bg);
+        assert_eq!(buf[4], fg);
+        assert_eq!(buf[16 + 5], fg);
+        assert_eq!(buf[16 * 15 + 15], bg);
+    }
+
+    #[test]
+    fn boxed_bitmap_zero_size() {
+        let mut bitmap = BoxedBitmap8::new(Size::new(0, 5), IndexedColor::RED);
+        assert_eq!(bitmap.size(), Size::new(1, 5));
+        assert!(!bitmap.is_empty());
+        assert_eq!(bitmap.get_pixel(Point::new(0, 4)), Some(IndexedColor::RED));
+        bitmap.draw(|bitmap| bitmap.fill_rect(Rect::new(0, 0, 4, 4), IndexedColor::BLUE));
+
+        let bitmap = BoxedBitmap32::new(Size::new(-3, 0), TrueColor::WHITE);
+        assert_eq!(bitmap.size(), Size::new(1, 1));
+        assert_eq!(bitmap.get_pixel(Point::new(0, 0)), Some(TrueColor::WHITE));
+
+        let mut buf = [TrueColor::WHITE; 0];
+        let bitmap = Bitmap32::from_slice(&mut buf, Size::new(0, 0), 0);
+        assert!(bitmap.is_empty());
+        assert_eq!(bitmap.get_pixel(Point::new(0, 0)), None);
+    }
+
+    #[test]
+    fn box_blur() {
+        let size = Size::new(7, 7);
+        let mut bitmap = BoxedBitmap32::new(size, TrueColor::from_argb(0xFF000000));
+        bitmap.draw(|bitmap| bitmap.set_pixel(Point::new(3, 3), TrueColor::from_rgb(0xFFFFFF)));
+        bitmap.box_blur(1);
+
+        let blue = |x, y| bitmap.get_pixel(Point::new(x, y)).unwrap().rgb() & 0xFF;
+        let center = blue(3, 3);
+        assert!(center > 0 && center < 0xFF);
+        for (dx, dy) in [(1, 0), (0, 1), (1, 1)].iter() {
+            let value = blue(3 + dx, 3 + dy);
+            assert!(value > 0);
+            assert_eq!(value, blue(3 - dx, 3 - dy));
+            assert_eq!(value, blue(3 - dx, 3 + dy));
+            assert_eq!(value, blue(3 + dy, 3 + dx));
+        }
+        assert_eq!(blue(1, 3), 0);
+        assert_eq!(blue(3, 5), 0);
+
+        let total: u32 = (0..7)
+            .flat_map(|y| (0..7).map(move |x| (x, y)))
+            .map(|(x, y)| blue(x, y))
+            .sum();
+        assert!((0xFF - 9..=0xFF + 9).contains(&total));
+        assert!(bitmap.slice().iter().all(|pixel| pixel.opacity() == 0xFF));
+    }
+
+    #[test]
+    fn enum_round_rect() {
+        let size = Size::new(24, 20);
+        let len = (size.width() * size.height()) as usize;
+        let color = TrueColor::from_rgb(0x55AAFF);
+        let draw = |bitmap: &mut dyn FnMut(usize)| {
+            for step in 0..4 {
+                bitmap(step);
+            }
+        };
+
+        let mut expected = vec![TrueColor::WHITE; len];
+        let mut bitmap32 = Bitmap32::from_slice(&mut expected, size, size.width() as usize);
+        draw(&mut |step| match step {
+            0 => bitmap32.fill_round_rect(Rect::new(1, 1, 20, 12), 4, color),
+            1 => bitmap32.draw_round_rect(Rect::new(3, 5, 18, 14), 6, color),
+            2 => bitmap32.draw_circle(Point::new(12, 10), 7, color),
+            _ => bitmap32.fill_circle(Point::new(20, 16), 5, color),
+        });
+
+        let mut actual = vec![TrueColor::WHITE; len];
+        {
+            let mut bitmap32 = Bitmap32::from_slice(&mut actual, size, size.width() as usize);
+            let mut bitmap = Bitmap::from(&mut bitmap32);
+            let color = AmbiguousColor::from(color);
+            probe::take(&probe::ENUM_PRIMITIVES);
+            draw(&mut |step| {
+                match step {
+                    0 => bitmap.fill_round_rect(Rect::new(1, 1, 20, 12), 4, color),
+                    1 => bitmap.draw_round_rect(Rect::new(3, 5, 18, 14), 6, color),
+                    2 => bitmap.draw_circle(Point::new(12, 10), 7, color),
+                    _ => bitmap.fill_circle(Point::new(20, 16), 5, color),
+                }
+                // forwarded as a whole, never through the primitives of the enum
+                assert_eq!(probe::take(&probe::ENUM_PRIMITIVES), 0, "step {}", step);
+            });
+
+            let bg = AmbiguousColor::from(TrueColor::WHITE);
+            bitmap.draw_hline(Point::new(0, 0), 1, bg);
+            bitmap.set_pixel(Point::new(0, 1), bg);
+            assert_eq!(probe::take(&probe::ENUM_PRIMITIVES), 2);
+        }
+
+        assert_eq!(actual, expected);
+        assert!(actual.iter().any(|&v| v == TrueColor::from_rgb(0x55AAFF)));
+    }
+}
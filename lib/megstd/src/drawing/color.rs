@@ -70,6 +70,10 @@ impl IndexedColor {
         0xFFFFFFFF, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
     ];
 
+    /// Number of valid entries in the palette, the rest are unused.
+    const PALETTE_LEN: usize = 232;
+
+    /// Returns the entry of the 6x6x6 color cube nearest to the given RGB color.
     #[inline]
     pub const fn from_rgb(rgb: u32) -> Self {
         let b = (((rgb & 0xFF) + 25) / 51) as u8;
@@ -78,6 +82,94 @@ impl IndexedColor {
         Self(16 + r + g * 6 + b * 36)
     }
 
+    /// Returns the palette entry nearest to the given RGB color,
+    /// also taking the 16 system colors into account.
+    ///
+    /// This is slower than `from_rgb`, which only looks up the color cube.
+    pub const fn from_rgb_system(rgb: u32) -> Self {
+        let cube = Self::from_rgb(rgb).0;
+        let mut result = cube;
+        let mut min_distance = Self::distance(rgb, Self::COLOR_PALETTE[cube as usize]);
+        let mut index = 0;
+        while index < 16 {
+            let distance = Self::distance(rgb, Self::COLOR_PALETTE[index]);
+            if distance <= min_distance {
+                result = index as u8;
+                min_distance = distance;
+                if distance == 0 {
+                    break;
+                }
+            }
+            index += 1;
+        }
+        Self(result)
+    }
+
+    /// Returns the palette entry nearest to the given RGB color, compared in linear light.
+    ///
+    /// The sRGB components are linearized before measuring the distance,
+    /// which is weighted by the luminance coefficients of BT.709.
+    /// This is much slower than `from_rgb` because it scans the whole palette.
+    pub const fn from_rgb_gamma(rgb: u32) -> Self {
+        let mut result = 0;
+        let mut min_distance = u64::MAX;
+        let mut index = 0;
+        while index < Self::PALETTE_LEN {
+            let distance = Self::linear_distance(rgb, Self::COLOR_PALETTE[index]);
+            if distance < min_distance {
+                result = index as u8;
+                min_distance = distance;
+            }
+            index += 1;
+        }
+        Self(result)
+    }
+
+    /// Squared euclidean distance between two RGB colors.
+    #[inline]
+    const fn distance(lhs: u32, rhs: u32) -> u32 {
+        let dr = ((lhs >> 16) & 0xFF) as i32 - ((rhs >> 16) & 0xFF) as i32;
+        let dg = ((lhs >> 8) & 0xFF) as i32 - ((rhs >> 8) & 0xFF) as i32;
+        let db = (lhs & 0xFF) as i32 - (rhs & 0xFF) as i32;
+        (dr * dr + dg * dg + db * db) as u32
+    }
+
+    /// sRGB component values to linear light, scaled to 0..=4095
+    const SRGB_TO_LINEAR: [u16; 256] = [
+        0, 1, 2, 4, 5, 6, 7, 9, 10, 11, 12, 14, 15, 16, 18, 20, 21, 23, 25, 27, 29, 31, 33, 35, 37,
+        40, 42, 45, 48, 50, 53, 56, 59, 62, 66, 69, 72, 76, 79, 83, 87, 91, 95, 99, 103, 107, 112,
+        116, 121, 126, 131, 136, 141, 146, 151, 156, 162, 168, 173, 179, 185, 191, 197, 204, 210,
+        216, 223, 230, 237, 244, 251, 258, 265, 273, 280, 288, 296, 304, 312, 320, 329, 337, 346,
+        354, 363, 372, 381, 390, 400, 409, 419, 428, 438, 448, 458, 469, 479, 490, 500, 511, 522,
+        533, 544, 555, 567, 578, 590, 602, 614, 626, 639, 651, 664, 676, 689, 702, 715, 728, 742,
+        755, 769, 783, 797, 811, 825, 840, 854, 869, 884, 899, 914, 929, 945, 960, 976, 992, 1008,
+        1024, 1041, 1057, 1074, 1091, 1108, 1125, 1142, 1159, 1177, 1195, 1213, 1231, 1249, 1267,
+        1286, 1304, 1323, 1342, 1361, 1381, 1400, 1420, 1440, 1459, 1480, 1500, 1520, 1541, 1562,
+        1582, 1603, 1625, 1646, 1668, 1689, 1711, 1733, 1755, 1778, 1800, 1823, 1846, 1869, 1892,
+        1916, 1939, 1963, 1987, 2011, 2035, 2059, 2084, 2109, 2133, 2159, 2184, 2209, 2235, 2260,
+        2286, 2312, 2339, 2365, 2392, 2419, 2446, 2473, 2500, 2527, 2555, 2583, 2611, 2639, 2668,
+        2696, 2725, 2754, 2783, 2812, 2841, 2871, 2901, 2931, 2961, 2991, 3022, 3052, 3083, 3114,
+        3146, 3177, 3209, 3240, 3272, 3304, 3337, 3369, 3402, 3435, 3468, 3501, 3535, 3568, 3602,
+        3636, 3670, 3705, 3739, 3774, 3809, 3844, 3879, 3915, 3950, 3986, 4022, 4059, 4095,
+    ];
+
+    /// Squared distance between two RGB colors in linear light, weighted by the luminance.
+    #[inline]
+    const fn linear_distance(lhs: u32, rhs: u32) -> u64 {
+        const fn delta(lhs: u32, rhs: u32, shift: usize) -> u64 {
+            let l = IndexedColor::SRGB_TO_LINEAR[((lhs >> shift) & 0xFF) as usize] as i64;
+            let r = IndexedColor::SRGB_TO_LINEAR[((rhs >> shift) & 0xFF) as usize] as i64;
+            ((l - r) * (l - r)) as u64
+        }
+        2126 * delta(lhs, rhs, 16) + 7152 * delta(lhs, rhs, 8) + 722 * delta(lhs, rhs, 0)
+    }
+
+    /// Returns the index in the palette.
+    #[inline]
+    pub const fn index(self) -> u8 {
+        self.0
+    }
+
     #[inline]
     pub const fn as_rgb(self) -> u32 {
         Self::COLOR_PALETTE[self.0 as usize] & 0xFF_FF_FF
@@ -94,6 +186,18 @@ impl IndexedColor {
     }
 }
 
+impl IndexedColor {
+    #[inline]
+    pub const fn to_le_bytes(self) -> [u8; 1] {
+        [self.0]
+    }
+
+    #[inline]
+    pub const fn from_le_bytes(bytes: [u8; 1]) -> Self {
+        Self(bytes[0])
+    }
+}
+
 impl From<u8> for IndexedColor {
     fn from(val: u8) -> Self {
         Self(val)
@@ -210,6 +314,43 @@ impl TrueColor {
     }
 }
 
+impl TrueColor {
+    /// Returns the ARGB value in little endian, that is B, G, R, A in this order.
+    #[inline]
+    pub const fn to_le_bytes(self) -> [u8; 4] {
+        self.argb.to_le_bytes()
+    }
+
+    /// Creates a color from B, G, R, A bytes in this order.
+    #[inline]
+    pub const fn from_le_bytes(bytes: [u8; 4]) -> Self {
+        Self::from_argb(u32::from_le_bytes(bytes))
+    }
+
+    /// Returns the pixels as bytes in the native byte order,
+    /// which is the same as `to_le_bytes` on little endian targets.
+    #[inline]
+    pub fn slice_to_bytes(slice: &[Self]) -> &[u8] {
+        // SAFETY: `TrueColor` is a `u32` without padding, and `u8` has no alignment requirement.
+        unsafe {
+            core::slice::from_raw_parts(slice.as_ptr() as *const u8, core::mem::size_of_val(slice))
+        }
+    }
+
+    /// Returns the bytes as pixels in the native byte order,
+    /// or `None` if the bytes are not aligned or their length is not a multiple of 4.
+    #[inline]
+    pub fn slice_from_bytes(bytes: &[u8]) -> Option<&[Self]> {
+        // SAFETY: any bit pattern is a valid `TrueColor`.
+        let (prefix, slice, suffix) = unsafe { bytes.align_to::<Self>() };
+        if prefix.is_empty() && suffix.is_empty() {
+            Some(slice)
+        } else {
+            None
+        }
+    }
+}
+
 impl From<u32> for TrueColor {
     fn from(val: u32) -> Self {
         Self::from_argb(val)
@@ -430,3 +571,105 @@ impl From<TrueColor> for AmbiguousColor {
         Self::Argb32(val)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indexed_from_rgb() {
+        // system colors are not looked up
+        assert_eq!(IndexedColor::from_rgb(0x2196F3).as_rgb(), 0x3399FF);
+        assert_eq!(IndexedColor::from_rgb(0xFFFFFF).index(), 231);
+        // exact color cube
+        assert_eq!(IndexedColor::from_rgb(0x000000).index(), 16);
+        assert_eq!(IndexedColor::from_rgb(0xFF0000).index(), 21);
+        assert_eq!(
+            IndexedColor::from_rgb(0x3366CC).index(),
+            16 + 1 + 2 * 6 + 4 * 36
+        );
+        // nearest
+        assert_eq!(IndexedColor::from_rgb(0x0A0A0A).index(), 16);
+        assert_eq!(IndexedColor::from_rgb(0xFE0101).index(), 21);
+
+        for index in 16..IndexedColor::PALETTE_LEN {
+            let color = IndexedColor(index as u8);
+            assert_eq!(IndexedColor::from_rgb(color.as_rgb()), color);
+        }
+    }
+
+    #[test]
+    fn indexed_from_rgb_system() {
+        // exact system colors
+        assert_eq!(IndexedColor::from_rgb_system(0x2196F3).index(), 9);
+        assert_eq!(IndexedColor::from_rgb_system(0xFFFFFF), IndexedColor::WHITE);
+        assert_eq!(
+            IndexedColor::from_rgb_system(0x9E9E9E),
+            IndexedColor::LIGHT_GRAY
+        );
+        // nearest
+        assert_eq!(IndexedColor::from_rgb_system(0x2097F0).index(), 9);
+        assert_eq!(IndexedColor::from_rgb_system(0x0A0A0A).index(), 16);
+        assert_eq!(
+            IndexedColor::from_rgb_system(0x3366CC).index(),
+            16 + 1 + 2 * 6 + 4 * 36
+        );
+
+        for index in 0..IndexedColor::PALETTE_LEN {
+            let color = IndexedColor(index as u8);
+            assert_eq!(
+                IndexedColor::from_rgb_system(color.as_rgb()).as_rgb(),
+                color.as_rgb()
+            );
+        }
+    }
+
+    #[test]
+    fn le_bytes() {
+        for index in 0..=255 {
+            let color = IndexedColor(index);
+            assert_eq!(IndexedColor::from_le_bytes(color.to_le_bytes()), color);
+        }
+
+        let color = TrueColor::from_argb(0x80_11_22_33);
+        assert_eq!(color.to_le_bytes(), [0x33, 0x22, 0x11, 0x80]);
+        assert_eq!(TrueColor::from_le_bytes(color.to_le_bytes()), color);
+        for &argb in &[0, 0xFFFF_FFFF, 0x12_34_56_78, 0xFF_2196F3] {
+            let color = TrueColor::from_argb(argb);
+            assert_eq!(TrueColor::from_le_bytes(color.to_le_bytes()), color);
+        }
+
+        let pixels = [color, TrueColor::WHITE];
+        let bytes = TrueColor::slice_to_bytes(&pixels);
+        assert_eq!(bytes.len(), 8);
+        #[cfg(target_endian = "little")]
+        assert_eq!(bytes, [0x33, 0x22, 0x11, 0x80, 0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(TrueColor::slice_from_bytes(bytes), Some(&pixels[..]));
+        assert_eq!(TrueColor::slice_from_bytes(&bytes[..7]), None);
+        assert_eq!(TrueColor::slice_from_bytes(&bytes[1..5]), None);
+    }
+
+    #[test]
+    fn indexed_from_rgb_gamma() {
+        assert_eq!(IndexedColor::from_rgb_gamma(0x2196F3).index(), 9);
+        assert_eq!(IndexedColor::from_rgb_gamma(0xFFFFFF), IndexedColor::WHITE);
+        assert_eq!(IndexedColor::from_rgb_gamma(0x000000).index(), 16);
+        assert_eq!(IndexedColor::from_rgb_gamma(0xFE0101).index(), 21);
+
+        for index in 0..IndexedColor::PALETTE_LEN {
+            let color = IndexedColor(index as u8);
+            assert_eq!(
+                IndexedColor::from_rgb_gamma(color.as_rgb()).as_rgb(),
+                color.as_rgb()
+            );
+        }
+
+        // colors in linear light may be nearer to a different entry
+        let rgb = 0x201880;
+        assert_eq!(IndexedColor::from_rgb(rgb).as_rgb(), 0x330099);
+        assert_eq!(IndexedColor::from_rgb_gamma(rgb).as_rgb(), 0x000066);
+        let rgb = 0x808080;
+        assert_eq!(IndexedColor::from_rgb(rgb).as_rgb(), 0x999999);
+        assert_eq!(IndexedColor::from_rgb_gamma(rgb).as_rgb(), 0x666666);
+    }
+}
@@ -25,6 +25,22 @@ impl Point {
         self.y
     }
 
+    #[inline]
+    pub const fn saturating_add(self, rhs: Self) -> Self {
+        Self {
+            x: self.x.saturating_add(rhs.x),
+            y: self.y.saturating_add(rhs.y),
+        }
+    }
+
+    #[inline]
+    pub const fn saturating_sub(self, rhs: Self) -> Self {
+        Self {
+            x: self.x.saturating_sub(rhs.x),
+            y: self.y.saturating_sub(rhs.y),
+        }
+    }
+
     pub fn line_to<F>(&self, other: Point, mut f: F)
     where
         F: FnMut(Self),
@@ -69,6 +85,59 @@ impl Point {
         }
     }
 
+    /// Same as `line_to`, but visits only the points within `clip`.
+    ///
+    /// Only the steps whose major axis falls within `clip` are iterated, and each of them
+    /// derives the minor axis from the same error term as `line_to`, so the points far
+    /// outside of `clip` are never iterated and the visited points are exactly the same.
+    pub fn line_to_clipped<F>(&self, other: Point, clip: Rect, mut f: F)
+    where
+        F: FnMut(Self),
+    {
+        let coords = match Coordinates::from_rect(clip) {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+        let (x0, y0) = (self.x as i128, self.y as i128);
+        let (x1, y1) = (other.x as i128, other.y as i128);
+        let (dx, dy) = ((x1 - x0).abs(), (y1 - y0).abs());
+        let sx = if x1 > x0 { 1 } else { -1 };
+        let sy = if y1 > y0 { 1 } else { -1 };
+
+        let x_major = dx >= dy;
+        let (major0, s_major, d_major, d_minor, min, max) = if x_major {
+            (x0, sx, dx, dy, coords.left, coords.right)
+        } else {
+            (y0, sy, dy, dx, coords.top, coords.bottom)
+        };
+        let (min, max) = (min as i128, max as i128 - 1);
+        let (first, last) = if s_major > 0 {
+            (min - major0, max - major0)
+        } else {
+            (major0 - max, major0 - min)
+        };
+        let first = i128::max(first, 0);
+        let last = i128::min(last, d_major);
+
+        let mut k = first;
+        while k <= last {
+            let minor = if d_major > 0 {
+                (2 * k * d_minor + d_major - 1) / (2 * d_major)
+            } else {
+                0
+            };
+            let point = if x_major {
+                Point::new((x0 + sx * k) as isize, (y0 + sy * minor) as isize)
+            } else {
+                Point::new((x0 + sx * minor) as isize, (y0 + sy * k) as isize)
+            };
+            if point.is_within(clip) {
+                f(point);
+            }
+            k += 1;
+        }
+    }
+
     #[inline]
     pub fn is_within(self, rect: Rect) -> bool {
         if let Ok(coords) = Coordinates::from_rect(rect) {
@@ -182,6 +251,22 @@ impl Size {
     pub const fn height(&self) -> isize {
         self.height
     }
+
+    #[inline]
+    pub const fn saturating_add(self, rhs: Self) -> Self {
+        Self {
+            width: self.width.saturating_add(rhs.width),
+            height: self.height.saturating_add(rhs.height),
+        }
+    }
+
+    #[inline]
+    pub const fn saturating_sub(self, rhs: Self) -> Self {
+        Self {
+            width: self.width.saturating_sub(rhs.width),
+            height: self.height.saturating_sub(rhs.height),
+        }
+    }
 }
 
 impl Add<Self> for Size {
@@ -311,6 +396,57 @@ impl Rect {
         }
     }
 
+    /// Shrinks each side by the corresponding inset.
+    #[inline]
+    pub fn deflate(self, insets: EdgeInsets) -> Self {
+        self.insets_by(insets)
+    }
+
+    /// Grows each side by the corresponding inset.
+    #[inline]
+    pub fn inflate(self, insets: EdgeInsets) -> Self {
+        Rect {
+            origin: Point {
+                x: self.origin.x - insets.left,
+                y: self.origin.y - insets.top,
+            },
+            size: Size {
+                width: self.size.width + (insets.left + insets.right),
+                height: self.size.height + (insets.top + insets.bottom),
+            },
+        }
+    }
+
+    /// Shrinks all sides by `d`, or grows them if `d` is negative.
+    ///
+    /// The size is clamped to zero if the rectangle is too small.
+    pub fn inset(&self, d: isize) -> Self {
+        let (x, width) = Self::inset_axis(self.origin.x, self.size.width, d);
+        let (y, height) = Self::inset_axis(self.origin.y, self.size.height, d);
+        Rect::new(x, y, width, height)
+    }
+
+    #[inline]
+    fn inset_axis(origin: isize, length: isize, d: isize) -> (isize, isize) {
+        let new_length = length.saturating_sub(d.saturating_mul(2));
+        if new_length < 0 {
+            (origin.saturating_add(length / 2), 0)
+        } else {
+            (origin.saturating_add(d), new_length)
+        }
+    }
+
+    /// Moves the rectangle by the specified amount.
+    #[inline]
+    pub const fn offset(&self, dx: isize, dy: isize) -> Self {
+        Rect::new(
+            self.origin.x.saturating_add(dx),
+            self.origin.y.saturating_add(dy),
+            self.size.width,
+            self.size.height,
+        )
+    }
+
     pub fn is_within_rect(self, rhs: Self) -> bool {
         let cl = match Coordinates::from_rect(self) {
             Ok(coords) => coords,
@@ -324,12 +460,71 @@ impl Rect {
         cl.left < cr.right && cr.left < cl.right && cl.top < cr.bottom && cr.top < cl.bottom
     }
 
+    /// Returns the area shared by both rectangles, or `None` if they do not intersect.
+    pub fn intersection(self, rhs: Self) -> Option<Self> {
+        if self.size.width < 0 || self.size.height < 0 || rhs.size.width < 0 || rhs.size.height < 0
+        {
+            return None;
+        }
+        let cl = Coordinates::from_rect(self).ok()?;
+        let cr = Coordinates::from_rect(rhs).ok()?;
+        let coords = Coordinates::new(
+            isize::max(cl.left, cr.left),
+            isize::max(cl.top, cr.top),
+            isize::min(cl.right, cr.right),
+            isize::min(cl.bottom, cr.bottom),
+        );
+        if coords.left < coords.right && coords.top < coords.bottom {
+            Some(coords.into())
+        } else {
+            None
+        }
+    }
+
+    /// Returns the point just past the bottom right corner, saturating at the numeric bounds.
+    #[inline]
+    pub const fn saturating_max(&self) -> Point {
+        self.origin
+            .saturating_add(Point::new(self.size.width, self.size.height))
+    }
+
     pub fn center(&self) -> Point {
         Point::new(
             self.origin.x + self.size.width / 2,
             self.origin.y + self.size.height / 2,
         )
     }
+
+    /// Returns whether the point hits the rectangle, excluding the right and bottom edges.
+    #[inline]
+    pub fn contains_point(&self, point: Point) -> bool {
+        point.is_within(*self)
+    }
+
+    /// Returns the nearest point within the rectangle, or the origin if it is empty.
+    pub fn clamp_point(&self, point: Point) -> Point {
+        match Coordinates::from_rect(*self) {
+            Ok(coords) => Point::new(
+                point.x.clamp(coords.left, coords.right - 1),
+                point.y.clamp(coords.top, coords.bottom - 1),
+            ),
+            Err(_) => self.origin,
+        }
+    }
+
+    /// Returns the top-left, top-right, bottom-right and bottom-left pixels of the rectangle.
+    pub fn corners(&self) -> [Point; 4] {
+        let left = self.origin.x;
+        let top = self.origin.y;
+        let right = left + isize::max(self.size.width, 1) - 1;
+        let bottom = top + isize::max(self.size.height, 1) - 1;
+        [
+            Point::new(left, top),
+            Point::new(right, top),
+            Point::new(right, bottom),
+            Point::new(left, bottom),
+        ]
+    }
 }
 
 impl From<Size> for Rect {
@@ -384,16 +579,7 @@ impl Sub<Size> for Rect {
 impl Add<EdgeInsets> for Rect {
     type Output = Self;
     fn add(self, rhs: EdgeInsets) -> Self::Output {
-        Rect {
-            origin: Point {
-                x: self.origin.x - rhs.left,
-                y: self.origin.y - rhs.top,
-            },
-            size: Size {
-                width: self.size.width + (rhs.left + rhs.right),
-                height: self.size.height + (rhs.top + rhs.bottom),
-            },
-        }
+        self.inflate(rhs)
     }
 }
 
@@ -469,12 +655,18 @@ impl Coordinates {
         }
     }
 
+    /// Converts a rect to coordinates, a negative width or height extends to the left or top.
     #[inline]
-    pub fn from_rect(rect: Rect) -> Result<Coordinates, ()> {
+    pub fn from_rect(rect: Rect) -> Result<Coordinates, CoordsError> {
         if rect.size.width == 0 || rect.size.height == 0 {
-            Err(())
+            Err(CoordsError::EmptyRect)
         } else {
-            Ok(unsafe { Self::from_rect_unchecked(rect) })
+            let coords = unsafe { Self::from_rect_unchecked(rect) };
+            if coords.left < coords.right && coords.top < coords.bottom {
+                Ok(coords)
+            } else {
+                Err(CoordsError::Overflow)
+            }
         }
     }
 
@@ -484,20 +676,20 @@ impl Coordinates {
         let right: isize;
         if rect.size.width > 0 {
             left = rect.origin.x;
-            right = left + rect.size.width;
+            right = left.saturating_add(rect.size.width);
         } else {
             right = rect.origin.x;
-            left = right + rect.size.width;
+            left = right.saturating_add(rect.size.width);
         }
 
         let top: isize;
         let bottom: isize;
         if rect.size.height > 0 {
             top = rect.origin.y;
-            bottom = top + rect.size.height;
+            bottom = top.saturating_add(rect.size.height);
         } else {
             bottom = rect.origin.y;
-            top = bottom + rect.size.height;
+            top = bottom.saturating_add(rect.size.height);
         }
 
         Self {
@@ -524,12 +716,21 @@ impl AddAssign for Coordinates {
 }
 
 impl TryFrom<Rect> for Coordinates {
-    type Error = ();
+    type Error = CoordsError;
     fn try_from(value: Rect) -> Result<Self, Self::Error> {
         Self::from_rect(value)
     }
 }
 
+/// The reason why a `Rect` cannot be converted to `Coordinates`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CoordsError {
+    /// The width or height is zero
+    EmptyRect,
+    /// Nothing is left after saturating the right or bottom edge to `isize::MAX`
+    Overflow,
+}
+
 impl From<Coordinates> for Rect {
     fn from(coods: Coordinates) -> Rect {
         Rect {
@@ -559,15 +760,21 @@ impl EdgeInsets {
         }
     }
 
+    /// Creates insets with the same value on all sides.
     #[inline]
-    pub const fn padding_each(value: isize) -> Self {
+    pub const fn uniform(d: isize) -> Self {
         Self {
-            top: value,
-            left: value,
-            bottom: value,
-            right: value,
+            top: d,
+            left: d,
+            bottom: d,
+            right: d,
         }
     }
+
+    #[inline]
+    pub const fn padding_each(value: isize) -> Self {
+        Self::uniform(value)
+    }
 }
 
 impl Add for EdgeInsets {
@@ -615,3 +822,201 @@ impl SubAssign for EdgeInsets {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn line_to_clipped() {
+        let clip = Rect::new(0, 0, 10, 10);
+        let clipped = |c0: Point, c1: Point| {
+            let mut points = Vec::new();
+            c0.line_to_clipped(c1, clip, |p| points.push(p));
+            points
+        };
+        let filtered = |c0: Point, c1: Point| {
+            let mut points = Vec::new();
+            c0.line_to(c1, |p| {
+                if p.is_within(clip) {
+                    points.push(p)
+                }
+            });
+            points
+        };
+
+        // a long diagonal line passing through the corner of the clip
+        let points = clipped(Point::new(-1000, -995), Point::new(1000, 1005));
+        assert_eq!(
+            points,
+            filtered(Point::new(-1000, -995), Point::new(1000, 1005))
+        );
+        assert_eq!(points.len(), 5);
+
+        // a shallow line
+        let points = clipped(Point::new(-5000, 2), Point::new(5000, 7));
+        assert_eq!(points, filtered(Point::new(-5000, 2), Point::new(5000, 7)));
+        assert_eq!(points.len(), 10);
+
+        // the same pixels as the unclipped line in every direction
+        for c0 in [(-7, 3), (4, -13), (15, 6), (2, 21), (5, 5), (-3, -3)] {
+            for c1 in [(12, 9), (-9, 1), (3, 17), (8, -6), (5, 5), (20, 20), (9, 0)] {
+                let c0 = Point::new(c0.0, c0.1);
+                let c1 = Point::new(c1.0, c1.1);
+                assert_eq!(clipped(c0, c1), filtered(c0, c1), "{:?} {:?}", c0, c1);
+                assert_eq!(clipped(c1, c0), filtered(c1, c0), "{:?} {:?}", c1, c0);
+            }
+        }
+
+        // lines outside of the clip
+        Point::new(-100, -10).line_to_clipped(Point::new(100, -1), clip, |_| unreachable!());
+        Point::new(-20, 5).line_to_clipped(Point::new(5, 30), clip, |_| unreachable!());
+        Point::new(0, 0).line_to_clipped(
+            Point::new(9, 9),
+            Rect::new(0, 0, 0, 0),
+            |_| unreachable!(),
+        );
+    }
+
+    #[test]
+    fn saturating() {
+        let point = Point::new(isize::MAX - 1, isize::MIN + 1);
+        assert_eq!(
+            point.saturating_add(Point::new(10, -10)),
+            Point::new(isize::MAX, isize::MIN)
+        );
+        assert_eq!(
+            point.saturating_sub(Point::new(-10, 10)),
+            Point::new(isize::MAX, isize::MIN)
+        );
+
+        let size = Size::new(isize::MAX, 1);
+        assert_eq!(size.saturating_add(size), Size::new(isize::MAX, 2));
+
+        let rect = Rect::new(100, 100, isize::MAX, isize::MAX);
+        assert_eq!(rect.saturating_max(), Point::new(isize::MAX, isize::MAX));
+        let coords = Coordinates::from_rect(rect).unwrap();
+        assert_eq!(coords.right, isize::MAX);
+        assert_eq!(coords.bottom, isize::MAX);
+    }
+
+    #[test]
+    fn inset() {
+        let rect = Rect::new(10, 20, 100, 50);
+        assert_eq!(rect.inset(5), Rect::new(15, 25, 90, 40));
+        assert_eq!(rect.inset(-5), Rect::new(5, 15, 110, 60));
+        assert_eq!(rect.inset(0), rect);
+
+        let over = rect.inset(30);
+        assert_eq!(over.width(), 40);
+        assert_eq!(over.height(), 0);
+        assert_eq!(over.y(), 45);
+
+        let over = rect.inset(60);
+        assert_eq!(over, Rect::new(60, 45, 0, 0));
+
+        assert_eq!(rect.offset(3, -4), Rect::new(13, 16, 100, 50));
+    }
+
+    #[test]
+    fn edge_insets() {
+        let status_bar = EdgeInsets::new(24, 0, 0, 0);
+        let margin = EdgeInsets::uniform(4);
+        let insets = status_bar + margin;
+        assert_eq!(insets, EdgeInsets::new(28, 4, 4, 4));
+        assert_eq!(insets - margin, status_bar);
+
+        let rect = Rect::new(0, 0, 640, 480);
+        let deflated = rect.deflate(insets);
+        assert_eq!(deflated, Rect::new(4, 28, 632, 448));
+        assert_eq!(deflated.inflate(insets), rect);
+        assert_eq!(rect - insets, deflated);
+        assert_eq!(deflated + insets, rect);
+    }
+
+    #[test]
+    fn coords_error() {
+        assert_eq!(
+            Coordinates::from_rect(Rect::new(10, 10, 0, 5)),
+            Err(CoordsError::EmptyRect)
+        );
+        assert_eq!(
+            Coordinates::try_from(Rect::new(10, 10, 5, 0)),
+            Err(CoordsError::EmptyRect)
+        );
+        // negative sizes are normalized
+        assert_eq!(
+            Coordinates::from_rect(Rect::new(10, 10, -5, 5)),
+            Ok(Coordinates::new(5, 10, 10, 15))
+        );
+        assert_eq!(
+            Coordinates::from_rect(Rect::new(10, 10, 5, -5)),
+            Ok(Coordinates::new(10, 5, 15, 10))
+        );
+        assert_eq!(
+            Coordinates::from_rect(Rect::new(isize::MAX, 0, 10, 10)),
+            Err(CoordsError::Overflow)
+        );
+        assert_eq!(
+            Coordinates::from_rect(Rect::new(10, 10, 5, 5)),
+            Ok(Coordinates::new(10, 10, 15, 15))
+        );
+    }
+
+    #[test]
+    fn intersection() {
+        let bounds = Rect::new(0, 0, 100, 50);
+        assert_eq!(
+            bounds.intersection(Rect::new(-10, 10, 200, 10)),
+            Some(Rect::new(0, 10, 100, 10))
+        );
+        assert_eq!(
+            bounds.intersection(Rect::new(90, 40, isize::MAX, isize::MAX)),
+            Some(Rect::new(90, 40, 10, 10))
+        );
+        assert_eq!(
+            bounds.intersection(Rect::new(isize::MIN, isize::MIN, isize::MAX, isize::MAX)),
+            None
+        );
+        assert_eq!(bounds.intersection(Rect::new(100, 0, 10, 10)), None);
+        assert_eq!(bounds.intersection(Rect::new(10, 10, -5, 5)), None);
+    }
+
+    #[test]
+    fn hit_test() {
+        let rect = Rect::new(10, 20, 30, 40);
+
+        // inside
+        assert!(rect.contains_point(Point::new(25, 30)));
+        assert_eq!(rect.clamp_point(Point::new(25, 30)), Point::new(25, 30));
+
+        // on the edges
+        assert!(rect.contains_point(Point::new(10, 20)));
+        assert!(rect.contains_point(Point::new(39, 59)));
+        assert!(!rect.contains_point(Point::new(40, 30)));
+        assert!(!rect.contains_point(Point::new(25, 60)));
+        assert_eq!(rect.clamp_point(Point::new(40, 60)), Point::new(39, 59));
+
+        // outside
+        assert!(!rect.contains_point(Point::new(9, 30)));
+        assert!(!rect.contains_point(Point::new(-100, -100)));
+        assert_eq!(rect.clamp_point(Point::new(-100, 30)), Point::new(10, 30));
+        assert_eq!(rect.clamp_point(Point::new(100, -100)), Point::new(39, 20));
+
+        assert_eq!(
+            rect.corners(),
+            [
+                Point::new(10, 20),
+                Point::new(39, 20),
+                Point::new(39, 59),
+                Point::new(10, 59),
+            ]
+        );
+        assert!(rect.corners().iter().all(|&p| rect.contains_point(p)));
+
+        let empty = Rect::new(5, 5, 0, 10);
+        assert!(!empty.contains_point(Point::new(5, 5)));
+        assert_eq!(empty.clamp_point(Point::new(100, 100)), Point::new(5, 5));
+    }
+}
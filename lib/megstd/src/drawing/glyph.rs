@@ -0,0 +1,242 @@
+// Glyph Cache
+
+use super::*;
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// A pre-rendered 1bpp glyph, in the format of `RasterFontWriter::draw_font`
+pub struct GlyphMask {
+    size: Size,
+    data: Box<[u8]>,
+}
+
+impl GlyphMask {
+    /// Renders a glyph into a mask, every pixel drawn by `f` in a non-black color is set.
+    pub fn render<F>(size: Size, f: F) -> Self
+    where
+        F: FnOnce(&mut Bitmap),
+    {
+        let width = isize::max(size.width(), 0) as usize;
+        let height = isize::max(size.height(), 0) as usize;
+        let size = Size::new(width as isize, height as isize);
+
+        let mut buf = vec![IndexedColor::BLACK; width * height];
+        {
+            let mut bitmap8 = Bitmap8::from_slice(&mut buf, size, width);
+            let mut bitmap = Bitmap::from(&mut bitmap8);
+            f(&mut bitmap);
+        }
+
+        let stride = width.div_ceil(8);
+        let mut data = vec![0u8; stride * height];
+        for (y, row) in buf.chunks(usize::max(width, 1)).enumerate() {
+            for (x, pixel) in row.iter().enumerate() {
+                if *pixel != IndexedColor::BLACK {
+                    data[y * stride + x / 8] |= 0x80 >> (x & 7);
+                }
+            }
+        }
+
+        Self {
+            size,
+            data: data.into_boxed_slice(),
+        }
+    }
+
+    #[inline]
+    pub const fn size(&self) -> Size {
+        self.size
+    }
+
+    #[inline]
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    #[inline]
+    pub fn draw(&self, bitmap: &mut Bitmap, origin: Point, color: AmbiguousColor) {
+        bitmap.draw_font(&self.data, self.size, origin, color);
+    }
+}
+
+/// Pre-rendered glyphs keyed by the character and the size, evicted in LRU order
+pub struct GlyphCache {
+    entries: Vec<GlyphCacheEntry>,
+    capacity: usize,
+    tick: u64,
+    hits: usize,
+    misses: usize,
+}
+
+struct GlyphCacheEntry {
+    character: char,
+    height: isize,
+    mask: GlyphMask,
+    last_used: u64,
+}
+
+impl GlyphCache {
+    #[inline]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+            capacity,
+            tick: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the number of lookups that found the glyph in the cache.
+    #[inline]
+    pub const fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Returns the number of lookups that had to render the glyph.
+    #[inline]
+    pub const fn misses(&self) -> usize {
+        self.misses
+    }
+
+    /// Returns the cached glyph, or renders it with `render` and caches it,
+    /// evicting the least recently used glyph if the cache is full.
+    ///
+    /// Returns `None` if the capacity of the cache is zero.
+    pub fn get_or_render<F>(
+        &mut self,
+        character: char,
+        height: isize,
+        render: F,
+    ) -> Option<&GlyphMask>
+    where
+        F: FnOnce() -> GlyphMask,
+    {
+        if self.capacity == 0 {
+            return None;
+        }
+        self.tick += 1;
+        let index = match self
+            .entries
+            .iter()
+            .position(|v| v.character == character && v.height == height)
+        {
+            Some(index) => {
+                self.hits += 1;
+                index
+            }
+            None => {
+                self.misses += 1;
+                let entry = GlyphCacheEntry {
+                    character,
+                    height,
+                    mask: render(),
+                    last_used: 0,
+                };
+                if self.entries.len() < self.capacity {
+                    self.entries.push(entry);
+                    self.entries.len() - 1
+                } else {
+                    let index = self
+                        .entries
+                        .iter()
+                        .enumerate()
+                        .min_by_key(|(_, v)| v.last_used)
+                        .map(|(index, _)| index)?;
+                    self.entries[index] = entry;
+                    index
+                }
+            }
+        };
+        let entry = &mut self.entries[index];
+        entry.last_used = self.tick;
+        Some(&entry.mask)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+
+    #[test]
+    fn glyph_mask() {
+        let mask = GlyphMask::render(Size::new(10, 2), |bitmap| {
+            bitmap.fill_rect(Rect::new(1, 0, 8, 1), IndexedColor::WHITE.into());
+            bitmap.set_pixel(Point::new(9, 1), IndexedColor::RED.into());
+        });
+        assert_eq!(mask.size(), Size::new(10, 2));
+        assert_eq!(mask.data(), [0x7F, 0x80, 0x00, 0x40]);
+
+        let mut buf = vec![IndexedColor::BLACK; 12 * 3];
+        let mut bitmap8 = Bitmap8::from_slice(&mut buf, Size::new(12, 3), 12);
+        let mut bitmap = Bitmap::from(&mut bitmap8);
+        mask.draw(&mut bitmap, Point::new(1, 1), IndexedColor::BLUE.into());
+        let drawn = buf
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| **v == IndexedColor::BLUE)
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        assert_eq!(drawn, [14, 15, 16, 17, 18, 19, 20, 21, 34]);
+    }
+
+    #[test]
+    fn glyph_cache() {
+        let renders = Cell::new(0);
+        let mut cache = GlyphCache::new(2);
+        let lookup = |cache: &mut GlyphCache, character, height| {
+            cache
+                .get_or_render(character, height, || {
+                    renders.set(renders.get() + 1);
+                    GlyphMask::render(Size::new(height / 2, height), |bitmap| {
+                        bitmap.fill_rect(bitmap.bounds(), IndexedColor::WHITE.into())
+                    })
+                })
+                .map(|mask| mask.size())
+        };
+
+        // the second time hits the cache
+        assert_eq!(lookup(&mut cache, 'A', 16), Some(Size::new(8, 16)));
+        assert_eq!((cache.hits(), cache.misses()), (0, 1));
+        assert_eq!(lookup(&mut cache, 'A', 16), Some(Size::new(8, 16)));
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+        assert_eq!(renders.get(), 1);
+
+        // a different size is a different glyph
+        assert_eq!(lookup(&mut cache, 'A', 8), Some(Size::new(4, 8)));
+        assert_eq!((cache.hits(), cache.misses()), (1, 2));
+        assert_eq!(cache.len(), 2);
+
+        // the least recently used glyph is evicted
+        lookup(&mut cache, 'A', 16);
+        lookup(&mut cache, 'B', 16);
+        assert_eq!(cache.len(), 2);
+        assert_eq!((cache.hits(), cache.misses()), (2, 3));
+        lookup(&mut cache, 'A', 16);
+        assert_eq!((cache.hits(), cache.misses()), (3, 3));
+        lookup(&mut cache, 'A', 8);
+        assert_eq!((cache.hits(), cache.misses()), (3, 4));
+        assert_eq!(renders.get(), 4);
+
+        let mut cache = GlyphCache::new(0);
+        assert!(lookup(&mut cache, 'A', 16).is_none());
+        assert!(cache.is_empty());
+    }
+}
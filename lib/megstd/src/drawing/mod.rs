@@ -2,7 +2,9 @@
 mod bitmap;
 mod color;
 mod coords;
+mod glyph;
 
 pub use bitmap::*;
 pub use color::*;
 pub use coords::*;
+pub use glyph::*;
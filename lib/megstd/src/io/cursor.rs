@@ -0,0 +1,145 @@
+// Cursor
+// Most of them are clones of Rust's original definition.
+
+use super::*;
+use core::convert::TryFrom;
+
+/// Wraps an in-memory buffer and provides it with a seekable `Read` implementation.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Cursor<T> {
+    inner: T,
+    pos: u64,
+}
+
+impl<T> Cursor<T> {
+    #[inline]
+    pub const fn new(inner: T) -> Self {
+        Self { inner, pos: 0 }
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    #[inline]
+    pub const fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    #[inline]
+    pub const fn position(&self) -> u64 {
+        self.pos
+    }
+
+    #[inline]
+    pub fn set_position(&mut self, pos: u64) {
+        self.pos = pos;
+    }
+}
+
+impl<T: AsRef<[u8]>> Cursor<T> {
+    /// Returns the rest of the buffer after the current position.
+    #[inline]
+    fn remaining_slice(&self) -> &[u8] {
+        let slice = self.inner.as_ref();
+        // a position beyond the address space must not wrap around on 32-bit targets
+        let pos = usize::try_from(self.pos).map_or(slice.len(), |p| p.min(slice.len()));
+        &slice[pos..]
+    }
+}
+
+impl<T: AsRef<[u8]>> Read for Cursor<T> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let remaining = self.remaining_slice();
+        let len = usize::min(buf.len(), remaining.len());
+        buf[..len].copy_from_slice(&remaining[..len]);
+        self.pos += len as u64;
+        Ok(len)
+    }
+}
+
+impl<T: AsRef<[u8]>> Seek for Cursor<T> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(v) => {
+                self.pos = v;
+                return Ok(v);
+            }
+            SeekFrom::End(v) => (self.inner.as_ref().len() as u64, v),
+            SeekFrom::Current(v) => (self.pos, v),
+        };
+        let new_pos = if offset >= 0 {
+            base.checked_add(offset as u64)
+        } else {
+            base.checked_sub(offset.wrapping_neg() as u64)
+        };
+        match new_pos {
+            Some(v) => {
+                self.pos = v;
+                Ok(v)
+            }
+            None => Err(ErrorKind::InvalidInput.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_slice() {
+        let data = [1u8, 2, 3, 4, 5];
+        let mut cursor = Cursor::new(&data[..]);
+        let mut buf = [0u8; 3];
+
+        assert_eq!(cursor.read(&mut buf).unwrap(), 3);
+        assert_eq!(buf, [1, 2, 3]);
+        assert_eq!(cursor.position(), 3);
+
+        // reading across the end is short, then returns 0
+        assert_eq!(cursor.read(&mut buf).unwrap(), 2);
+        assert_eq!(buf[..2], [4, 5]);
+        assert_eq!(cursor.read(&mut buf).unwrap(), 0);
+        assert_eq!(cursor.position(), 5);
+
+        cursor.set_position(100);
+        assert_eq!(cursor.read(&mut buf).unwrap(), 0);
+        assert_eq!(cursor.read(&mut []).unwrap(), 0);
+        cursor.set_position(u64::MAX);
+        assert_eq!(cursor.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn seek_slice() {
+        let data = [10u8, 11, 12, 13, 14, 15];
+        let mut cursor = Cursor::new(&data[..]);
+        let mut buf = [0u8; 2];
+
+        assert_eq!(cursor.seek(SeekFrom::Start(2)).unwrap(), 2);
+        cursor.read(&mut buf).unwrap();
+        assert_eq!(buf, [12, 13]);
+
+        assert_eq!(cursor.seek(SeekFrom::Current(-3)).unwrap(), 1);
+        cursor.read(&mut buf).unwrap();
+        assert_eq!(buf, [11, 12]);
+
+        assert_eq!(cursor.seek(SeekFrom::End(-1)).unwrap(), 5);
+        assert_eq!(cursor.read(&mut buf).unwrap(), 1);
+        assert_eq!(buf[0], 15);
+
+        // beyond the end is allowed, before the start is not
+        assert_eq!(cursor.seek(SeekFrom::End(4)).unwrap(), 10);
+        assert_eq!(cursor.read(&mut buf).unwrap(), 0);
+        let error = cursor.seek(SeekFrom::Current(-11)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        assert_eq!(cursor.position(), 10);
+        assert_eq!(cursor.stream_position().unwrap(), 10);
+    }
+}
@@ -26,6 +26,7 @@ pub enum ErrorKind {
     Interrupted,
     Other,
     UnexpectedEof,
+    OutOfMemory,
 }
 
 pub struct Error {
@@ -42,6 +43,8 @@ impl fmt::Debug for Error {
 pub enum Repr {
     Os(i32),
     Simple(ErrorKind),
+    /// An error at the byte offset of the source, reported by parsers
+    Offset(ErrorKind, usize),
     Custom(Box<Custom>),
 }
 
@@ -64,6 +67,24 @@ impl Error {
         }
     }
 
+    /// Creates an error that occurred at `offset` bytes into the source.
+    #[inline]
+    pub const fn with_offset(kind: ErrorKind, offset: usize) -> Self {
+        Self {
+            repr: Repr::Offset(kind, offset),
+        }
+    }
+
+    /// Returns the byte offset of the source where the error occurred, if known.
+    #[inline]
+    pub fn offset(&self) -> Option<usize> {
+        if let Repr::Offset(_, offset) = self.repr {
+            Some(offset)
+        } else {
+            None
+        }
+    }
+
     pub fn last_os_error() -> Self {
         todo!()
     }
@@ -110,6 +131,7 @@ impl Error {
         match self.repr {
             Repr::Os(_) => todo!(),
             Repr::Simple(kind) => kind,
+            Repr::Offset(kind, _) => kind,
             Repr::Custom(ref v) => v.kind,
         }
     }
@@ -122,3 +144,35 @@ impl From<ErrorKind> for Error {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_offset() {
+        let error = Error::with_offset(ErrorKind::InvalidData, 0x76);
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(error.offset(), Some(0x76));
+        assert!(error.get_ref().is_none());
+
+        let error = Error::from(ErrorKind::UnexpectedEof);
+        assert_eq!(error.offset(), None);
+
+        for (kind, offset) in [
+            (ErrorKind::PermissionDenied, 0),
+            (ErrorKind::AlreadyExists, 110),
+            (ErrorKind::OutOfMemory, usize::MAX),
+        ]
+        .iter()
+        {
+            let error = Error::with_offset(*kind, *offset);
+            match error.kind() {
+                ErrorKind::PermissionDenied => assert_eq!(error.offset(), Some(0)),
+                ErrorKind::AlreadyExists => assert_eq!(error.offset(), Some(110)),
+                ErrorKind::OutOfMemory => assert_eq!(error.offset(), Some(usize::MAX)),
+                _ => unreachable!(),
+            }
+        }
+    }
+}
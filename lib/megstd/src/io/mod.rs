@@ -1,4 +1,6 @@
+mod cursor;
 mod error;
+pub use cursor::*;
 pub use error::*;
 pub type Result<T> = core::result::Result<T, Error>;
 
@@ -19,3 +21,21 @@ pub trait Write {
 
     //fn write_all(&mut self, buf: &[u8]) -> Result<()>
 }
+
+/// Enumeration of possible methods to seek within an I/O object.
+#[derive(Copy, PartialEq, Eq, Clone, Debug)]
+pub enum SeekFrom {
+    Start(u64),
+    End(i64),
+    Current(i64),
+}
+
+pub trait Seek {
+    /// Seeks to an offset, in bytes, and returns the new position from the start.
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64>;
+
+    #[inline]
+    fn stream_position(&mut self) -> Result<u64> {
+        self.seek(SeekFrom::Current(0))
+    }
+}
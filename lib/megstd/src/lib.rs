@@ -9,6 +9,10 @@ pub mod error;
 pub mod fs;
 pub mod io;
 pub mod path;
+pub mod string;
+pub mod sync;
 pub mod sys;
 
 extern crate alloc;
+#[cfg(test)]
+extern crate std;
@@ -0,0 +1,221 @@
+// Small String Buffer & Formatter
+
+use alloc::vec::Vec;
+use core::{fmt, slice, str};
+use fmt::Error;
+
+/// Small String Buffer (NO MORE ALLOC)
+pub struct Sb255([u8; 256]);
+
+impl Sb255 {
+    pub const CAPACITY: usize = 255;
+
+    #[inline]
+    pub const fn new() -> Self {
+        Self([0; 256])
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.0[0] = 0;
+    }
+
+    /// Removes the last character. Does nothing if the buffer is empty.
+    #[inline]
+    pub fn backspace(&mut self) {
+        if let Some(c) = self.as_str().chars().next_back() {
+            self.0[0] = (self.len() - c.len_utf8()) as u8;
+        }
+    }
+
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.0[0] as usize
+    }
+
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of bytes that can still be written.
+    #[inline]
+    pub const fn remaining(&self) -> usize {
+        Self::CAPACITY - self.len()
+    }
+
+    /// SAFETY: This method does not strictly conform to Rust's ownership and lifetime philosophy
+    #[inline]
+    pub fn as_str<'a>(&self) -> &'a str {
+        unsafe { str::from_utf8_unchecked(slice::from_raw_parts(&self.0[1], self.len())) }
+    }
+}
+
+impl Default for Sb255 {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Write for Sb255 {
+    /// Appends a string, or returns `Err` without writing anything if it does not fit.
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let len = self.len();
+        if s.len() > self.remaining() {
+            return Err(Error);
+        }
+        self.0[1 + len..1 + len + s.len()].copy_from_slice(s.as_bytes());
+        self.0[0] = (len + s.len()) as u8;
+        Ok(())
+    }
+
+    #[inline]
+    fn write_char(&mut self, c: char) -> core::fmt::Result {
+        self.write_str(c.encode_utf8(&mut [0; 4]))
+    }
+}
+
+pub struct StringBuffer {
+    vec: Vec<u8>,
+    start_index: usize,
+}
+
+impl StringBuffer {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            vec: Vec::new(),
+            start_index: 0,
+        }
+    }
+
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            vec: Vec::with_capacity(capacity),
+            start_index: 0,
+        }
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.start_index = 0;
+        self.vec.clear()
+    }
+
+    #[inline]
+    pub fn split(&mut self) {
+        self.start_index = self.vec.len();
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.vec.len() - self.start_index
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes the last character. Does nothing if the buffer is empty.
+    #[inline]
+    pub fn backspace(&mut self) {
+        if let Some(c) = self.as_str().chars().next_back() {
+            self.vec.truncate(self.vec.len() - c.len_utf8());
+        }
+    }
+
+    /// SAFETY: This method does not strictly conform to Rust's ownership and lifetime philosophy
+    #[inline]
+    pub fn as_str<'a>(&self) -> &'a str {
+        match self.len() {
+            0 => "",
+            len => unsafe {
+                str::from_utf8_unchecked(slice::from_raw_parts(&self.vec[self.start_index], len))
+            },
+        }
+    }
+}
+
+impl Default for StringBuffer {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Write for StringBuffer {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        for c in s.bytes() {
+            self.vec.push(c);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::fmt::Write;
+
+    #[test]
+    fn sb255_overflow() {
+        let mut sb = Sb255::new();
+        assert_eq!(sb.remaining(), Sb255::CAPACITY);
+
+        sb.backspace();
+        assert!(sb.is_empty());
+
+        for _ in 0..Sb255::CAPACITY {
+            sb.write_char('a').unwrap();
+        }
+        assert_eq!(sb.len(), 255);
+        assert_eq!(sb.remaining(), 0);
+        assert!(sb.as_str().bytes().all(|v| v == b'a'));
+
+        assert!(sb.write_char('b').is_err());
+        assert!(sb.write_str("bc").is_err());
+        assert_eq!(sb.len(), 255);
+
+        sb.backspace();
+        assert_eq!(sb.remaining(), 1);
+        assert!(sb.write_char('\u{3042}').is_err());
+        assert_eq!(sb.len(), 254);
+        sb.write_char('z').unwrap();
+        assert_eq!(sb.len(), 255);
+        assert!(sb.as_str().ends_with("az"));
+
+        sb.clear();
+        assert!(sb.is_empty());
+        for _ in 0..Sb255::CAPACITY {
+            sb.backspace();
+        }
+        assert!(sb.is_empty());
+    }
+
+    #[test]
+    fn backspace_utf8() {
+        let mut sb = Sb255::new();
+        write!(sb, "a\u{e9}\u{3042}\u{1F600}").unwrap();
+        assert_eq!(sb.len(), 10);
+        sb.backspace();
+        assert_eq!(sb.as_str(), "a\u{e9}\u{3042}");
+        sb.backspace();
+        assert_eq!(sb.as_str(), "a\u{e9}");
+        sb.backspace();
+        assert_eq!(sb.as_str(), "a");
+        sb.backspace();
+        assert_eq!(sb.as_str(), "");
+
+        let mut sb = StringBuffer::new();
+        sb.write_str("x").unwrap();
+        sb.split();
+        sb.write_str("\u{3042}").unwrap();
+        sb.backspace();
+        assert_eq!(sb.as_str(), "");
+        sb.backspace();
+        assert_eq!(sb.len(), 0);
+    }
+}
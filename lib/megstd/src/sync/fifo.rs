@@ -0,0 +1,402 @@
+// First In First Out Simple Ring Buffer
+
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::sync::atomic::*;
+
+pub struct Fifo<T>
+where
+    T: Sized + Copy,
+{
+    vec: Vec<UnsafeCell<T>>,
+    capacity: usize,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+}
+
+impl<T> Fifo<T>
+where
+    T: Sized + Default + Copy,
+{
+    /// Creates a new fifo. One slot of the ring buffer is kept empty,
+    /// so it can hold up to `capacity - 1` items.
+    #[track_caller]
+    pub fn new(capacity: usize) -> Self {
+        if !capacity.is_power_of_two() {
+            panic!(
+                "the expected capacity is a power of 2, but the actual capacity is {}",
+                capacity
+            );
+        }
+        Self::with_backing_store(capacity, capacity - 1)
+    }
+
+    /// Creates a new fifo that holds exactly `capacity` items.
+    ///
+    /// The backing store is rounded up to the next power of two.
+    pub fn with_exact_capacity(capacity: usize) -> Self {
+        let backing = (capacity + 1).next_power_of_two();
+        Self::with_backing_store(backing, capacity)
+    }
+
+    #[inline]
+    fn with_backing_store(len: usize, capacity: usize) -> Self {
+        let mut vec = Vec::with_capacity(len);
+        vec.resize_with(len, || UnsafeCell::new(T::default()));
+
+        Self {
+            vec,
+            capacity,
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    #[inline]
+    pub fn mask(&self) -> usize {
+        self.vec.len() - 1
+    }
+
+    /// Returns the maximum number of items this fifo can hold.
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tail
+            .load(Ordering::SeqCst)
+            .wrapping_sub(self.head.load(Ordering::SeqCst))
+            & self.mask()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::SeqCst) == self.tail.load(Ordering::SeqCst)
+    }
+
+    /// # Safety
+    ///
+    /// Thread unsafe, only one producer may enqueue at a time.
+    pub unsafe fn enqueue(&self, data: T) -> Result<(), T> {
+        let old_tail = self.tail.load(Ordering::SeqCst);
+        let new_tail = (old_tail + 1) & self.mask();
+        if self.len() >= self.capacity {
+            Err(data)
+        } else {
+            let p = self.vec.get_unchecked(old_tail).get();
+            p.write_volatile(data);
+            self.tail.store(new_tail, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    /// Returns the head item without removing it.
+    ///
+    /// # Safety
+    ///
+    /// Thread unsafe, no consumer may dequeue at the same time.
+    pub unsafe fn peek(&self) -> Option<T> {
+        if self.is_empty() {
+            None
+        } else {
+            let head = self.head.load(Ordering::SeqCst);
+            Some(self.vec.get_unchecked(head).get().read_volatile())
+        }
+    }
+
+    /// # Safety
+    ///
+    /// Thread unsafe, only one consumer may dequeue at a time.
+    pub unsafe fn dequeue(&self) -> Option<T> {
+        if self.is_empty() {
+            None
+        } else {
+            let head = self.head.load(Ordering::SeqCst);
+            let p = self.vec.get_unchecked(head).get();
+            let r = p.read_volatile();
+            self.head.store((head + 1) & self.mask(), Ordering::SeqCst);
+            Some(r)
+        }
+    }
+
+    /// Removes the items in the fifo at the time of the call, in order.
+    ///
+    /// Items enqueued after the call are left for the next pass.
+    /// Items not consumed by the iterator remain in the fifo.
+    pub fn drain(&mut self) -> impl Iterator<Item = T> + '_ {
+        let tail = self.tail.load(Ordering::SeqCst);
+        core::iter::from_fn(move || {
+            let head = self.head.load(Ordering::SeqCst);
+            if head == tail {
+                None
+            } else {
+                let r = unsafe { self.vec.get_unchecked(head).get().read_volatile() };
+                self.head.store((head + 1) & self.mask(), Ordering::SeqCst);
+                Some(r)
+            }
+        })
+    }
+}
+
+/// A bounded multi-producer multi-consumer queue.
+///
+/// Each slot carries a sequence number, and producers and consumers claim
+/// slots by compare-and-swap on `tail` and `head`, so no interrupt masking
+/// is needed.
+pub struct InterlockedFifo<T>
+where
+    T: Sized + Copy,
+{
+    vec: Vec<InterlockedSlot<T>>,
+    capacity: usize,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+}
+
+struct InterlockedSlot<T> {
+    seq: AtomicUsize,
+    data: UnsafeCell<T>,
+}
+
+impl<T> InterlockedFifo<T>
+where
+    T: Sized + Default + Copy,
+{
+    /// Creates a new fifo that can hold up to `capacity - 1` items, as does `Fifo::new`.
+    #[track_caller]
+    pub fn new(capacity: usize) -> Self {
+        if !capacity.is_power_of_two() {
+            panic!(
+                "the expected capacity is a power of 2, but the actual capacity is {}",
+                capacity
+            );
+        }
+        Self::with_backing_store(capacity, capacity - 1)
+    }
+
+    /// Creates a new fifo that holds exactly `capacity` items.
+    pub fn with_exact_capacity(capacity: usize) -> Self {
+        Self::with_backing_store(capacity.next_power_of_two(), capacity)
+    }
+
+    #[inline]
+    fn with_backing_store(len: usize, capacity: usize) -> Self {
+        let mut vec = Vec::with_capacity(len);
+        for index in 0..len {
+            vec.push(InterlockedSlot {
+                seq: AtomicUsize::new(index),
+                data: UnsafeCell::new(T::default()),
+            });
+        }
+
+        Self {
+            vec,
+            capacity,
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    #[inline]
+    fn mask(&self) -> usize {
+        self.vec.len() - 1
+    }
+
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire) == self.tail.load(Ordering::Acquire)
+    }
+
+    pub fn enqueue(&self, data: T) -> Result<(), T> {
+        let mut pos = self.tail.load(Ordering::Relaxed);
+        let slot = loop {
+            let slot = unsafe { self.vec.get_unchecked(pos & self.mask()) };
+            let seq = slot.seq.load(Ordering::Acquire);
+            let diff = seq.wrapping_sub(pos) as isize;
+            if diff == 0 {
+                if pos.wrapping_sub(self.head.load(Ordering::Acquire)) >= self.capacity {
+                    return Err(data);
+                }
+                match self.tail.compare_exchange_weak(
+                    pos,
+                    pos.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => break slot,
+                    Err(v) => pos = v,
+                }
+            } else if diff < 0 {
+                return Err(data);
+            } else {
+                pos = self.tail.load(Ordering::Relaxed);
+            }
+        };
+        unsafe {
+            slot.data.get().write_volatile(data);
+        }
+        slot.seq.store(pos.wrapping_add(1), Ordering::Release);
+        Ok(())
+    }
+
+    pub fn dequeue(&self) -> Option<T> {
+        let mut pos = self.head.load(Ordering::Relaxed);
+        let slot = loop {
+            let slot = unsafe { self.vec.get_unchecked(pos & self.mask()) };
+            let seq = slot.seq.load(Ordering::Acquire);
+            let diff = seq.wrapping_sub(pos.wrapping_add(1)) as isize;
+            if diff == 0 {
+                match self.head.compare_exchange_weak(
+                    pos,
+                    pos.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => break slot,
+                    Err(v) => pos = v,
+                }
+            } else if diff < 0 {
+                return None;
+            } else {
+                pos = self.head.load(Ordering::Relaxed);
+            }
+        };
+        let data = unsafe { slot.data.get().read_volatile() };
+        slot.seq
+            .store(pos.wrapping_add(self.vec.len()), Ordering::Release);
+        Some(data)
+    }
+}
+
+unsafe impl<T: Copy> Send for InterlockedFifo<T> {}
+
+unsafe impl<T: Copy> Sync for InterlockedFifo<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_capacity() {
+        let fifo = Fifo::<u8>::with_exact_capacity(5);
+        assert_eq!(fifo.capacity(), 5);
+        assert_eq!(fifo.mask(), 7);
+        unsafe {
+            for i in 0..5 {
+                assert_eq!(fifo.enqueue(i), Ok(()));
+            }
+            assert_eq!(fifo.enqueue(5), Err(5));
+            assert_eq!(fifo.len(), 5);
+
+            assert_eq!(fifo.dequeue(), Some(0));
+            assert_eq!(fifo.enqueue(5), Ok(()));
+            assert_eq!(fifo.enqueue(6), Err(6));
+            for i in 1..6 {
+                assert_eq!(fifo.dequeue(), Some(i));
+            }
+            assert_eq!(fifo.dequeue(), None);
+        }
+    }
+
+    #[test]
+    fn drain() {
+        let mut fifo = Fifo::<u8>::new(8);
+        unsafe {
+            // move the head so that the items wrap around
+            for i in 0..6 {
+                fifo.enqueue(i).unwrap();
+                fifo.dequeue().unwrap();
+            }
+            for i in 10..15 {
+                fifo.enqueue(i).unwrap();
+            }
+        }
+
+        let mut iter = fifo.drain();
+        assert_eq!(iter.next(), Some(10));
+        assert_eq!(iter.next(), Some(11));
+        drop(iter);
+        assert_eq!(fifo.len(), 3);
+
+        assert_eq!(fifo.drain().collect::<Vec<_>>(), [12, 13, 14]);
+        assert!(fifo.is_empty());
+        assert_eq!(fifo.drain().next(), None);
+
+        unsafe {
+            fifo.enqueue(20).unwrap();
+            assert_eq!(fifo.dequeue(), Some(20));
+        }
+    }
+
+    #[test]
+    fn power_of_two_capacity() {
+        let fifo = Fifo::<u8>::new(4);
+        assert_eq!(fifo.capacity(), 3);
+        unsafe {
+            for i in 0..3 {
+                assert_eq!(fifo.enqueue(i), Ok(()));
+            }
+            assert_eq!(fifo.enqueue(3), Err(3));
+        }
+    }
+
+    #[test]
+    fn interlocked_exact_capacity() {
+        let fifo = InterlockedFifo::<u8>::with_exact_capacity(5);
+        assert_eq!(fifo.capacity(), 5);
+        for i in 0..5 {
+            assert_eq!(fifo.enqueue(i), Ok(()));
+        }
+        assert_eq!(fifo.enqueue(5), Err(5));
+        for i in 0..5 {
+            assert_eq!(fifo.dequeue(), Some(i));
+        }
+        assert_eq!(fifo.dequeue(), None);
+        assert!(fifo.is_empty());
+    }
+
+    #[test]
+    fn interlocked_interleaved() {
+        const N_PRODUCERS: usize = 4;
+        const N_ITEMS: usize = 1000;
+
+        let fifo = InterlockedFifo::<u32>::new(16);
+        let mut next = [0usize; N_PRODUCERS];
+        let mut seen = [0usize; N_PRODUCERS];
+        let mut rng = 0x1234_5678u32;
+        let mut received = 0;
+
+        while received < N_PRODUCERS * N_ITEMS {
+            // xorshift32
+            rng ^= rng << 13;
+            rng ^= rng >> 17;
+            rng ^= rng << 5;
+            let who = rng as usize % (N_PRODUCERS + 2);
+            if who < N_PRODUCERS {
+                if next[who] < N_ITEMS {
+                    let item = ((who as u32) << 16) | next[who] as u32;
+                    if fifo.enqueue(item).is_ok() {
+                        next[who] += 1;
+                    }
+                }
+            } else if let Some(item) = fifo.dequeue() {
+                let producer = (item >> 16) as usize;
+                let index = (item & 0xFFFF) as usize;
+                // no lost or duplicated items, in order per producer
+                assert_eq!(index, seen[producer]);
+                seen[producer] += 1;
+                received += 1;
+            }
+        }
+
+        assert!(fifo.is_empty());
+        assert_eq!(seen, [N_ITEMS; N_PRODUCERS]);
+    }
+}
@@ -0,0 +1,2 @@
+// Synchronization Primitives
+pub mod fifo;
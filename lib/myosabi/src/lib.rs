@@ -11,4 +11,151 @@ impl MyOsAbi {
 
     /// Use 32bit bitmap in window
     pub const WINDOW_32BIT_BITMAP: u32 = 0b0000_0000_0000_0001;
+
+    /// Use 32bit bitmap in surface
+    pub const SURFACE_32BIT_BITMAP: u32 = 0b0000_0000_0000_0001;
+}
+
+/// System information returned by `GetSystemInfo`
+///
+/// The leading `size` field holds the number of valid bytes so that the structure can grow.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SystemInfo {
+    pub size: u32,
+    pub version: u32,
+    pub platform: u8,
+    pub cpu_ver: u8,
+    pub screen_bpp: u8,
+    _reserved: u8,
+    pub total_memory: u32,
+    pub free_memory: u32,
+    pub screen_width: u16,
+    pub screen_height: u16,
+}
+
+impl SystemInfo {
+    /// Size of the current version of this structure
+    pub const SIZE: usize = 24;
+
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            size: Self::SIZE as u32,
+            version: 0,
+            platform: 0,
+            cpu_ver: 0,
+            screen_bpp: 0,
+            _reserved: 0,
+            total_memory: 0,
+            free_memory: 0,
+            screen_width: 0,
+            screen_height: 0,
+        }
+    }
+
+    /// Writes as many bytes as fit in the buffer and returns the number of bytes written.
+    pub fn write_to(&self, buf: &mut [u8]) -> usize {
+        let mut bytes = [0u8; Self::SIZE];
+        let len = usize::min(buf.len(), Self::SIZE);
+        bytes[0..4].copy_from_slice(&(len as u32).to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.version.to_le_bytes());
+        bytes[8] = self.platform;
+        bytes[9] = self.cpu_ver;
+        bytes[10] = self.screen_bpp;
+        bytes[12..16].copy_from_slice(&self.total_memory.to_le_bytes());
+        bytes[16..20].copy_from_slice(&self.free_memory.to_le_bytes());
+        bytes[20..22].copy_from_slice(&self.screen_width.to_le_bytes());
+        bytes[22..24].copy_from_slice(&self.screen_height.to_le_bytes());
+        buf[..len].copy_from_slice(&bytes[..len]);
+        len
+    }
+
+    /// Reads the structure from the buffer, leaving missing fields as zero.
+    pub fn from_bytes(buf: &[u8]) -> Option<Self> {
+        if buf.len() < 4 {
+            return None;
+        }
+        let mut bytes = [0u8; Self::SIZE];
+        let size = u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as usize;
+        let len = usize::min(usize::min(buf.len(), size), Self::SIZE);
+        bytes[..len].copy_from_slice(&buf[..len]);
+        let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+        let u32_at =
+            |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+        Some(Self {
+            size: len as u32,
+            version: u32_at(4),
+            platform: bytes[8],
+            cpu_ver: bytes[9],
+            screen_bpp: bytes[10],
+            _reserved: 0,
+            total_memory: u32_at(12),
+            free_memory: u32_at(16),
+            screen_width: u16_at(20),
+            screen_height: u16_at(22),
+        })
+    }
+}
+
+impl Default for SystemInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::svc::Function;
+    use super::*;
+    use core::convert::TryFrom;
+
+    #[test]
+    fn file_functions() {
+        assert!(matches!(Function::try_from(20), Ok(Function::OpenFile)));
+        assert!(matches!(Function::try_from(21), Ok(Function::ReadFile)));
+        assert!(matches!(Function::try_from(22), Ok(Function::CloseFile)));
+        assert!(Function::try_from(19).is_err());
+        assert!(matches!(Function::try_from(23), Ok(Function::CreateSurface)));
+        assert_eq!(Function::OpenFile as u32, 20);
+        assert_eq!(Function::ReadFile as u32, 21);
+        assert_eq!(Function::CloseFile as u32, 22);
+    }
+
+    #[test]
+    fn surface_functions() {
+        assert!(matches!(Function::try_from(24), Ok(Function::BltSurface)));
+        assert!(Function::try_from(25).is_err());
+        assert_eq!(Function::CreateSurface as u32, 23);
+        assert_eq!(Function::BltSurface as u32, 24);
+    }
+
+    #[test]
+    fn system_info() {
+        let mut info = SystemInfo::new();
+        info.version = 0x0000_0001;
+        info.platform = 2;
+        info.cpu_ver = 5;
+        info.screen_bpp = 32;
+        info.total_memory = 0x0100_0000;
+        info.free_memory = 0x0080_0000;
+        info.screen_width = 640;
+        info.screen_height = 480;
+
+        let mut buf = [0u8; 32];
+        assert_eq!(info.write_to(&mut buf), SystemInfo::SIZE);
+        assert_eq!(SystemInfo::from_bytes(&buf), Some(info));
+
+        let mut buf = [0u8; 10];
+        assert_eq!(info.write_to(&mut buf), 10);
+        let short = SystemInfo::from_bytes(&buf).unwrap();
+        assert_eq!(short.size, 10);
+        assert_eq!(short.version, 1);
+        assert_eq!(short.platform, 2);
+        assert_eq!(short.cpu_ver, 5);
+        assert_eq!(short.screen_bpp, 0);
+        assert_eq!(short.total_memory, 0);
+
+        assert_eq!(SystemInfo::from_bytes(&buf[..3]), None);
+    }
 }
@@ -42,6 +42,16 @@ pub enum Function {
     WaitChar = 17,
     /// [18] Read a char event
     ReadChar = 18,
+    /// [20] Open a file
+    OpenFile = 20,
+    /// [21] Read data from a file
+    ReadFile = 21,
+    /// [22] Close a file
+    CloseFile = 22,
+    /// [23] Create an off-screen surface
+    CreateSurface = 23,
+    /// [24] Draw an off-screen surface in a window or another surface
+    BltSurface = 24,
     /// [100] Return a random number
     Rand = 100,
     /// [101] Set the seed of the random number
@@ -78,6 +88,11 @@ impl TryFrom<u32> for Function {
             16 => Ok(Self::RefreshWindow),
             17 => Ok(Self::WaitChar),
             18 => Ok(Self::ReadChar),
+            20 => Ok(Self::OpenFile),
+            21 => Ok(Self::ReadFile),
+            22 => Ok(Self::CloseFile),
+            23 => Ok(Self::CreateSurface),
+            24 => Ok(Self::BltSurface),
             100 => Ok(Self::Rand),
             101 => Ok(Self::Srand),
             10000 => Ok(Self::Alloc),
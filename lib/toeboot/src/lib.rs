@@ -1,6 +1,7 @@
 // MEG-OS Boot Protocol for TOE
 #![no_std]
 
+use core::convert::TryFrom;
 use core::fmt;
 
 #[repr(C)]
@@ -16,6 +17,8 @@ pub struct BootInfo {
     pub screen_bpp: u8,
 
     /// Screen informations
+    ///
+    /// The lower 32 bits of the frame buffer address, see also `vram_base_hi`
     pub vram_base: u32,
     pub screen_width: u16,
     pub screen_height: u16,
@@ -34,11 +37,29 @@ pub struct BootInfo {
 
     /// TODO: SMAP
     pub smap: (u32, u32),
+
+    /// The upper 32 bits of the frame buffer address, zero for the legacy layout
+    pub vram_base_hi: u32,
+}
+
+impl BootInfo {
+    /// Returns the full physical address of the frame buffer.
+    #[inline]
+    pub const fn vram_base(&self) -> u64 {
+        ((self.vram_base_hi as u64) << 32) | self.vram_base as u64
+    }
+
+    /// Returns the address of the frame buffer,
+    /// or `None` if it is not addressable on this architecture.
+    #[inline]
+    pub fn vram_address(&self) -> Option<usize> {
+        usize::try_from(self.vram_base()).ok()
+    }
 }
 
 #[non_exhaustive]
 #[repr(u8)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Platform {
     Unknown = 0,
     Nec98 = 1,
@@ -46,6 +67,24 @@ pub enum Platform {
     FmTowns = 3,
 }
 
+impl From<u8> for Platform {
+    /// Converts the raw platform value passed by the boot loader.
+    fn from(val: u8) -> Self {
+        match val {
+            1 => Self::Nec98,
+            2 => Self::PcCompatible,
+            3 => Self::FmTowns,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl From<Platform> for u8 {
+    fn from(val: Platform) -> Self {
+        val as u8
+    }
+}
+
 impl fmt::Display for Platform {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -79,3 +118,54 @@ impl Default for CpuVersion {
         Self::UNSPECIFIED
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn boot_info(vram_base: u32, vram_base_hi: u32) -> BootInfo {
+        BootInfo {
+            platform: Platform::PcCompatible,
+            bios_boot_drive: 0,
+            cpu_ver: CpuVersion::X86_HAS_CPUID,
+            screen_bpp: 32,
+            vram_base,
+            screen_width: 640,
+            screen_height: 480,
+            screen_stride: 640,
+            _boot_flags: 0,
+            acpi_rsdptr: 0,
+            total_memory_size: 0,
+            reserved_memory_size: 0,
+            initrd_base: 0,
+            initrd_size: 0,
+            smap: (0, 0),
+            vram_base_hi,
+        }
+    }
+
+    #[test]
+    fn vram_base() {
+        // legacy layout
+        let info = boot_info(0xFD00_0000, 0);
+        assert_eq!(info.vram_base(), 0xFD00_0000);
+        assert_eq!(info.vram_address(), Some(0xFD00_0000));
+
+        let info = boot_info(0xC000_0000, 0x0000_0038);
+        assert_eq!(info.vram_base(), 0x38_C000_0000);
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(info.vram_address(), Some(0x38_C000_0000));
+        #[cfg(target_pointer_width = "32")]
+        assert_eq!(info.vram_address(), None);
+    }
+
+    #[test]
+    fn platform_round_trip() {
+        for platform in [Platform::Nec98, Platform::PcCompatible, Platform::FmTowns].iter() {
+            let raw: u8 = (*platform).into();
+            assert_eq!(Platform::from(raw), *platform);
+        }
+        assert_eq!(Platform::from(0), Platform::Unknown);
+        assert_eq!(Platform::from(0xFF), Platform::Unknown);
+    }
+}
@@ -823,11 +823,23 @@ impl WasmMemory {
         }
     }
 
+    /// Returns the specified range of memory as a mutable slice
+    #[inline]
+    pub fn slice_mut(&mut self, offset: usize, size: usize) -> Result<&mut [u8], WasmRuntimeError> {
+        let memory = self.memory_mut();
+        let limit = memory.len();
+        if offset < limit && size < limit && offset + size < limit {
+            Ok(&mut memory[offset..offset + size])
+        } else {
+            Err(WasmRuntimeError::OutOfBounds)
+        }
+    }
+
     #[inline]
     pub fn read_u32_array(&self, offset: usize, len: usize) -> Result<&[u32], WasmRuntimeError> {
         let memory = self.memory();
         let limit = memory.len();
-        let size = len * 4;
+        let size = len.checked_mul(4).ok_or(WasmRuntimeError::OutOfBounds)?;
         if offset < limit && size < limit && offset + size < limit {
             unsafe {
                 Ok(slice::from_raw_parts(
@@ -3621,7 +3633,7 @@ impl WasmRunnable<'_> {
 
 #[cfg(test)]
 mod tests {
-    use super::{Leb128Stream, WasmLoader};
+    use super::{Leb128Stream, WasmLimit, WasmLoader, WasmMemory, WasmRuntimeError};
 
     #[test]
     fn instantiate() {
@@ -3693,4 +3705,18 @@ mod tests {
         let test = stream.read_signed().unwrap();
         assert_eq!(test, -559038737);
     }
+
+    #[test]
+    fn read_u32_array_bounds() {
+        let memory = WasmMemory::new(WasmLimit { min: 1, max: 1 });
+        assert_eq!(memory.read_u32_array(0, 4).unwrap().len(), 4);
+        assert!(memory.read_u32_array(0, 0x4000).is_err());
+
+        // the byte length wraps around to 4
+        let len = usize::MAX / 4 + 2;
+        assert!(matches!(
+            memory.read_u32_array(0, len),
+            Err(WasmRuntimeError::OutOfBounds)
+        ));
+    }
 }
@@ -5,8 +5,9 @@ use crate::*;
 use bitflags::*;
 use core::ffi::c_void;
 use core::intrinsics::*;
+use core::marker::PhantomData;
 use core::sync::atomic::*;
-use toeboot::Platform;
+use toeboot::{CpuVersion, Platform};
 
 extern "fastcall" {
     fn asm_handle_exception(_: InterruptVector) -> usize;
@@ -23,32 +24,37 @@ impl Cpu {
         InterruptDescriptorTable::init();
     }
 
+    /// Atomically adds `val` to the value and returns the previous value.
+    #[inline]
+    pub fn fetch_add(p: &AtomicUsize, val: usize) -> usize {
+        p.fetch_add(val, Ordering::SeqCst)
+    }
+
+    /// Atomically replaces the value with `desired` if it equals `expected`.
+    ///
+    /// Returns the previous value if successful, otherwise the current value.
+    #[inline]
+    pub fn compare_and_swap(
+        p: &AtomicUsize,
+        expected: usize,
+        desired: usize,
+    ) -> Result<usize, usize> {
+        p.compare_exchange(expected, desired, Ordering::SeqCst, Ordering::SeqCst)
+    }
+
     #[inline]
     pub fn interlocked_increment(p: &AtomicUsize) -> usize {
-        Self::interlocked_add(p, 1)
+        Self::fetch_add(p, 1)
     }
 
     #[inline]
     pub fn interlocked_increment_u64(p: &AtomicU64) -> u64 {
-        unsafe {
-            Self::without_interrupts(|| {
-                let p = p as *const _ as *mut u64;
-                let r = p.read_volatile();
-                p.write_volatile(r + 1);
-                r
-            })
-        }
+        p.fetch_add(1, Ordering::SeqCst)
     }
 
     #[inline]
     pub fn interlocked_add(p: &AtomicUsize, val: usize) -> usize {
-        unsafe {
-            Self::without_interrupts(|| {
-                let p = p as *const _ as *mut usize;
-                let r = val + atomic_load(p);
-                atomic_xchg(p, r)
-            })
-        }
+        Self::fetch_add(p, val)
     }
 
     #[inline]
@@ -80,17 +86,9 @@ impl Cpu {
         expected: usize,
         desired: usize,
     ) -> (bool, usize) {
-        unsafe {
-            Self::without_interrupts(|| {
-                let p = p as *const _ as *mut usize;
-                let v = atomic_load(p);
-                if v == expected {
-                    let r = atomic_xchg(p, desired);
-                    (true, r)
-                } else {
-                    (false, v)
-                }
-            })
+        match Self::compare_and_swap(p, expected, desired) {
+            Ok(v) => (true, v),
+            Err(v) => (false, v),
         }
     }
 
@@ -155,6 +153,12 @@ impl Cpu {
         unsafe { asm!("nop") };
     }
 
+    /// Hints the processor that the caller is in a busy-wait loop.
+    #[inline]
+    pub fn spin_wait() {
+        core::hint::spin_loop();
+    }
+
     #[inline]
     pub unsafe fn halt() {
         asm!("hlt");
@@ -255,34 +259,107 @@ impl Cpu {
         assert!(!flags.contains(Eflags::IF));
     }
 
+    /// Runs the closure with interrupts disabled.
+    ///
+    /// It can be nested, interrupts are enabled again only when the outermost call returns.
     #[inline]
     pub(crate) unsafe fn without_interrupts<F, R>(f: F) -> R
     where
         F: FnOnce() -> R,
     {
+        let _guard = IrqGuard::<Self>::new();
+        f()
+    }
+
+    /// Detects the CPU features, if the CPU has a CPUID instruction.
+    pub(crate) fn detect_features() -> CpuFeatures {
+        if System::cpu_ver() < CpuVersion::X86_HAS_CPUID {
+            return CpuFeatures::empty();
+        }
+        let (_, _, ecx, edx) = unsafe { Self::cpuid(1, 0) };
+        CpuFeatures::from_cpuid(edx, ecx)
+    }
+
+    /// Returns EAX, EBX, ECX and EDX of the CPUID instruction.
+    #[inline]
+    pub unsafe fn cpuid(eax: u32, ecx: u32) -> (u32, u32, u32, u32) {
+        let eax_out: u32;
+        let ebx_out: u32;
+        let ecx_out: u32;
+        let edx_out: u32;
+        asm!("
+            push ebx
+            cpuid
+            mov {0}, ebx
+            pop ebx
+            ",
+            lateout(reg) ebx_out,
+            inlateout("eax") eax => eax_out,
+            inlateout("ecx") ecx => ecx_out,
+            lateout("edx") edx_out,
+        );
+        (eax_out, ebx_out, ecx_out, edx_out)
+    }
+
+    #[inline]
+    pub unsafe fn rdtsc() -> u64 {
+        let eax: u32;
+        let edx: u32;
+        asm!("rdtsc", lateout("edx") edx, lateout("eax") eax);
+        eax as u64 + edx as u64 * 0x10000_0000
+    }
+}
+
+impl InterruptControl for Cpu {
+    #[inline]
+    unsafe fn save_and_disable() -> bool {
         let r0: u32;
         asm!("
             pushfd
             cli
             pop {0}
             ", out(reg) r0);
-        let flags = Eflags::from_bits_unchecked(r0);
+        Eflags::from_bits_unchecked(r0).contains(Eflags::IF)
+    }
 
-        let result = f();
+    #[inline]
+    unsafe fn restore() {
+        Self::enable_interrupt();
+    }
+}
 
-        if flags.contains(Eflags::IF) {
-            Self::enable_interrupt();
-        }
+pub(crate) trait InterruptControl {
+    /// Disables interrupts and returns whether they were enabled before.
+    unsafe fn save_and_disable() -> bool;
 
-        result
+    /// Enables interrupts again.
+    unsafe fn restore();
+}
+
+/// Disables interrupts until dropped, and then restores the previous state.
+pub(crate) struct IrqGuard<C: InterruptControl> {
+    was_enabled: bool,
+    _phantom: PhantomData<C>,
+}
+
+impl<C: InterruptControl> IrqGuard<C> {
+    #[inline]
+    pub unsafe fn new() -> Self {
+        Self {
+            was_enabled: C::save_and_disable(),
+            _phantom: PhantomData,
+        }
     }
+}
 
+impl<C: InterruptControl> Drop for IrqGuard<C> {
     #[inline]
-    pub unsafe fn rdtsc() -> u64 {
-        let eax: u32;
-        let edx: u32;
-        asm!("rdtsc", lateout("edx") edx, lateout("eax") eax);
-        eax as u64 + edx as u64 * 0x10000_0000
+    fn drop(&mut self) {
+        if self.was_enabled {
+            unsafe {
+                C::restore();
+            }
+        }
     }
 }
 
@@ -341,6 +418,38 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// CPU features reported by CPUID (EDX in the lower half, ECX in the upper half)
+    pub struct CpuFeatures: u64 {
+        const FPU       = 1 << 0;
+        const TSC       = 1 << 4;
+        const CX8       = 1 << 8;
+        const CMOV      = 1 << 15;
+        const MMX       = 1 << 23;
+        const FXSR      = 1 << 24;
+        const SSE       = 1 << 25;
+        const SSE2      = 1 << 26;
+        const HTT       = 1 << 28;
+
+        const SSE3      = 1 << 32;
+        const SSSE3     = 1 << (32 + 9);
+        const CX16      = 1 << (32 + 13);
+        const SSE4_1    = 1 << (32 + 19);
+        const SSE4_2    = 1 << (32 + 20);
+        const POPCNT    = 1 << (32 + 23);
+        const AVX       = 1 << (32 + 28);
+        const HYPERVISOR = 1 << (32 + 31);
+    }
+}
+
+impl CpuFeatures {
+    /// Decodes the EDX and ECX values of CPUID leaf 1.
+    #[inline]
+    pub const fn from_cpuid(edx: u32, ecx: u32) -> Self {
+        Self::from_bits_truncate(edx as u64 | (ecx as u64) << 32)
+    }
+}
+
 #[repr(transparent)]
 #[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
 pub struct Limit(pub u16);
@@ -7,14 +7,34 @@ pub mod pic;
 pub mod pit;
 pub mod ps2;
 pub mod rtc;
+pub mod serial;
 
 use crate::system::{System, SystemTime};
+use core::time::Duration;
 use cpu::Cpu;
 use megstd::drawing::IndexedColor;
 use toeboot::Platform;
 
 pub(crate) struct Arch;
 
+/// Converts the duration to the number of ticks of the clock.
+///
+/// Any non-zero duration is at least one tick, and it saturates at `u64::MAX`.
+pub fn ticks_from_duration(d: Duration, hz: u64) -> u64 {
+    const NANOS_PER_SEC: u128 = 1_000_000_000;
+    match d.as_nanos().checked_mul(hz as u128) {
+        Some(v) => {
+            let ticks = (v + NANOS_PER_SEC - 1) / NANOS_PER_SEC;
+            if ticks > u64::MAX as u128 {
+                u64::MAX
+            } else {
+                ticks as u64
+            }
+        }
+        None => u64::MAX,
+    }
+}
+
 impl Arch {
     pub unsafe fn init() {
         cpu::Cpu::init();
@@ -28,6 +48,8 @@ impl Arch {
     }
 
     pub unsafe fn late_init() {
+        rtc::Rtc::start_calibration();
+
         let platform = System::platform();
         match platform {
             Platform::PcCompatible => {
@@ -98,8 +120,27 @@ impl Arch {
         }
     }
 
+    /// Sounds the speaker at the frequency for the duration.
+    ///
+    /// `freq_hz == 0` silences the speaker immediately.
+    #[inline]
+    pub fn beep(freq_hz: u32, duration: Duration) {
+        pit::Pit::beep(freq_hz, duration)
+    }
+
     #[inline]
     pub fn system_time() -> SystemTime {
         rtc::Rtc::system_time()
     }
+
+    /// Reads the byte that survives a reset, or `None` if the platform does not have one.
+    #[inline]
+    pub fn reset_record() -> Option<u8> {
+        rtc::Rtc::reset_record()
+    }
+
+    #[inline]
+    pub fn set_reset_record(value: u8) {
+        rtc::Rtc::set_reset_record(value)
+    }
 }
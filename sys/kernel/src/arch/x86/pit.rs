@@ -1,12 +1,16 @@
 // Programmable Interval Timer
 
-use super::{cpu::Cpu, pic::Irq};
+use super::{cpu::Cpu, pic::Irq, ticks_from_duration};
 use crate::{audio::*, task::scheduler::*, System};
+use core::convert::TryFrom;
+use core::sync::atomic::*;
 use core::time::Duration;
 use toeboot::Platform;
 
 static mut PIT: Pit = Pit::new();
 
+static BEEP_SEQ: AtomicUsize = AtomicUsize::new(0);
+
 pub struct Pit {
     monotonic: u64,
     tmr_cnt0: u32,
@@ -17,6 +21,11 @@ pub struct Pit {
 
 impl Pit {
     const TIMER_RES: u64 = 1;
+    const TIMER_HZ: u64 = 1000 / Self::TIMER_RES;
+
+    const CLOCK_PC: u32 = 1_193_180;
+    const CLOCK_NEC98: u32 = 2_457_600;
+    const CLOCK_FMTOWNS: u32 = 307_200;
 
     const fn new() -> Self {
         Self {
@@ -76,6 +85,41 @@ impl Pit {
         unsafe { &mut PIT }
     }
 
+    /// Sounds the speaker, then silences it after the duration.
+    pub(super) fn beep(freq_hz: u32, duration: Duration) {
+        let seq = BEEP_SEQ.fetch_add(1, Ordering::SeqCst).wrapping_add(1);
+        AudioManager::make_beep(freq_hz as usize);
+        if freq_hz > 0 {
+            let _ = Scheduler::schedule_timer(TimerEvent::callback(
+                Timer::new(duration),
+                Self::end_beep,
+                seq,
+            ));
+        }
+    }
+
+    /// Silences the speaker unless another beep has started since.
+    fn end_beep(seq: usize) {
+        if BEEP_SEQ.load(Ordering::SeqCst) == seq {
+            AudioManager::make_beep(0);
+        }
+    }
+
+    /// Returns the counter value of the timer to generate the frequency.
+    pub const fn beep_divisor(clock: u32, freq_hz: u32) -> u16 {
+        if freq_hz == 0 {
+            return 0;
+        }
+        let count = (clock as u64 + freq_hz as u64 / 2) / freq_hz as u64;
+        if count > u16::MAX as u64 {
+            u16::MAX
+        } else if count < 1 {
+            1
+        } else {
+            count as u16
+        }
+    }
+
     /// Timer IRQ handler for IBM PC and NEC PC98
     fn timer_irq_handler_pc(_irq: Irq) {
         let shared = Self::shared();
@@ -102,7 +146,8 @@ impl TimerSource for Pit {
     }
 
     fn from_duration(&self, val: Duration) -> TimeSpec {
-        TimeSpec((val.as_millis() as u64 / Self::TIMER_RES) as usize)
+        let ticks = ticks_from_duration(val, Self::TIMER_HZ);
+        TimeSpec(usize::try_from(ticks).unwrap_or(usize::MAX))
     }
 
     fn to_duration(&self, val: TimeSpec) -> Duration {
@@ -117,7 +162,7 @@ impl BeepDriver for Pit {
                 Platform::PcCompatible => {
                     if freq > 0 {
                         asm!("out dx, al", in ("edx") self.tmr_ctl, in ("al") 0b1011_0110u8);
-                        let count = 0x0012_34DC / freq;
+                        let count = Self::beep_divisor(Self::CLOCK_PC, freq as u32) as u32;
                         asm!("
                             out dx, al
                             mov al, ah
@@ -139,7 +184,7 @@ impl BeepDriver for Pit {
                 Platform::Nec98 => {
                     if freq > 0 {
                         asm!("out dx, al", in ("edx") self.tmr_ctl, in ("al") 0b1011_0110u8);
-                        let count = 0x0025_8000 / freq;
+                        let count = Self::beep_divisor(Self::CLOCK_NEC98, freq as u32) as u32;
                         asm!("
                             out dx, al
                             mov al, ah
@@ -159,7 +204,7 @@ impl BeepDriver for Pit {
                 Platform::FmTowns => {
                     if freq > 0 {
                         asm!("out dx, al", in ("edx") self.tmr_ctl, in ("al") 0b1011_0110u8);
-                        let count = 0x0004_B000 / freq;
+                        let count = Self::beep_divisor(Self::CLOCK_FMTOWNS, freq as u32) as u32;
                         asm!("
                             out dx, al
                             mov al, ah
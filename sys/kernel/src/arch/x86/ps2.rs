@@ -11,8 +11,7 @@ use core::time::Duration;
 static mut PS2: Ps2 = Ps2::new();
 
 pub(super) struct Ps2 {
-    key_state: Ps2KeyState,
-    key_modifier: Modifier,
+    key_decoder: ScancodeSet2,
     mouse_state: MouseState,
     mouse_phase: Ps2MousePhase,
     mouse_buf: [Ps2Data; 3],
@@ -25,8 +24,7 @@ impl Ps2 {
 
     const fn new() -> Self {
         Self {
-            key_state: Ps2KeyState::Default,
-            key_modifier: Modifier::empty(),
+            key_decoder: ScancodeSet2::new(),
             mouse_phase: Ps2MousePhase::Ack,
             mouse_buf: [Ps2Data(0); 3],
             mouse_state: MouseState::empty(),
@@ -50,7 +48,8 @@ impl Ps2 {
         Irq(12).register(Self::irq_12).unwrap();
 
         Self::send_command(Ps2Command::WRITE_CONFIG, 1)?;
-        Self::send_data(Ps2Data(0x47), 1)?;
+        // Receive scancode set 2 without translation
+        Self::send_data(Ps2Data(0x07), 1)?;
 
         Self::send_command(Ps2Command::ENABLE_FIRST_PORT, 1)?;
         Self::send_command(Ps2Command::ENABLE_SECOND_PORT, 1)?;
@@ -148,31 +147,8 @@ impl Ps2 {
 
     #[inline]
     fn process_key_data(&mut self, data: Ps2Data) {
-        if data == Ps2Data::SCAN_E0 {
-            self.key_state = Ps2KeyState::PrefixE0;
-        } else {
-            let flags = if data.is_break() {
-                KeyEventFlags::BREAK
-            } else {
-                KeyEventFlags::empty()
-            };
-            let mut scancode = data.scancode();
-            match self.key_state {
-                Ps2KeyState::PrefixE0 => {
-                    scancode |= 0x80;
-                    self.key_state = Ps2KeyState::Default;
-                }
-                _ => (),
-            }
-            let usage = Usage(PS2_TO_HID[scancode as usize]);
-            if usage >= Usage::MOD_MIN && usage < Usage::MOD_MAX {
-                let bit_position =
-                    unsafe { Modifier::from_bits_unchecked(1 << (usage.0 - Usage::MOD_MIN.0)) };
-                self.key_modifier.set(bit_position, !data.is_break());
-                KeyEvent::new(Usage::NONE, self.key_modifier, flags).post();
-            } else {
-                KeyEvent::new(usage, self.key_modifier, flags).post();
-            }
+        if let Some(event) = self.key_decoder.feed(data.0) {
+            event.post();
         }
     }
 
@@ -225,18 +201,6 @@ pub(super) enum Ps2Error {
     Timeout,
 }
 
-#[derive(Debug)]
-enum Ps2KeyState {
-    Default,
-    PrefixE0,
-}
-
-impl Default for Ps2KeyState {
-    fn default() -> Self {
-        Self::Default
-    }
-}
-
 bitflags! {
     struct MouseLeadByte: u8 {
         const LEFT_BUTTON = 0b0000_0001;
@@ -314,16 +278,6 @@ impl Ps2Data {
     const ENABLE_SEND: Ps2Data = Ps2Data(0xF4);
     const DISABLE_SEND: Ps2Data = Ps2Data(0xF5);
     const SET_DEFAULT: Ps2Data = Ps2Data(0xF6);
-
-    const SCAN_E0: Ps2Data = Ps2Data(0xE0);
-
-    const fn is_break(self) -> bool {
-        (self.0 & 0x80) != 0
-    }
-
-    const fn scancode(self) -> u8 {
-        self.0 & 0x7F
-    }
 }
 
 #[repr(transparent)]
@@ -350,20 +304,3 @@ bitflags! {
         const PARITY_ERROR = 0b1000_0000;
     }
 }
-
-// PS2 scan code to HID usage table
-static PS2_TO_HID: [u8; 256] = [
-    0x00, 0x29, 0x1E, 0x1F, 0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x2D, 0x2E, 0x2A, 0x2B,
-    0x14, 0x1A, 0x08, 0x15, 0x17, 0x1C, 0x18, 0x0C, 0x12, 0x13, 0x2F, 0x30, 0x28, 0xE0, 0x04, 0x16,
-    0x07, 0x09, 0x0A, 0x0B, 0x0D, 0x0E, 0x0F, 0x33, 0x34, 0x35, 0xE1, 0x31, 0x1D, 0x1B, 0x06, 0x19,
-    0x05, 0x11, 0x10, 0x36, 0x37, 0x38, 0xE5, 0x55, 0xE2, 0x2C, 0x39, 0x3A, 0x3B, 0x3C, 0x3D, 0x3E,
-    0x3F, 0x40, 0x41, 0x42, 0x43, 0x53, 0x47, 0x5F, 0x60, 0x61, 0x56, 0x5C, 0x5D, 0x5E, 0x57, 0x59,
-    0x5A, 0x5B, 0x62, 0x63, 0, 0, 0, 0x44, 0x45, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-    0, 0, 0, 0, 0, 0, 0x88, 0, 0, 0x87, 0, 0, 0, 0, 0, 0x8A, 0, 0x8B, 0, 0x89, 0, 0,
-    // ----
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x58, 0xE4,
-    0, 0, 0x7F, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x81, 0, 0x80, 0, 0, 0, 0, 0x54, 0, 0, 0xE6,
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x4A, 0x52, 0x4B, 0, 0x50, 0, 0x4F, 0, 0x4D, 0x51,
-    0x4E, 0x49, 0x4C, 0, 0, 0, 0, 0, 0, 0, 0xE3, 0xE7, 0x65, 0x66, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-];
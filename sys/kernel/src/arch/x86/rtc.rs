@@ -4,10 +4,14 @@ use super::cpu::*;
 use crate::system::*;
 use crate::task::scheduler::*;
 use alloc::boxed::Box;
+use core::time::Duration;
 use toeboot::Platform;
 
 static mut RTC: Rtc = Rtc::new();
 
+/// Number of seconds of the RTC to calibrate the timer over
+const CALIBRATION_PERIODS: u64 = 8;
+
 pub(super) struct Rtc {
     base: u64,
     offset: u64,
@@ -39,7 +43,8 @@ impl Rtc {
             _ => unreachable!(),
         }
 
-        shared.base = shared.device.as_ref().unwrap().fetch_time();
+        let device = shared.device.as_ref().unwrap();
+        shared.base = device.fetch_time();
         shared.offset = Timer::monotonic().as_millis() as u64;
     }
 
@@ -48,6 +53,60 @@ impl Rtc {
         unsafe { &mut RTC }
     }
 
+    /// Starts calibrating the timer against the RTC in the background.
+    pub(super) fn start_calibration() {
+        SpawnOption::with_priority(Priority::High).spawn(
+            Self::calibration_thread,
+            0,
+            "RTC Calibration",
+        );
+    }
+
+    fn calibration_thread(_: usize) {
+        let shared = Self::shared();
+        let device = match shared.device.as_ref() {
+            Some(v) => v.as_ref(),
+            None => return,
+        };
+        Timer::calibrate(1, CALIBRATION_PERIODS, &mut || Self::wait_second(device));
+
+        // The RTC has just ticked, so the clock restarts here at the new rate
+        unsafe {
+            Cpu::without_interrupts(|| {
+                shared.base = device.fetch_time();
+                shared.offset = Timer::monotonic().as_millis() as u64;
+            })
+        }
+    }
+
+    /// Waits for the next second of the RTC, giving up after two seconds of the timer.
+    fn wait_second(device: &dyn RtcImpl) {
+        let limit = Timer::new(Duration::from_secs(2));
+        let time = unsafe { device.fetch_time() };
+        while unsafe { device.fetch_time() } == time && limit.until() {
+            Timer::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Reads the byte that survives a reset, if the platform has one.
+    pub(super) fn reset_record() -> Option<u8> {
+        match System::platform() {
+            Platform::PcCompatible => {
+                Some(unsafe { Cpu::without_interrupts(|| PcCmos::ResetRecord.read()) })
+            }
+            _ => None,
+        }
+    }
+
+    pub(super) fn set_reset_record(value: u8) {
+        match System::platform() {
+            Platform::PcCompatible => unsafe {
+                Cpu::without_interrupts(|| PcCmos::ResetRecord.write(value))
+            },
+            _ => (),
+        }
+    }
+
     #[inline(never)]
     pub fn system_time() -> SystemTime {
         let shared = Self::shared();
@@ -62,6 +121,16 @@ impl Rtc {
     }
 }
 
+/// Expands the two-digit year of the RTC, assuming the years from 1980 to 2079.
+#[inline]
+const fn full_year(year: usize) -> u32 {
+    if year < 80 {
+        2000 + year as u32
+    } else {
+        1900 + year as u32
+    }
+}
+
 trait RtcImpl {
     unsafe fn fetch_time(&self) -> u64 {
         Cpu::without_interrupts(|| loop {
@@ -73,6 +142,7 @@ trait RtcImpl {
         })
     }
 
+    /// Reads the date and time as seconds since 1970-01-01.
     unsafe fn read_time(&self) -> u64;
 }
 
@@ -91,7 +161,18 @@ impl RtcImpl for PcRtc {
         let sec = PcCmos::Seconds.read_bcd();
         let min = PcCmos::Minutes.read_bcd();
         let hour = PcCmos::Hours.read_bcd();
-        (sec + min * 60 + hour * 3600) as u64
+        let day = PcCmos::DayOfMonth.read_bcd();
+        let month = PcCmos::Month.read_bcd();
+        let year = full_year(PcCmos::Year.read_bcd());
+        SystemTime::from_ymd_hms(
+            year,
+            month as u8,
+            day as u8,
+            hour as u8,
+            min as u8,
+            sec as u8,
+        )
+        .secs
     }
 }
 
@@ -108,6 +189,11 @@ enum PcCmos {
     DayOfMonth,
     Month,
     Year,
+    /// Keeps the reason of the last reset.
+    ///
+    /// Not a standard location, some BIOSes use it for their own settings,
+    /// so it is only touched with `reset_record=on`.
+    ResetRecord = 0x3F,
 }
 
 #[allow(dead_code)]
@@ -219,10 +305,19 @@ impl RtcImpl for N98Rtc {
         let sec = self.read_bcd();
         let min = self.read_bcd();
         let hour = self.read_bcd();
-        let _ = self.read_bcd();
-        let _ = self.read_bcd();
-        let _ = self.read_bcd();
-        (sec + min * 60 + hour * 3600) as u64
+        let day = self.read_bcd();
+        // the month in binary is followed by the day of the week
+        let month = self.read_bcd() / 10;
+        let year = full_year(self.read_bcd());
+        SystemTime::from_ymd_hms(
+            year,
+            month as u8,
+            day as u8,
+            hour as u8,
+            min as u8,
+            sec as u8,
+        )
+        .secs
     }
 }
 
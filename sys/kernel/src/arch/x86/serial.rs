@@ -0,0 +1,206 @@
+// 16550 Serial Port
+
+use super::cpu::Cpu;
+use crate::io::tty::*;
+use alloc::boxed::Box;
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use megstd::io;
+
+/// Access to I/O ports, replaceable for testing.
+pub trait PortIo {
+    unsafe fn read(&self, port: u16) -> u8;
+
+    unsafe fn write(&self, port: u16, value: u8);
+}
+
+/// The I/O ports of the x86 processor
+#[derive(Default)]
+pub struct X86PortIo;
+
+impl PortIo for X86PortIo {
+    #[inline]
+    unsafe fn read(&self, port: u16) -> u8 {
+        Cpu::in8(port)
+    }
+
+    #[inline]
+    unsafe fn write(&self, port: u16, value: u8) {
+        Cpu::out8(port, value)
+    }
+}
+
+/// A serial port driven by 16550 compatible UART
+pub struct SerialPort<P: PortIo = X86PortIo> {
+    base: u16,
+    io: P,
+}
+
+impl SerialPort<X86PortIo> {
+    /// The base port of COM1
+    pub const COM1: u16 = 0x3F8;
+
+    pub const DEFAULT_BAUD_RATE: usize = 115200;
+
+    /// Initializes COM1 with the default baud rate.
+    pub unsafe fn com1() -> Self {
+        let port = Self::new(Self::COM1, X86PortIo);
+        port.init(Self::DEFAULT_BAUD_RATE);
+        port
+    }
+}
+
+impl<P: PortIo> SerialPort<P> {
+    /// The frequency of the baud rate generator divided by 16
+    const CLOCK: usize = 115200;
+
+    /// The number of polls before giving up waiting for the transmitter.
+    const MAX_WAIT: usize = 100_000;
+
+    const THR: u16 = 0;
+    const DLL: u16 = 0;
+    const IER: u16 = 1;
+    const DLM: u16 = 1;
+    const FCR: u16 = 2;
+    const LCR: u16 = 3;
+    const MCR: u16 = 4;
+    const LSR: u16 = 5;
+
+    const LCR_DLAB: u8 = 0x80;
+    const LCR_8N1: u8 = 0x03;
+    const FCR_ENABLE_AND_CLEAR: u8 = 0xC7;
+    const MCR_DTR_RTS_OUT2: u8 = 0x0B;
+    const LSR_THRE: u8 = 0x20;
+
+    #[inline]
+    pub const fn new(base: u16, io: P) -> Self {
+        Self { base, io }
+    }
+
+    /// Returns the value of the divisor latch for the baud rate, if it can be generated.
+    pub const fn divisor(baud_rate: usize) -> Option<u16> {
+        if baud_rate == 0 || baud_rate > Self::CLOCK || Self::CLOCK % baud_rate != 0 {
+            None
+        } else {
+            Some((Self::CLOCK / baud_rate) as u16)
+        }
+    }
+
+    /// Programs the UART as 8N1 with FIFO enabled and interrupts disabled.
+    pub unsafe fn init(&self, baud_rate: usize) -> bool {
+        let divisor = match Self::divisor(baud_rate) {
+            Some(v) => v,
+            None => return false,
+        };
+        self.io.write(self.base + Self::IER, 0);
+        self.io.write(self.base + Self::LCR, Self::LCR_DLAB);
+        self.io.write(self.base + Self::DLL, divisor as u8);
+        self.io.write(self.base + Self::DLM, (divisor >> 8) as u8);
+        self.io.write(self.base + Self::LCR, Self::LCR_8N1);
+        self.io
+            .write(self.base + Self::FCR, Self::FCR_ENABLE_AND_CLEAR);
+        self.io.write(self.base + Self::MCR, Self::MCR_DTR_RTS_OUT2);
+        true
+    }
+
+    /// Waits until the transmitter can accept a byte, returns `false` on timeout.
+    pub fn wait_for_transmit(&self) -> bool {
+        for _ in 0..Self::MAX_WAIT {
+            if (unsafe { self.io.read(self.base + Self::LSR) } & Self::LSR_THRE) != 0 {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Sends a byte, returns `false` if the transmitter is not ready.
+    pub fn write_byte(&mut self, byte: u8) -> bool {
+        if self.wait_for_transmit() {
+            unsafe { self.io.write(self.base + Self::THR, byte) };
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl<P: PortIo> io::Write for SerialPort<P> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for (index, &byte) in buf.iter().enumerate() {
+            if !self.write_byte(byte) {
+                return if index > 0 {
+                    Ok(index)
+                } else {
+                    Err(io::ErrorKind::TimedOut.into())
+                };
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<P: PortIo> fmt::Write for SerialPort<P> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for byte in s.bytes() {
+            if !self.write_byte(byte) {
+                return Err(fmt::Error);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TtyWrite for SerialPort {
+    fn reset(&mut self) -> Result<(), TtyError> {
+        Ok(())
+    }
+
+    fn dims(&self) -> (isize, isize) {
+        (80, 24)
+    }
+
+    fn cursor_position(&self) -> (isize, isize) {
+        (0, 0)
+    }
+
+    fn set_cursor_position(&mut self, _x: isize, _y: isize) {}
+
+    fn is_cursor_enabled(&self) -> bool {
+        false
+    }
+
+    fn set_cursor_enabled(&mut self, _enabled: bool) -> bool {
+        false
+    }
+
+    fn attribute(&self) -> u8 {
+        0
+    }
+
+    fn set_attribute(&mut self, _attribute: u8) {}
+}
+
+impl TtyRead for SerialPort {
+    fn read_async(&self) -> Pin<Box<dyn Future<Output = TtyReadResult> + '_>> {
+        Box::pin(SerialReader {})
+    }
+}
+
+impl Tty for SerialPort {}
+
+/// Reading from the serial port is not supported yet.
+struct SerialReader {}
+
+impl Future for SerialReader {
+    type Output = TtyReadResult;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Poll::Ready(Err(TtyError::EndOfStream))
+    }
+}
@@ -1,8 +1,10 @@
 // Fonts
 
+use crate::arch::cpu::Cpu;
 use alloc::boxed::Box;
 use alloc::collections::BTreeMap;
 use megstd::drawing::*;
+use toeboot::Platform;
 
 include!("megh0816.rs");
 const SYSTEM_FONT: FixedFontDriver = FixedFontDriver::new(8, 16, &FONT_MEGH0816_DATA, None);
@@ -30,11 +32,17 @@ static mut FONT_MANAGER: FontManager = FontManager::new();
 
 pub struct FontManager {
     fonts: Option<BTreeMap<FontFamily, Box<dyn FontDriver>>>,
+    glyph_cache: Option<GlyphCache>,
 }
 
 impl FontManager {
+    const GLYPH_CACHE_SIZE: usize = 256;
+
     const fn new() -> Self {
-        Self { fonts: None }
+        Self {
+            fonts: None,
+            glyph_cache: None,
+        }
     }
 
     #[inline]
@@ -52,6 +60,7 @@ impl FontManager {
         fonts.insert(FontFamily::SystemUI, Box::new(SYSTEM_UI_FONT));
 
         shared.fonts = Some(fonts);
+        shared.glyph_cache = Some(GlyphCache::new(Self::GLYPH_CACHE_SIZE));
     }
 
     fn driver_for(family: FontFamily) -> Option<&'static dyn FontDriver> {
@@ -73,6 +82,11 @@ impl FontManager {
         &SMALL_FONT
     }
 
+    #[inline]
+    pub const fn fixed_ui_font() -> &'static FixedFontDriver<'static> {
+        &SYSTEM_UI_FONT
+    }
+
     #[inline]
     pub fn system_font() -> FontDescriptor {
         FontDescriptor::new(FontFamily::FixedSystem, 0).unwrap()
@@ -87,6 +101,58 @@ impl FontManager {
     pub fn ui_font() -> FontDescriptor {
         FontDescriptor::new(FontFamily::SystemUI, 0).unwrap_or(Self::system_font())
     }
+
+    /// Returns the default fixed font that fits the native text metrics of the platform.
+    #[inline]
+    pub fn default_ui_font_for(platform: Platform) -> FontDescriptor {
+        FontDescriptor::new(Self::default_ui_family_for(platform), 0).unwrap_or(Self::system_font())
+    }
+
+    fn default_ui_family_for(platform: Platform) -> FontFamily {
+        match platform {
+            Platform::PcCompatible | Platform::FmTowns => FontFamily::FixedSystem,
+            // PC-98 is typically 640x400, so the narrower font is preferred
+            _ => FontFamily::SmallFixed,
+        }
+    }
+
+    /// Draws a glyph of the scalable font through the glyph cache.
+    fn draw_cached_char(
+        driver: &dyn FontDriver,
+        character: char,
+        bitmap: &mut Bitmap,
+        origin: Point,
+        height: isize,
+        color: AmbiguousColor,
+    ) {
+        let shared = Self::shared();
+        let cache = match shared.glyph_cache.as_mut() {
+            Some(v) => v,
+            None => return driver.draw_char(character, bitmap, origin, height, color),
+        };
+        let render = || {
+            let base_height = isize::max(driver.base_height(), 1);
+            let size = Size::new(
+                driver.width_of(character) * height / base_height,
+                driver.preferred_line_height() * height / base_height,
+            );
+            GlyphMask::render(size, |bitmap| {
+                driver.draw_char(
+                    character,
+                    bitmap,
+                    Point::default(),
+                    height,
+                    IndexedColor::WHITE.into(),
+                )
+            })
+        };
+        unsafe {
+            Cpu::without_interrupts(|| match cache.get_or_render(character, height, render) {
+                Some(mask) => mask.draw(bitmap, origin, color),
+                None => driver.draw_char(character, bitmap, origin, height, color),
+            })
+        }
+    }
 }
 
 #[non_exhaustive]
@@ -156,8 +222,19 @@ impl FontDescriptor {
         origin: Point,
         color: AmbiguousColor,
     ) {
-        self.driver
-            .draw_char(character, bitmap, origin, self.point(), color)
+        if self.driver.is_scalable() {
+            FontManager::draw_cached_char(
+                self.driver,
+                character,
+                bitmap,
+                origin,
+                self.point(),
+                color,
+            )
+        } else {
+            self.driver
+                .draw_char(character, bitmap, origin, self.point(), color)
+        }
     }
 }
 
@@ -185,7 +262,8 @@ pub trait FontDriver {
 pub struct FixedFontDriver<'a> {
     size: Size,
     data: &'a [u8],
-    leading: isize,
+    top_padding: isize,
+    descent: isize,
     line_height: isize,
     stride: usize,
     width_table: Option<[u8; 96]>,
@@ -201,12 +279,14 @@ impl FixedFontDriver<'_> {
         let width = width as isize;
         let height = height as isize;
         let line_height = height * 5 / 4;
-        let leading = (line_height - height) / 2;
+        let top_padding = (line_height - height) / 2;
         let stride = ((width as usize + 7) >> 3) * height as usize;
+        let descent = Self::measure_descent(data, stride, height);
         FixedFontDriver {
             size: Size::new(width, height),
             line_height,
-            leading,
+            top_padding,
+            descent,
             stride,
             data,
             width_table,
@@ -223,6 +303,43 @@ impl FixedFontDriver<'_> {
         self.line_height
     }
 
+    /// Distance from the top of the line to the baseline
+    #[inline]
+    pub const fn ascent(&self) -> isize {
+        self.top_padding + self.size.height - self.descent
+    }
+
+    /// Distance from the baseline to the bottom of the glyph
+    #[inline]
+    pub const fn descent(&self) -> isize {
+        self.descent
+    }
+
+    /// Space below the glyph to the next line
+    #[inline]
+    pub const fn leading(&self) -> isize {
+        self.line_height - self.top_padding - self.size.height
+    }
+
+    /// Counts the blank rows below the capital H, where the baseline of the font lies
+    const fn measure_descent(data: &[u8], stride: usize, height: isize) -> isize {
+        let bytes_per_row = stride / height as usize;
+        let base = stride * (b'H' - 0x20) as usize;
+        let mut y = height as usize;
+        while y > 0 {
+            let row = base + (y - 1) * bytes_per_row;
+            let mut x = 0;
+            while x < bytes_per_row {
+                if data[row + x] != 0 {
+                    return height - y as isize;
+                }
+                x += 1;
+            }
+            y -= 1;
+        }
+        0
+    }
+
     #[inline]
     pub fn height_for(&self, character: char) -> isize {
         let _ = character;
@@ -286,7 +403,7 @@ impl FontDriver for FixedFontDriver<'_> {
         color: AmbiguousColor,
     ) {
         if let Some(font) = self.glyph_for(character) {
-            let origin = Point::new(origin.x, origin.y + self.leading);
+            let origin = Point::new(origin.x, origin.y + self.top_padding);
             let size = Size::new(self.width_of(character), self.size.height());
             bitmap.draw_font(font, size, origin, color);
         }
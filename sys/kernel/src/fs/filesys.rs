@@ -1,6 +1,7 @@
 // FileSystem
 
 use super::initramfs::*;
+use crate::system::{System, SystemTime};
 use alloc::string::String;
 use alloc::vec::Vec;
 use core::num::{NonZeroU64, NonZeroUsize};
@@ -23,7 +24,7 @@ impl FileManager {
 
     pub(crate) unsafe fn init(initrd_base: usize, initrd_size: usize) {
         let shared = Self::shared_mut();
-        shared.initramfs = InitRamfs::from_static(initrd_base, initrd_size);
+        shared.initramfs = InitRamfs::from_static(initrd_base, initrd_size, System::system_time());
     }
 
     #[inline]
@@ -41,7 +42,46 @@ impl FileManager {
         Ok(FsRawReadDir::new())
     }
 
+    /// Returns the entries matching the pattern, which may end with a `*` wildcard.
+    pub fn find_matching(pattern: &str) -> io::Result<Vec<FsRawDirEntry>> {
+        let shared = FileManager::shared();
+        match shared.initramfs.as_ref() {
+            Some(fs) => Ok(fs.find_matching(pattern)),
+            None => Err(io::ErrorKind::NotConnected.into()),
+        }
+    }
+
+    pub fn stat(path: &str) -> io::Result<FsRawMetaData> {
+        let shared = FileManager::shared();
+        let fs = match shared.initramfs.as_ref() {
+            Some(v) => v,
+            None => return Err(io::ErrorKind::NotConnected.into()),
+        };
+
+        let lpc = path; // TODO: parse path
+        if lpc.is_empty() || lpc == "/" {
+            return Ok(fs.stat_root());
+        }
+        fs.find_file(lpc)
+            .and_then(|inode| fs.stat(inode))
+            .ok_or(io::ErrorKind::NotFound.into())
+    }
+
+    #[inline]
     pub fn open(path: &str) -> io::Result<FsRawFileControlBlock> {
+        Self::open_with(path, InitRamfs::find_file)
+    }
+
+    /// Opens the file ignoring the ASCII case of the name, preferring an exact match.
+    #[inline]
+    pub fn open_ci(path: &str) -> io::Result<FsRawFileControlBlock> {
+        Self::open_with(path, InitRamfs::find_file_ci)
+    }
+
+    fn open_with(
+        path: &str,
+        find_file: fn(&InitRamfs, &str) -> Option<NonZeroINodeType>,
+    ) -> io::Result<FsRawFileControlBlock> {
         let shared = FileManager::shared();
         let fs = match shared.initramfs.as_ref() {
             Some(v) => v,
@@ -49,7 +89,7 @@ impl FileManager {
         };
 
         let lpc = path; // TODO: parse path
-        let inode = match fs.find_file(lpc) {
+        let inode = match find_file(fs, lpc) {
             Some(v) => v,
             None => return Err(io::ErrorKind::NotFound.into()),
         };
@@ -130,18 +170,48 @@ impl FsRawDirEntry {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsKind {
+    File,
+    Directory,
+}
+
 pub struct FsRawMetaData {
+    kind: FsKind,
     len: OffsetType,
+    mtime: Option<SystemTime>,
 }
 
 impl FsRawMetaData {
-    pub const fn new(len: OffsetType) -> Self {
-        Self { len }
+    pub const fn new(kind: FsKind, len: OffsetType, mtime: Option<SystemTime>) -> Self {
+        Self { kind, len, mtime }
+    }
+
+    #[inline]
+    pub const fn kind(&self) -> FsKind {
+        self.kind
     }
 
+    #[inline]
+    pub fn is_dir(&self) -> bool {
+        self.kind == FsKind::Directory
+    }
+
+    #[inline]
+    pub fn is_file(&self) -> bool {
+        self.kind == FsKind::File
+    }
+
+    #[inline]
     pub const fn len(&self) -> OffsetType {
         self.len
     }
+
+    /// Returns the last modification time, if known.
+    #[inline]
+    pub const fn mtime(&self) -> Option<SystemTime> {
+        self.mtime
+    }
 }
 
 pub struct FsRawFileControlBlock {
@@ -191,6 +261,13 @@ impl FsRawFileControlBlock {
         self.file_pos
     }
 
+    /// Returns the entire contents of the file without copying, if the file system supports it.
+    pub fn as_slice(&self) -> Option<&'static [u8]> {
+        let shared = FileManager::shared();
+        self.inode
+            .and_then(|inode| shared.initramfs.as_ref().and_then(|v| v.file_slice(inode)))
+    }
+
     pub fn stat(&self) -> Option<FsRawMetaData> {
         let shared = FileManager::shared();
         self.inode
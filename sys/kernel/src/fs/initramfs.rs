@@ -1,6 +1,7 @@
 // Minimal Initial Ram Filesystem
 
 use super::*;
+use crate::system::SystemTime;
 use alloc::{borrow::ToOwned, boxed::Box, string::String, vec::Vec};
 use byteorder::*;
 use core::{intrinsics::copy_nonoverlapping, ptr::slice_from_raw_parts_mut};
@@ -9,6 +10,7 @@ use megstd::io;
 pub(super) struct InitRamfs {
     data: Box<[u8]>,
     dir: Box<[MyFsDirEntry]>,
+    mtime: SystemTime,
 }
 
 impl InitRamfs {
@@ -17,12 +19,13 @@ impl InitRamfs {
     const OFFSET_DATA: usize = 16;
 
     /// SAFETY: Must guarantee the existence of the data.
-    pub(super) unsafe fn from_static(base: usize, len: usize) -> Option<Self> {
+    pub(super) unsafe fn from_static(base: usize, len: usize, mtime: SystemTime) -> Option<Self> {
         let boxed = Box::from_raw(slice_from_raw_parts_mut(base as *mut u8, len));
         let mut dir = Vec::new();
         Self::parse_header(&boxed, &mut dir).then(|| Self {
             data: boxed,
             dir: dir.into_boxed_slice(),
+            mtime,
         })
     }
 
@@ -46,6 +49,9 @@ impl InitRamfs {
                 name,
                 offset: LE::read_u32(&data[dir_offset + 0x18..dir_offset + 0x1C]) as usize,
                 size: LE::read_u32(&data[dir_offset + 0x1C..dir_offset + 0x20]) as usize,
+                link: NonZeroINodeType::new(LE::read_u32(
+                    &data[dir_offset + 0x10..dir_offset + 0x14],
+                ) as INodeType),
             });
         }
 
@@ -54,7 +60,7 @@ impl InitRamfs {
 
     #[inline]
     pub fn read_dir(&self, index: usize) -> Option<FsRawDirEntry> {
-        self.dir.get(index).map(|v| v.into())
+        self.dir.get(index).map(|v| self.dir_entry(v))
     }
 
     #[inline]
@@ -62,14 +68,81 @@ impl InitRamfs {
         self.dir.iter().find(|v| lpc == v.name).map(|v| v.inode)
     }
 
+    /// Finds the file ignoring the ASCII case of the name.
+    #[inline]
+    pub fn find_file_ci(&self, lpc: &str) -> Option<NonZeroINodeType> {
+        self.find_file(lpc).or_else(|| {
+            self.dir
+                .iter()
+                .find(|v| lpc.eq_ignore_ascii_case(&v.name))
+                .map(|v| v.inode)
+        })
+    }
+
+    /// Returns the files matching the pattern, which may end with a `*` wildcard.
+    pub fn find_matching(&self, pattern: &str) -> Vec<FsRawDirEntry> {
+        let matches = |name: &str| match pattern.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == pattern,
+        };
+        self.dir
+            .iter()
+            .filter(|v| matches(&v.name))
+            .map(|v| self.dir_entry(v))
+            .collect()
+    }
+
+    /// Aliases report the metadata of their target, or none if the target cannot be resolved.
+    #[inline]
+    fn dir_entry(&self, dir_ent: &MyFsDirEntry) -> FsRawDirEntry {
+        FsRawDirEntry::new(
+            dir_ent.inode,
+            dir_ent.name.clone(),
+            self.get_file(dir_ent.inode).map(|v| self.metadata(v)),
+        )
+    }
+
     #[inline]
     pub fn stat(&self, inode: NonZeroINodeType) -> Option<FsRawMetaData> {
-        self.get_file(inode).map(|v| v.into())
+        self.get_file(inode).map(|v| self.metadata(v))
+    }
+
+    /// Returns the metadata of the root directory, which is the only directory.
+    #[inline]
+    pub fn stat_root(&self) -> FsRawMetaData {
+        FsRawMetaData::new(
+            FsKind::Directory,
+            self.dir.len() as OffsetType,
+            Some(self.mtime),
+        )
     }
 
+    /// All files share the boot time as the modification time.
     #[inline]
+    fn metadata(&self, dir_ent: &MyFsDirEntry) -> FsRawMetaData {
+        FsRawMetaData::new(FsKind::File, dir_ent.size as OffsetType, Some(self.mtime))
+    }
+
+    /// Returns the entry that holds the data of the file, following aliases.
+    ///
+    /// Returns `None` if an alias points to a missing entry or forms a cycle.
     fn get_file(&self, inode: NonZeroINodeType) -> Option<&MyFsDirEntry> {
-        self.dir.get(inode.get() as usize - 1)
+        let mut dir_ent = self.dir.get(inode.get() as usize - 1)?;
+        for _ in 0..self.dir.len() {
+            match dir_ent.link {
+                Some(link) => dir_ent = self.dir.get(link.get() as usize - 1)?,
+                None => return Some(dir_ent),
+            }
+        }
+        None
+    }
+
+    /// Returns the contents of the file without copying.
+    pub fn file_slice(&self, inode: NonZeroINodeType) -> Option<&[u8]> {
+        self.get_file(inode).and_then(|dir_ent| {
+            let base = Self::OFFSET_DATA + dir_ent.offset;
+            self.data.get(base..base + dir_ent.size)
+        })
     }
 
     pub fn read_data(
@@ -83,7 +156,10 @@ impl InitRamfs {
             None => return Err(io::ErrorKind::NotFound.into()),
         };
         if offset > dir_ent.size as OffsetType {
-            return Err(io::ErrorKind::UnexpectedEof.into());
+            return Err(io::Error::with_offset(
+                io::ErrorKind::UnexpectedEof,
+                offset as usize,
+            ));
         }
         let size_left = dir_ent.size as OffsetType - offset;
         let count = usize::min(size_left as usize, buf.len());
@@ -106,16 +182,6 @@ struct MyFsDirEntry {
     name: String,
     offset: usize,
     size: usize,
-}
-
-impl From<&MyFsDirEntry> for FsRawDirEntry {
-    fn from(src: &MyFsDirEntry) -> Self {
-        FsRawDirEntry::new(src.inode, src.name.clone(), Some(src.into()))
-    }
-}
-
-impl From<&MyFsDirEntry> for FsRawMetaData {
-    fn from(src: &MyFsDirEntry) -> Self {
-        FsRawMetaData::new(src.size as OffsetType)
-    }
+    /// The inode of the target if this entry is an alias
+    link: Option<NonZeroINodeType>,
 }
@@ -1,10 +1,19 @@
 // Emergency debugging console
 
+use super::tty::*;
+use crate::arch::cpu::Cpu;
 use crate::fonts::*;
 use crate::system::*;
+use alloc::boxed::Box;
 use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
 use megstd::drawing::*;
 
+/// A console that draws directly to the main screen.
+///
+/// It never allocates, so it can be used before the memory manager is initialized.
 pub struct EmConsole {
     x: usize,
     y: usize,
@@ -14,6 +23,9 @@ pub struct EmConsole {
 }
 
 impl EmConsole {
+    /// The number of attempts to lock the screen before falling back to the serial port
+    const SCREEN_LOCK_SPIN_COUNT: usize = 10_000;
+
     pub const fn new(font: &'static FixedFontDriver<'static>) -> Self {
         Self {
             x: 0,
@@ -24,12 +36,77 @@ impl EmConsole {
         }
     }
 
+    /// Clears the screen with white on red for reporting a fatal error.
+    pub fn enter_panic_mode(&mut self) {
+        self.fg_color = IndexedColor::WHITE;
+        self.bg_color = IndexedColor::RED;
+        let _ = self.reset();
+    }
+
+    /// Writes a character to the main screen.
+    ///
+    /// If the screen is unavailable or stays locked for a while,
+    /// the character is written to the serial port instead.
     pub fn write_char(&mut self, c: char) {
+        if System::has_main_screen() {
+            for _ in 0..Self::SCREEN_LOCK_SPIN_COUNT {
+                if let Some(mut screen) = System::try_lock_screen() {
+                    self.put_char(&mut screen, c);
+                    return;
+                }
+                Cpu::spin_wait();
+            }
+        }
+        if let Some(serial) = System::serial() {
+            let _ = fmt::Write::write_char(serial, c);
+        }
+    }
+
+    /// Returns the cursor position in character cells as `(col, row)`.
+    #[inline]
+    pub const fn cursor(&self) -> (usize, usize) {
+        (self.x, self.y)
+    }
+
+    /// Moves the cursor to the character cell, where the next character is written.
+    #[inline]
+    pub fn set_cursor(&mut self, col: usize, row: usize) {
+        self.x = col;
+        self.y = row;
+    }
+
+    /// Clears the line at the cursor and moves the cursor to the beginning of it.
+    pub fn clear_line(&mut self) {
+        if let Some(mut screen) = System::try_lock_screen() {
+            self.clear_line_in(&mut screen);
+        } else {
+            self.x = 0;
+        }
+    }
+
+    fn clear_line_in(&mut self, bitmap: &mut Bitmap) {
+        let font_size = self.font_size();
+        bitmap.fill_rect(
+            Rect::new(
+                0,
+                self.y as isize * font_size.height(),
+                bitmap.width() as isize,
+                font_size.height(),
+            ),
+            self.bg_color.into(),
+        );
+        self.x = 0;
+    }
+
+    #[inline]
+    fn font_size(&self) -> Size {
+        Size::new(self.font.width(), self.font.line_height())
+    }
+
+    fn put_char(&mut self, bitmap: &mut Bitmap, c: char) {
         // let font = FontManager::fixed_system_font();
         let font = self.font;
-        let font_size = Size::new(font.width(), font.line_height());
-        let mut bitmap = System::main_screen();
-        let bitmap = &mut bitmap;
+        let font_size = self.font_size();
 
         // check bounds
         let cols = bitmap.width() / font_size.width() as usize;
@@ -92,3 +169,69 @@ impl fmt::Write for EmConsole {
         Ok(())
     }
 }
+
+impl TtyWrite for EmConsole {
+    fn reset(&mut self) -> Result<(), TtyError> {
+        if let Some(mut screen) = System::try_lock_screen() {
+            let bounds = screen.bounds();
+            screen.fill_rect(bounds, self.bg_color.into());
+        }
+        self.x = 0;
+        self.y = 0;
+        Ok(())
+    }
+
+    fn dims(&self) -> (isize, isize) {
+        let screen_size = System::main_screen_size();
+        let font_size = self.font_size();
+        (
+            screen_size.width() / font_size.width(),
+            screen_size.height() / font_size.height(),
+        )
+    }
+
+    fn cursor_position(&self) -> (isize, isize) {
+        let (col, row) = self.cursor();
+        (col as isize, row as isize)
+    }
+
+    fn set_cursor_position(&mut self, x: isize, y: isize) {
+        self.set_cursor(x.max(0) as usize, y.max(0) as usize);
+    }
+
+    fn is_cursor_enabled(&self) -> bool {
+        false
+    }
+
+    fn set_cursor_enabled(&mut self, _enabled: bool) -> bool {
+        false
+    }
+
+    fn attribute(&self) -> u8 {
+        (self.bg_color.0 << 4) | (self.fg_color.0 & 0x0F)
+    }
+
+    fn set_attribute(&mut self, attribute: u8) {
+        self.fg_color = IndexedColor(attribute & 0x0F);
+        self.bg_color = IndexedColor(attribute >> 4);
+    }
+}
+
+impl TtyRead for EmConsole {
+    fn read_async(&self) -> Pin<Box<dyn Future<Output = TtyReadResult> + '_>> {
+        Box::pin(EmConsoleReader {})
+    }
+}
+
+impl Tty for EmConsole {}
+
+/// The emergency console has no input.
+struct EmConsoleReader {}
+
+impl Future for EmConsoleReader {
+    type Output = TtyReadResult;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Poll::Ready(Err(TtyError::EndOfStream))
+    }
+}
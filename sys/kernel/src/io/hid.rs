@@ -22,7 +22,18 @@ impl Usage {
     pub const NUMBER_MAX: Usage = Usage(0x27);
     pub const NON_ALPHABET_MIN: Usage = Usage(0x28);
     pub const NON_ALPHABET_MAX: Usage = Usage(0x38);
+    pub const F1: Usage = Usage(0x3A);
+    pub const F12: Usage = Usage(0x45);
+    pub const INSERT: Usage = Usage(0x49);
+    pub const HOME: Usage = Usage(0x4A);
+    pub const PAGE_UP: Usage = Usage(0x4B);
     pub const DELETE: Usage = Usage(0x4C);
+    pub const END: Usage = Usage(0x4D);
+    pub const PAGE_DOWN: Usage = Usage(0x4E);
+    pub const ARROW_RIGHT: Usage = Usage(0x4F);
+    pub const ARROW_LEFT: Usage = Usage(0x50);
+    pub const ARROW_DOWN: Usage = Usage(0x51);
+    pub const ARROW_UP: Usage = Usage(0x52);
     pub const NUMPAD_MIN: Usage = Usage(0x54);
     pub const NUMPAD_MAX: Usage = Usage(0x63);
     pub const INTERNATIONAL_1: Usage = Usage(0x87);
@@ -142,6 +153,11 @@ impl KeyEvent {
         unsafe { KeyEventFlags::from_bits_unchecked(((self.0.get() >> 24) & 0xFF) as u8) }
     }
 
+    /// Returns the navigation or function key of this event, if any.
+    pub fn key_code(self) -> Option<KeyCode> {
+        KeyCode::from_usage(self.usage())
+    }
+
     pub fn key_data(self) -> Option<Self> {
         if self.usage() != Usage::NONE && !self.flags().contains(KeyEventFlags::BREAK) {
             Some(self)
@@ -161,6 +177,157 @@ impl Into<char> for KeyEvent {
     }
 }
 
+/// Keys that have no character representation
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KeyCode {
+    ArrowUp,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Insert,
+    Delete,
+    /// F1 to F12
+    Function(u8),
+}
+
+impl KeyCode {
+    pub fn from_usage(usage: Usage) -> Option<Self> {
+        match usage {
+            Usage::ARROW_UP => Some(Self::ArrowUp),
+            Usage::ARROW_DOWN => Some(Self::ArrowDown),
+            Usage::ARROW_LEFT => Some(Self::ArrowLeft),
+            Usage::ARROW_RIGHT => Some(Self::ArrowRight),
+            Usage::HOME => Some(Self::Home),
+            Usage::END => Some(Self::End),
+            Usage::PAGE_UP => Some(Self::PageUp),
+            Usage::PAGE_DOWN => Some(Self::PageDown),
+            Usage::INSERT => Some(Self::Insert),
+            Usage::DELETE => Some(Self::Delete),
+            _ => {
+                if usage >= Usage::F1 && usage <= Usage::F12 {
+                    Some(Self::Function(usage.0 - Usage::F1.0 + 1))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// Decodes PS/2 scancode set 2 into key events.
+pub struct ScancodeSet2 {
+    extended: bool,
+    is_break: bool,
+    skip: usize,
+    modifier: Modifier,
+}
+
+impl ScancodeSet2 {
+    const PREFIX_EXTENDED: u8 = 0xE0;
+    const PREFIX_PAUSE: u8 = 0xE1;
+    const PREFIX_BREAK: u8 = 0xF0;
+
+    /// The number of bytes following `E1` in the Pause key sequence
+    const PAUSE_SEQUENCE_LEN: usize = 7;
+
+    pub const fn new() -> Self {
+        Self {
+            extended: false,
+            is_break: false,
+            skip: 0,
+            modifier: Modifier::empty(),
+        }
+    }
+
+    #[inline]
+    pub const fn modifier(&self) -> Modifier {
+        self.modifier
+    }
+
+    /// Feeds a byte from the keyboard, returns the key event when a sequence is complete.
+    pub fn feed(&mut self, data: u8) -> Option<KeyEvent> {
+        if self.skip > 0 {
+            self.skip -= 1;
+            return None;
+        }
+        match data {
+            Self::PREFIX_EXTENDED => {
+                self.extended = true;
+                return None;
+            }
+            Self::PREFIX_BREAK => {
+                self.is_break = true;
+                return None;
+            }
+            Self::PREFIX_PAUSE => {
+                self.skip = Self::PAUSE_SEQUENCE_LEN;
+                return None;
+            }
+            _ => (),
+        }
+
+        let usage = Self::to_usage(data, self.extended);
+        let flags = if self.is_break {
+            KeyEventFlags::BREAK
+        } else {
+            KeyEventFlags::empty()
+        };
+        self.extended = false;
+        self.is_break = false;
+
+        if usage == Usage::NONE {
+            None
+        } else if usage >= Usage::MOD_MIN && usage <= Usage::MOD_MAX {
+            let bit_position =
+                unsafe { Modifier::from_bits_unchecked(1 << (usage.0 - Usage::MOD_MIN.0)) };
+            self.modifier
+                .set(bit_position, !flags.contains(KeyEventFlags::BREAK));
+            Some(KeyEvent::new(Usage::NONE, self.modifier, flags))
+        } else {
+            Some(KeyEvent::new(usage, self.modifier, flags))
+        }
+    }
+
+    /// Converts a scancode to the HID usage.
+    const fn to_usage(data: u8, extended: bool) -> Usage {
+        if extended {
+            Usage(match data {
+                0x11 => 0xE6,
+                0x14 => 0xE4,
+                0x1F => 0xE3,
+                0x27 => 0xE7,
+                0x2F => 0x65,
+                0x37 => 0x66,
+                0x4A => 0x54,
+                0x5A => 0x58,
+                0x69 => 0x4D,
+                0x6B => 0x50,
+                0x6C => 0x4A,
+                0x70 => 0x49,
+                0x71 => 0x4C,
+                0x72 => 0x51,
+                0x74 => 0x4F,
+                0x75 => 0x52,
+                0x7A => 0x4E,
+                0x7C => 0x46,
+                0x7D => 0x4B,
+                _ => 0,
+            })
+        } else if (data as usize) < SCANCODE_SET2_TO_HID.len() {
+            Usage(SCANCODE_SET2_TO_HID[data as usize])
+        } else if data == 0x83 {
+            // F7
+            Usage(0x40)
+        } else {
+            Usage::NONE
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
 pub struct MouseReport<T>
@@ -346,6 +513,18 @@ static USAGE_TO_CHAR_NON_ALPLABET_109: [char; 27] = [
     '^', '@', '[', ']', ']', ';', ':', '`', ',', '.', '/',
 ];
 
+// Scancode set 2 to HID usage table
+static SCANCODE_SET2_TO_HID: [u8; 128] = [
+    0x00, 0x42, 0x00, 0x3E, 0x3C, 0x3A, 0x3B, 0x45, 0x00, 0x43, 0x41, 0x3F, 0x3D, 0x2B, 0x35, 0x00,
+    0x00, 0xE2, 0xE1, 0x88, 0xE0, 0x14, 0x1E, 0x00, 0x00, 0x00, 0x1D, 0x16, 0x04, 0x1A, 0x1F, 0x00,
+    0x00, 0x06, 0x1B, 0x07, 0x08, 0x21, 0x20, 0x00, 0x00, 0x2C, 0x19, 0x09, 0x17, 0x15, 0x22, 0x00,
+    0x00, 0x11, 0x05, 0x0B, 0x0A, 0x1C, 0x23, 0x00, 0x00, 0x00, 0x10, 0x0D, 0x18, 0x24, 0x25, 0x00,
+    0x00, 0x36, 0x0E, 0x0C, 0x12, 0x27, 0x26, 0x00, 0x00, 0x37, 0x38, 0x0F, 0x33, 0x13, 0x2D, 0x00,
+    0x00, 0x87, 0x34, 0x00, 0x2F, 0x2E, 0x00, 0x00, 0x39, 0xE5, 0x28, 0x30, 0x00, 0x31, 0x00, 0x00,
+    0x00, 0x64, 0x00, 0x00, 0x8A, 0x00, 0x2A, 0x8B, 0x00, 0x59, 0x89, 0x5C, 0x5F, 0x00, 0x00, 0x00,
+    0x62, 0x63, 0x5A, 0x5D, 0x5E, 0x60, 0x29, 0x53, 0x44, 0x57, 0x5B, 0x56, 0x55, 0x61, 0x47, 0x00,
+];
+
 // Numpads
 static USAGE_TO_CHAR_NUMPAD: [char; 16] = [
     '/', '*', '-', '+', '\x0D', '1', '2', '3', '4', '5', '6', '7', '8', '9', '0', '.',
@@ -157,3 +157,73 @@ impl TtyRead for CombinedTty<'_> {
 }
 
 impl Tty for CombinedTty<'_> {}
+
+/// Echoes everything written to the terminal, e.g. to the serial port.
+pub struct TeeTty<'a> {
+    stdout: Box<dyn Tty + 'a>,
+    echo: &'a mut dyn Write,
+}
+
+impl<'a> TeeTty<'a> {
+    pub fn new(stdout: Box<dyn Tty + 'a>, echo: &'a mut dyn Write) -> Self {
+        Self { stdout, echo }
+    }
+}
+
+impl Write for TeeTty<'_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let _ = self.echo.write_str(s);
+        self.stdout.write_str(s)
+    }
+}
+
+impl TtyWrite for TeeTty<'_> {
+    #[inline]
+    fn reset(&mut self) -> Result<(), TtyError> {
+        self.stdout.reset()
+    }
+
+    #[inline]
+    fn dims(&self) -> (isize, isize) {
+        self.stdout.dims()
+    }
+
+    #[inline]
+    fn cursor_position(&self) -> (isize, isize) {
+        self.stdout.cursor_position()
+    }
+
+    #[inline]
+    fn set_cursor_position(&mut self, x: isize, y: isize) {
+        self.stdout.set_cursor_position(x, y)
+    }
+
+    #[inline]
+    fn is_cursor_enabled(&self) -> bool {
+        self.stdout.is_cursor_enabled()
+    }
+
+    #[inline]
+    fn set_cursor_enabled(&mut self, enabled: bool) -> bool {
+        self.stdout.set_cursor_enabled(enabled)
+    }
+
+    #[inline]
+    fn attribute(&self) -> u8 {
+        self.stdout.attribute()
+    }
+
+    #[inline]
+    fn set_attribute(&mut self, attribute: u8) {
+        self.stdout.set_attribute(attribute)
+    }
+}
+
+impl TtyRead for TeeTty<'_> {
+    #[inline]
+    fn read_async(&self) -> Pin<Box<dyn Future<Output = TtyReadResult> + '_>> {
+        self.stdout.read_async()
+    }
+}
+
+impl Tty for TeeTty<'_> {}
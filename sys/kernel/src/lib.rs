@@ -6,6 +6,7 @@
 #![feature(associated_type_bounds)]
 #![feature(option_result_contains)]
 #![feature(core_intrinsics)]
+#![feature(panic_info_message)]
 
 use arch::cpu::Cpu;
 use core::fmt::Write;
@@ -58,6 +59,5 @@ macro_rules! entry {
 
 #[panic_handler]
 fn panic(info: &PanicInfo) -> ! {
-    let _ = write!(System::em_console(), "{}", info);
-    unsafe { Cpu::stop() };
+    util::panic::panic_stop(info)
 }
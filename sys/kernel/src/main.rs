@@ -49,11 +49,11 @@ impl Shell {
         shared.path_ext.push("wasm".to_string());
 
         WindowManager::set_desktop_color(AmbiguousColor::from_rgb(0x2196F3));
-        if let Ok(mut file) = FileManager::open("wall.bmp") {
-            let stat = file.stat().unwrap();
-            let mut vec = Vec::with_capacity(stat.len() as usize);
-            file.read_to_end(&mut vec);
-            if let Some(dib) = ImageLoader::from_msdib(vec.as_slice()) {
+        if let Some(blob) = FileManager::open_ci("wall.bmp")
+            .ok()
+            .and_then(|file| file.as_slice())
+        {
+            if let Some(dib) = ImageLoader::from_msdib(blob) {
                 WindowManager::set_desktop_bitmap(&dib.as_const());
             }
         }
@@ -73,13 +73,14 @@ impl Shell {
         Self::invoke_command(stdout, "ver");
 
         writeln!(stdout, "Platform {}", System::platform(),).unwrap();
-        let screen = System::main_screen();
+        writeln!(stdout, "CPU Features {:?}", System::cpu_features()).unwrap();
+        let screen_size = System::main_screen_size();
         writeln!(
             stdout,
             "Screen {}x{} {} bit color",
-            screen.width(),
-            screen.height(),
-            screen.color_mode(),
+            screen_size.width(),
+            screen_size.height(),
+            System::main_screen_color_mode(),
         )
         .unwrap();
 
@@ -352,12 +353,8 @@ impl Shell {
 
         let window = WindowBuilder::new("Activity Monitor")
             .style_add(WindowStyle::PINCHABLE)
-            .frame(Rect::new(
-                -window_size.width - 8,
-                -window_size.height - 8,
-                window_size.width,
-                window_size.height,
-            ))
+            .size(window_size)
+            .anchored(Anchor::BottomRight)
             .bg_color(bg_color)
             .build();
         window.show();
@@ -374,6 +371,7 @@ impl Shell {
         let mut sb = StringBuffer::with_capacity(0x1000);
 
         let interval = 1000;
+        let mut last_switches = Scheduler::switch_count();
         window.create_timer(0, Duration::from_millis(0));
         while let Some(message) = window.get_message().await {
             match message {
@@ -400,6 +398,15 @@ impl Shell {
                     .unwrap();
                     writeln!(sb, "B Used").unwrap();
 
+                    let switches = Scheduler::switch_count();
+                    writeln!(
+                        sb,
+                        "Switches {}/s",
+                        switches.wrapping_sub(last_switches) * 1000 / interval as usize
+                    )
+                    .unwrap();
+                    last_switches = switches;
+
                     Scheduler::print_statistics(&mut sb, true);
 
                     window.set_needs_display();
@@ -413,8 +420,7 @@ impl Shell {
                             {
                                 let padding = 4;
                                 let item_size = Size::new(n_items as isize, 32);
-                                let rect =
-                                    Rect::new(padding, padding, item_size.width, item_size.height);
+                                let rect = Rect::from(item_size).offset(padding, padding);
                                 // let cursor = rect.x() + rect.width() + padding;
 
                                 let h_lines = 4;
@@ -473,21 +479,22 @@ impl Shell {
                         })
                         .unwrap();
                 }
-                _ => window.handle_default_message(message),
+                _ => {
+                    window.handle_default_message(message);
+                }
             }
         }
-        unimplemented!()
     }
 
     #[allow(dead_code)]
     async fn status_bar_main() {
         const STATUS_BAR_HEIGHT: isize = 24;
-        let screen_size = System::main_screen().size();
+        let screen_size = System::main_screen_size();
         let window_size = Size::new(screen_size.width(), STATUS_BAR_HEIGHT);
         let window_rect = Rect::new(0, 0, screen_size.width(), STATUS_BAR_HEIGHT);
         let window = WindowBuilder::new("Status")
             // .style(WindowStyle::BORDER | WindowStyle::FLOATING)
-            .style(WindowStyle::FLOATING)
+            .style(WindowStyle::ALWAYS_ON_TOP)
             .frame(window_rect)
             // .bg_color(AmbiguousColor::from_rgb(0xCCCCFF))
             .build();
@@ -507,7 +514,8 @@ impl Shell {
             })
             .unwrap();
         window.show();
-        WindowManager::add_screen_insets(EdgeInsets::new(STATUS_BAR_HEIGHT, 0, 0, 0));
+        let _reservation =
+            WindowManager::add_screen_insets(EdgeInsets::new(STATUS_BAR_HEIGHT, 0, 0, 0));
 
         let mut sb = StringBuffer::new();
 
@@ -522,12 +530,8 @@ impl Shell {
                 }
                 WindowMessage::Draw => {
                     sb.clear();
-                    let time = System::system_time();
-                    let tod = time.secs % 86400;
-                    let min = tod / 60 % 60;
-                    let hour = tod / 3600;
+                    let (hour, min, sec) = System::system_time().to_hms();
                     if true {
-                        let sec = tod % 60;
                         if sec % 2 == 0 {
                             write!(sb, "{:2} {:02} {:02}", hour, min, sec).unwrap();
                         } else {
@@ -559,9 +563,10 @@ impl Shell {
                         .unwrap();
                     window.invalidate_rect(clock_rect);
                 }
-                _ => window.handle_default_message(message),
+                _ => {
+                    window.handle_default_message(message);
+                }
             }
         }
-        unimplemented!()
     }
 }
@@ -2,10 +2,13 @@
 use super::*;
 use core::alloc::{GlobalAlloc, Layout};
 use core::num::NonZeroUsize;
+use core::sync::atomic::*;
 
 #[global_allocator]
 static mut ALLOCATOR: CustomAlloc = CustomAlloc::new();
 
+static ALLOCATION_COUNT: AtomicUsize = AtomicUsize::new(0);
+
 pub struct CustomAlloc {
     _dummy: (),
 }
@@ -14,10 +17,17 @@ impl CustomAlloc {
     const fn new() -> Self {
         CustomAlloc { _dummy: () }
     }
+
+    /// Returns the number of allocations made so far.
+    #[inline]
+    pub fn allocation_count() -> usize {
+        ALLOCATION_COUNT.load(Ordering::Relaxed)
+    }
 }
 
 unsafe impl GlobalAlloc for CustomAlloc {
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATION_COUNT.fetch_add(1, Ordering::Relaxed);
         MemoryManager::zalloc(layout).map(|v| v.get()).unwrap_or(0) as *mut u8
     }
     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
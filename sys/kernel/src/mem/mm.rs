@@ -3,10 +3,13 @@
 use super::slab::SlabAllocator;
 use super::string::StringBuffer;
 use crate::arch::cpu::Cpu;
+use crate::task::scheduler::{ProcessId, Scheduler};
 use crate::*;
 use bitflags::*;
 use core::alloc::Layout;
+use core::mem::size_of;
 use core::num::*;
+use core::ptr::NonNull;
 use toeboot::*;
 
 static mut MM: MemoryManager = MemoryManager::new();
@@ -18,11 +21,15 @@ pub struct MemoryManager {
     n_free: usize,
     pairs: [MemFreePair; Self::MAX_FREE_PAIRS],
     slab: Option<SlabAllocator>,
+    usage: ProcessUsageTable,
+    low_pool: LowMemoryPool,
+    low_memory: LowMemoryWatch,
 }
 
 impl MemoryManager {
     const MAX_FREE_PAIRS: usize = 1024;
     pub const PAGE_SIZE_MIN: usize = 0x1000;
+    const LOW_POOL_SIZE: usize = LowMemoryPool::MAX_PAGES * Self::PAGE_SIZE_MIN;
 
     const fn new() -> Self {
         Self {
@@ -32,6 +39,9 @@ impl MemoryManager {
             n_free: 0,
             pairs: [MemFreePair::empty(); Self::MAX_FREE_PAIRS],
             slab: None,
+            usage: ProcessUsageTable::new(),
+            low_pool: LowMemoryPool::empty(),
+            low_memory: LowMemoryWatch::empty(),
         }
     }
 
@@ -46,6 +56,16 @@ impl MemoryManager {
         };
         shared.n_free = 1;
 
+        // Reserve the pool for legacy DMA before anything else is allocated
+        let pair = &mut shared.pairs[0];
+        let base = (pair.base + Self::PAGE_SIZE_MIN - 1) & !(Self::PAGE_SIZE_MIN - 1);
+        let end = base + Self::LOW_POOL_SIZE;
+        if end <= LowMemoryPool::LIMIT && end <= pair.base + pair.size {
+            shared.low_pool = LowMemoryPool::new(base, Self::LOW_POOL_SIZE);
+            pair.size -= end - pair.base;
+            pair.base = end;
+        }
+
         shared.slab = Some(SlabAllocator::new());
 
         // todo!();
@@ -87,6 +107,49 @@ impl MemoryManager {
         total
     }
 
+    /// Sets the hook called once when free memory drops below `threshold`.
+    ///
+    /// The hook is armed again when free memory recovers above the threshold plus a quarter.
+    pub fn set_low_memory_hook(threshold: usize, hook: fn()) {
+        unsafe {
+            Cpu::without_interrupts(|| {
+                let shared = Self::shared();
+                shared.low_memory = LowMemoryWatch::new(threshold, hook);
+            })
+        }
+    }
+
+    /// Returns the low memory hook if it should be called now.
+    #[inline]
+    fn check_low_memory() -> Option<fn()> {
+        let shared = Self::shared();
+        if shared.low_memory.hook.is_some() {
+            let free = Self::free_memory_size();
+            shared.low_memory.update(free)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the size of memory currently allocated by the process.
+    #[inline]
+    pub fn process_usage(pid: ProcessId) -> usize {
+        let shared = Self::shared();
+        shared.usage.get(pid.as_usize())
+    }
+
+    /// Returns the size of allocated memory that could not be attributed to a process.
+    #[inline]
+    pub fn unattributed_usage() -> usize {
+        let shared = Self::shared();
+        shared.usage.unattributed
+    }
+
+    #[inline]
+    fn current_pid() -> usize {
+        Scheduler::current_pid().map(|v| v.as_usize()).unwrap_or(0)
+    }
+
     #[inline]
     pub unsafe fn direct_map(
         base: usize,
@@ -96,6 +159,28 @@ impl MemoryManager {
         NonZeroUsize::new(base).ok_or(AllocationError::InvalidArgument)
     }
 
+    /// Allocates physically contiguous memory below 16MB for legacy DMA.
+    pub fn alloc_low(size: usize) -> Option<PhysicalAddress> {
+        unsafe {
+            Cpu::without_interrupts(|| {
+                let shared = Self::shared();
+                shared.low_pool.alloc(size).map(PhysicalAddress)
+            })
+        }
+    }
+
+    /// Returns the memory allocated by `alloc_low` to the pool.
+    pub unsafe fn free_low(addr: PhysicalAddress, size: usize) -> Result<(), DeallocationError> {
+        Cpu::without_interrupts(|| {
+            let shared = Self::shared();
+            if shared.low_pool.free(addr.0, size) {
+                Ok(())
+            } else {
+                Err(DeallocationError::InvalidArgument)
+            }
+        })
+    }
+
     /// Allocate static pages
     unsafe fn static_alloc(layout: Layout) -> Result<NonZeroUsize, AllocationError> {
         let shared = Self::shared();
@@ -116,17 +201,23 @@ impl MemoryManager {
 
     /// Allocate kernel memory
     pub unsafe fn zalloc(layout: Layout) -> Result<NonZeroUsize, AllocationError> {
-        Cpu::without_interrupts(|| {
+        let (result, hook) = Cpu::without_interrupts(|| {
             let shared = Self::shared();
-            if let Some(slab) = &shared.slab {
-                let r = slab.alloc(layout);
-                match r {
-                    Err(AllocationError::Unsupported) => (),
-                    _ => return r,
-                }
+            let result = match shared.slab.as_ref().map(|slab| slab.alloc(layout)) {
+                Some(Err(AllocationError::Unsupported)) | None => Self::static_alloc(layout),
+                Some(r) => r,
+            };
+            if let Ok(ptr) = result {
+                shared
+                    .usage
+                    .alloc(ptr.get(), Self::current_pid(), layout.size());
             }
-            Self::static_alloc(layout)
-        })
+            (result, Self::check_low_memory())
+        });
+        if let Some(hook) = hook {
+            hook();
+        }
+        result
     }
 
     /// Deallocate kernel memory
@@ -140,7 +231,8 @@ impl MemoryManager {
                 ptr.write_bytes(0xCC, layout.size());
 
                 let shared = Self::shared();
-                if let Some(slab) = &shared.slab {
+                shared.usage.free(base.get(), layout.size());
+                let result = if let Some(slab) = &shared.slab {
                     match slab.free(base, layout) {
                         Ok(_) => Ok(()),
                         Err(_) => {
@@ -151,13 +243,65 @@ impl MemoryManager {
                 } else {
                     shared.dummy_size += layout.size();
                     Ok(())
-                }
+                };
+                // only re-arms the hook, as free memory never decreases here
+                let _ = Self::check_low_memory();
+                result
             })
         } else {
             Ok(())
         }
     }
 
+    /// Allocate kernel memory with the specified alignment
+    ///
+    /// Alignments up to the page size are served directly.
+    /// Larger ones are over-allocated, and the original address is kept just before the returned pointer.
+    pub fn alloc_aligned(size: usize, align: usize) -> Option<NonNull<u8>> {
+        if !align.is_power_of_two() || size == 0 {
+            return None;
+        }
+        unsafe {
+            if align <= Self::PAGE_SIZE_MIN {
+                let layout = Layout::from_size_align(size, align).ok()?;
+                Self::zalloc(layout)
+                    .ok()
+                    .and_then(|v| NonNull::new(v.get() as *mut u8))
+            } else {
+                let layout = Self::over_aligned_layout(size, align)?;
+                let base = Self::zalloc(layout).ok()?.get();
+                let ptr = Self::place_aligned(base, align);
+                (ptr as *mut usize).sub(1).write(base);
+                NonNull::new(ptr as *mut u8)
+            }
+        }
+    }
+
+    /// Deallocate memory allocated by `alloc_aligned`
+    ///
+    /// SAFETY: `size` and `align` must be the same as the allocation.
+    pub unsafe fn free_aligned(ptr: NonNull<u8>, size: usize, align: usize) {
+        if align <= Self::PAGE_SIZE_MIN {
+            let layout = Layout::from_size_align_unchecked(size, align);
+            let _ = Self::zfree(NonZeroUsize::new(ptr.as_ptr() as usize), layout);
+        } else if let Some(layout) = Self::over_aligned_layout(size, align) {
+            let base = (ptr.as_ptr() as *const usize).sub(1).read();
+            let _ = Self::zfree(NonZeroUsize::new(base), layout);
+        }
+    }
+
+    #[inline]
+    fn over_aligned_layout(size: usize, align: usize) -> Option<Layout> {
+        size.checked_add(align + size_of::<usize>())
+            .and_then(|size| Layout::from_size_align(size, Self::PAGE_SIZE_MIN).ok())
+    }
+
+    /// Returns the first aligned address after the header word
+    #[inline]
+    const fn place_aligned(base: usize, align: usize) -> usize {
+        (base + size_of::<usize>() + align - 1) & !(align - 1)
+    }
+
     #[allow(dead_code)]
     pub fn statistics(sb: &mut StringBuffer) {
         let shared = Self::shared();
@@ -187,6 +331,258 @@ impl MemoryManager {
     }
 }
 
+/// Memory usage per process, without any heap allocation.
+///
+/// The owner of each allocation is kept in a hash table keyed by its address,
+/// so that it is credited back to the same process whichever thread frees it.
+/// Allocations that do not fit in the tables are counted as unattributed.
+struct ProcessUsageTable {
+    entries: [ProcessUsage; Self::MAX_ENTRIES],
+    owners: [AllocationOwner; Self::MAX_OWNERS],
+    unattributed: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ProcessUsage {
+    pid: usize,
+    size: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct AllocationOwner {
+    ptr: usize,
+    pid: usize,
+}
+
+impl AllocationOwner {
+    const EMPTY: Self = Self { ptr: 0, pid: 0 };
+}
+
+impl ProcessUsageTable {
+    const MAX_ENTRIES: usize = 64;
+    const MAX_OWNERS: usize = 4096;
+    const MAX_PROBES: usize = 16;
+
+    const fn new() -> Self {
+        Self {
+            entries: [ProcessUsage { pid: 0, size: 0 }; Self::MAX_ENTRIES],
+            owners: [AllocationOwner::EMPTY; Self::MAX_OWNERS],
+            unattributed: 0,
+        }
+    }
+
+    /// Charges the allocation to the process.
+    fn alloc(&mut self, ptr: usize, pid: usize, size: usize) {
+        if self.add(pid, size) {
+            if self.insert_owner(ptr, pid) {
+                return;
+            }
+            self.sub(pid, size);
+        }
+        self.unattributed += size;
+    }
+
+    /// Credits the allocation back to the process that owns it.
+    fn free(&mut self, ptr: usize, size: usize) {
+        match self.remove_owner(ptr) {
+            Some(pid) => self.sub(pid, size),
+            None => self.unattributed = self.unattributed.saturating_sub(size),
+        }
+    }
+
+    fn get(&self, pid: usize) -> usize {
+        self.entries
+            .iter()
+            .find(|v| v.size > 0 && v.pid == pid)
+            .map(|v| v.size)
+            .unwrap_or(0)
+    }
+
+    /// Returns `false` if there is no room for a new process.
+    fn add(&mut self, pid: usize, size: usize) -> bool {
+        if let Some(entry) = self.entries.iter_mut().find(|v| v.size > 0 && v.pid == pid) {
+            entry.size += size;
+            true
+        } else if let Some(entry) = self.entries.iter_mut().find(|v| v.size == 0) {
+            entry.pid = pid;
+            entry.size = size;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn sub(&mut self, pid: usize, size: usize) {
+        if let Some(entry) = self.entries.iter_mut().find(|v| v.size > 0 && v.pid == pid) {
+            entry.size = entry.size.saturating_sub(size);
+        }
+    }
+
+    #[inline]
+    const fn home(ptr: usize) -> usize {
+        ((ptr >> 3).wrapping_mul(0x9E37_79B9) >> 12) & (Self::MAX_OWNERS - 1)
+    }
+
+    #[inline]
+    const fn distance(from: usize, to: usize) -> usize {
+        to.wrapping_sub(from) & (Self::MAX_OWNERS - 1)
+    }
+
+    fn insert_owner(&mut self, ptr: usize, pid: usize) -> bool {
+        let home = Self::home(ptr);
+        for i in 0..Self::MAX_PROBES {
+            let owner = &mut self.owners[(home + i) & (Self::MAX_OWNERS - 1)];
+            if owner.ptr == 0 {
+                *owner = AllocationOwner { ptr, pid };
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Removes the owner by shifting the following entries back, so that no tombstones are left.
+    fn remove_owner(&mut self, ptr: usize) -> Option<usize> {
+        let home = Self::home(ptr);
+        let mut hole = (0..Self::MAX_PROBES)
+            .map(|i| (home + i) & (Self::MAX_OWNERS - 1))
+            .take_while(|&index| self.owners[index].ptr != 0)
+            .find(|&index| self.owners[index].ptr == ptr)?;
+        let pid = self.owners[hole].pid;
+        self.owners[hole] = AllocationOwner::EMPTY;
+
+        let mut index = hole;
+        loop {
+            index = (index + 1) & (Self::MAX_OWNERS - 1);
+            let owner = self.owners[index];
+            if owner.ptr == 0 {
+                break;
+            }
+            let home = Self::home(owner.ptr);
+            if Self::distance(home, hole) < Self::distance(home, index) {
+                self.owners[hole] = owner;
+                self.owners[index] = AllocationOwner::EMPTY;
+                hole = index;
+            }
+        }
+        Some(pid)
+    }
+}
+
+/// Physical memory address
+#[repr(transparent)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PhysicalAddress(pub usize);
+
+/// Physically contiguous pages below 16MB, which the ISA DMA controller can reach
+struct LowMemoryPool {
+    base: usize,
+    pages: usize,
+    used: u64,
+}
+
+impl LowMemoryPool {
+    const LIMIT: usize = 0x0100_0000;
+    const MAX_PAGES: usize = 64;
+    const PAGE_SIZE: usize = MemoryManager::PAGE_SIZE_MIN;
+
+    const fn empty() -> Self {
+        Self {
+            base: 0,
+            pages: 0,
+            used: 0,
+        }
+    }
+
+    fn new(base: usize, size: usize) -> Self {
+        let limit = Self::LIMIT.saturating_sub(base) / Self::PAGE_SIZE;
+        let pages = usize::min(usize::min(size / Self::PAGE_SIZE, Self::MAX_PAGES), limit);
+        Self {
+            base,
+            pages,
+            used: 0,
+        }
+    }
+
+    #[inline]
+    fn mask(pages: usize) -> u64 {
+        u64::MAX >> (64 - pages)
+    }
+
+    fn alloc(&mut self, size: usize) -> Option<usize> {
+        let pages = (size + Self::PAGE_SIZE - 1) / Self::PAGE_SIZE;
+        if pages == 0 || pages > self.pages {
+            return None;
+        }
+        let mask = Self::mask(pages);
+        for index in 0..=(self.pages - pages) {
+            if self.used & (mask << index) == 0 {
+                self.used |= mask << index;
+                return Some(self.base + index * Self::PAGE_SIZE);
+            }
+        }
+        None
+    }
+
+    fn free(&mut self, addr: usize, size: usize) -> bool {
+        let pages = (size + Self::PAGE_SIZE - 1) / Self::PAGE_SIZE;
+        let offset = match addr.checked_sub(self.base) {
+            Some(v) => v,
+            None => return false,
+        };
+        let index = offset / Self::PAGE_SIZE;
+        if pages == 0 || offset % Self::PAGE_SIZE != 0 || index + pages > self.pages {
+            return false;
+        }
+        let mask = Self::mask(pages) << index;
+        if self.used & mask != mask {
+            return false;
+        }
+        self.used &= !mask;
+        true
+    }
+}
+
+/// Watermark of free memory to call the low memory hook
+struct LowMemoryWatch {
+    threshold: usize,
+    recover: usize,
+    hook: Option<fn()>,
+    armed: bool,
+}
+
+impl LowMemoryWatch {
+    const fn empty() -> Self {
+        Self {
+            threshold: 0,
+            recover: 0,
+            hook: None,
+            armed: false,
+        }
+    }
+
+    fn new(threshold: usize, hook: fn()) -> Self {
+        Self {
+            threshold,
+            recover: threshold.saturating_add(threshold / 4),
+            hook: Some(hook),
+            armed: true,
+        }
+    }
+
+    /// Returns the hook when free memory drops below the threshold while armed.
+    fn update(&mut self, free: usize) -> Option<fn()> {
+        if self.armed {
+            if free < self.threshold {
+                self.armed = false;
+                return self.hook;
+            }
+        } else if free > self.recover {
+            self.armed = true;
+        }
+        None
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 struct MemFreePair {
     base: usize,
@@ -1,13 +1,15 @@
 // MEG-OS Arlequin Subsystem
 
 use super::*;
-use crate::io::hid::*;
+use crate::fs::*;
+use crate::mem::MemoryManager;
 use crate::util::rng::*;
 use crate::util::text::*;
 use alloc::collections::BTreeMap;
 use byteorder::*;
 use core::{
-    convert::TryFrom, intrinsics::transmute, num::NonZeroU32, sync::atomic::*, time::Duration,
+    cell::UnsafeCell, convert::TryFrom, intrinsics::transmute, num::NonZeroU32, sync::atomic::*,
+    time::Duration,
 };
 use megstd::drawing::*;
 use myosabi::*;
@@ -76,24 +78,31 @@ pub struct ArleRuntime {
     module: WasmModule,
     next_handle: AtomicUsize,
     windows: BTreeMap<usize, WindowHandle>,
+    files: BTreeMap<usize, FsRawFileControlBlock>,
+    surfaces: BTreeMap<usize, UnsafeCell<BoxedBitmap<'static>>>,
     rng32: XorShift32,
-    key_buffer: Vec<KeyEvent>,
+}
+
+/// Where the drawing functions draw
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DrawTarget {
+    Window(WindowHandle),
+    Surface(usize),
 }
 
 impl ArleRuntime {
     const MOD_NAME: &'static str = "megos-canary";
     const ENTRY_FUNC_NAME: &'static str = "_start";
 
-    const SIZE_KEYBUFFER: usize = 32;
-
     fn new(module: WasmModule) -> Box<Self> {
         Box::new(Self {
             // uuid: Uuid::generate().unwrap(),
             module,
             next_handle: AtomicUsize::new(1),
             windows: BTreeMap::new(),
+            files: BTreeMap::new(),
+            surfaces: BTreeMap::new(),
             rng32: XorShift32::default(),
-            key_buffer: Vec::with_capacity(Self::SIZE_KEYBUFFER),
         })
     }
 
@@ -164,6 +173,16 @@ impl ArleRuntime {
                 let sub_func_no = params.get_usize()?;
                 match sub_func_no {
                     0 => return Ok(WasmValue::from(System::version().as_u32())),
+                    1 => {
+                        let memory = self
+                            .module
+                            .memories()
+                            .get_mut(0)
+                            .ok_or(WasmRuntimeError::OutOfMemory)?;
+                        let buf = params.get_buffer_mut(memory)?;
+                        let len = Self::system_info().write_to(buf);
+                        return Ok(WasmValue::from(len as u32));
+                    }
                     _ => (),
                 }
             }
@@ -182,6 +201,7 @@ impl ArleRuntime {
                     .style_add(WindowStyle::NAKED)
                     .size(size)
                     .bg_color(bg_color)
+                    .with_char_queue()
                     .bitmap_strategy(if (window_option & MyOsAbi::WINDOW_32BIT_BITMAP) != 0 {
                         BitmapStrategy::Expressive
                     } else {
@@ -204,111 +224,115 @@ impl ArleRuntime {
                 }
             }
             svc::Function::DrawString => {
-                if let Some(window) = params.get_window(self)? {
+                if let Some(target) = params.get_target(self)? {
                     let max_lines = 0;
                     let origin = params.get_point()?;
                     let text = params.get_string(memory).unwrap_or("");
                     let color = params.get_color()?;
-                    let mut rect = Rect::from(window.content_rect().size());
-                    rect.origin = origin;
-                    rect.size.width -= origin.x;
-                    rect.size.height -= origin.y;
-                    let _ = window.draw_in_rect(rect, |bitmap| {
-                        AttributedString::props()
-                            .align(TextAlignment::Left)
-                            .valign(VerticalAlignment::Top)
-                            .color(color)
-                            .text(text)
-                            .draw_text(bitmap, rect.size.into(), max_lines);
-                    });
-                    window.set_needs_display();
+                    let rect = Rect {
+                        origin,
+                        size: self.target_size(target),
+                    };
+                    if let Some(rect) = self.clip_rect(target, rect) {
+                        self.draw_in_target(target, rect, |bitmap| {
+                            AttributedString::props()
+                                .align(TextAlignment::Left)
+                                .valign(VerticalAlignment::Top)
+                                .color(color)
+                                .text(text)
+                                .draw_text(bitmap, rect.size.into(), max_lines);
+                        });
+                    }
                 }
             }
             svc::Function::FillRect => {
-                if let Some(window) = params.get_window(self)? {
+                if let Some(target) = params.get_target(self)? {
                     let origin = params.get_point()?;
                     let size = params.get_size()?;
                     let color = params.get_color()?;
                     let rect = Rect { origin, size };
-                    let _ = window.draw_in_rect(rect, |bitmap| {
-                        bitmap.fill_rect(rect.size.into(), color);
-                    });
-                    window.set_needs_display();
+                    if let Some(rect) = self.clip_rect(target, rect) {
+                        self.draw_in_target(target, rect, |bitmap| {
+                            bitmap.fill_rect(rect.size.into(), color);
+                        });
+                    }
                 }
             }
             svc::Function::DrawRect => {
-                if let Some(window) = params.get_window(self)? {
+                if let Some(target) = params.get_target(self)? {
                     let origin = params.get_point()?;
                     let size = params.get_size()?;
                     let color = params.get_color()?;
                     let rect = Rect { origin, size };
-                    let _ = window.draw_in_rect(rect, |bitmap| {
-                        bitmap.draw_rect(rect.size.into(), color);
-                    });
-                    window.set_needs_display();
+                    if let Some(clipped) = self.clip_rect(target, rect) {
+                        self.draw_in_target(target, clipped, |bitmap| {
+                            bitmap.draw_rect(rect - clipped.origin, color);
+                        });
+                    }
                 }
             }
             svc::Function::DrawLine => {
-                if let Some(window) = params.get_window(self)? {
+                if let Some(target) = params.get_target(self)? {
                     let c1 = params.get_point()?;
                     let c2 = params.get_point()?;
                     let color = params.get_color()?;
                     let rect = Rect::from(Coordinates::from_two(c1, c2)) + Size::new(1, 1);
-                    let _ = window.draw_in_rect(rect, |bitmap| {
-                        bitmap.draw_line(c1 - rect.origin, c2 - rect.origin, color);
-                    });
-                    window.set_needs_display();
+                    if let Some(clipped) = self.clip_rect(target, rect) {
+                        self.draw_in_target(target, clipped, |bitmap| {
+                            bitmap.draw_line(c1 - clipped.origin, c2 - clipped.origin, color);
+                        });
+                    }
                 }
             }
             svc::Function::WaitChar => {
                 if let Some(window) = params.get_window(self)? {
-                    let c = self.wait_key(window);
+                    let c = window.wait_char();
                     return Ok(WasmValue::I32(c.unwrap_or('\0') as i32));
                 }
             }
             svc::Function::ReadChar => {
                 if let Some(window) = params.get_window(self)? {
-                    let c = self.read_key(window);
-                    return Ok(WasmValue::from(
-                        c.map(|v| v as u32).unwrap_or(MyOsAbi::OPTION_CHAR_NONE),
-                    ));
+                    let c = Self::read_char(window);
+                    return Ok(WasmValue::from(Self::char_value(c)));
                 }
             }
             svc::Function::Blt8 => {
-                if let Some(window) = params.get_window(self)? {
+                if let Some(target) = params.get_target(self)? {
                     let origin = params.get_point()?;
                     let src = params.get_bitmap8(memory)?;
                     let rect = Rect {
                         origin,
                         size: src.size(),
                     };
-                    let _ = window.draw_in_rect(rect, |bitmap| {
-                        bitmap.blt_transparent(
-                            &ConstBitmap::from(&src),
-                            Point::default(),
-                            src.size().into(),
-                            IndexedColor::DEFAULT_KEY,
-                        );
-                    });
-                    window.set_needs_display();
+                    if let Some(clipped) = self.clip_rect(target, rect) {
+                        self.draw_in_target(target, clipped, |bitmap| {
+                            bitmap.blt_transparent(
+                                &ConstBitmap::from(&src),
+                                origin - clipped.origin,
+                                src.size().into(),
+                                IndexedColor::DEFAULT_KEY,
+                            );
+                        });
+                    }
                 }
             }
             svc::Function::Blt32 => {
-                if let Some(window) = params.get_window(self)? {
+                if let Some(target) = params.get_target(self)? {
                     let origin = params.get_point()?;
                     let src = params.get_bitmap32(memory)?;
                     let rect = Rect {
                         origin,
                         size: src.size(),
                     };
-                    let _ = window.draw_in_rect(rect, |bitmap| {
-                        bitmap.blt(
-                            &ConstBitmap::from(&src),
-                            Point::default(),
-                            src.size().into(),
-                        );
-                    });
-                    window.set_needs_display();
+                    if let Some(clipped) = self.clip_rect(target, rect) {
+                        self.draw_in_target(target, clipped, |bitmap| {
+                            bitmap.blt(
+                                &ConstBitmap::from(&src),
+                                origin - clipped.origin,
+                                src.size().into(),
+                            );
+                        });
+                    }
                 }
             }
             svc::Function::BlendRect => {
@@ -321,15 +345,23 @@ impl ArleRuntime {
                 bitmap.blend_rect(rect, color);
             }
             svc::Function::Blt1 => {
-                if let Some(window) = params.get_window(self)? {
+                if let Some(target) = params.get_target(self)? {
                     let origin = params.get_point()?;
-                    let os_bitmap = params.get_bitmap1(memory)?;
+                    let src = params.get_bitmap1(memory)?;
                     let color = params.get_color()?;
-                    let mode = params.get_usize()?;
-                    let _ = window.draw_in_rect(os_bitmap.rect(origin, mode), |bitmap| {
-                        os_bitmap.blt(bitmap, Point::default(), color, mode);
-                    });
-                    window.set_needs_display();
+                    let scale = params.get_usize()?;
+                    let rect = Rect {
+                        origin,
+                        size: Size::new(
+                            src.size().width().saturating_mul(scale as isize),
+                            src.size().height().saturating_mul(scale as isize),
+                        ),
+                    };
+                    if let Some(clipped) = self.clip_rect(target, rect) {
+                        self.draw_in_target(target, clipped, |bitmap| {
+                            src.blt_scaled(bitmap, origin - clipped.origin, color, None, scale);
+                        });
+                    }
                 }
             }
             svc::Function::RefreshWindow => {
@@ -338,6 +370,69 @@ impl ArleRuntime {
                 }
             }
 
+            svc::Function::OpenFile => {
+                let name = params.get_string(memory).unwrap_or("");
+                if let Ok(fcb) = FileManager::open(name) {
+                    let handle = self.next_handle();
+                    self.files.insert(handle, fcb);
+                    return Ok(WasmValue::I32(handle as i32));
+                }
+            }
+            svc::Function::ReadFile => {
+                let handle = params.get_usize()?;
+                let memory = self
+                    .module
+                    .memories()
+                    .get_mut(0)
+                    .ok_or(WasmRuntimeError::OutOfMemory)?;
+                let buf = params.get_buffer_mut(memory)?;
+                if let Some(fcb) = self.files.get_mut(&handle) {
+                    let len = fcb.read(buf).unwrap_or(0);
+                    return Ok(WasmValue::I32(len as i32));
+                }
+            }
+            svc::Function::CloseFile => {
+                let handle = params.get_usize()?;
+                self.files.remove(&handle);
+            }
+
+            svc::Function::CreateSurface => {
+                let size = params.get_size()?;
+                let bg_color = params.get_color().unwrap_or(WindowManager::DEFAULT_BGCOLOR);
+                let option = params.get_u32().unwrap_or(0);
+                let is_32bit = (option & MyOsAbi::SURFACE_32BIT_BITMAP) != 0;
+                let bytes_per_pixel = if is_32bit { 4 } else { 1 };
+                if Self::is_valid_surface_size(size, bytes_per_pixel) {
+                    let bitmap: BoxedBitmap = if is_32bit {
+                        BoxedBitmap32::new(size, bg_color.into()).into()
+                    } else {
+                        BoxedBitmap8::new(size, bg_color.into()).into()
+                    };
+                    let handle = self.next_handle();
+                    self.surfaces.insert(handle, UnsafeCell::new(bitmap));
+                    return Ok(WasmValue::I32(handle as i32));
+                }
+            }
+            svc::Function::BltSurface => {
+                if let Some(target) = params.get_target(self)? {
+                    let origin = params.get_point()?;
+                    let handle = params.get_usize()?;
+                    if target != DrawTarget::Surface(handle) {
+                        if let Some(src) = self.surface_const(handle) {
+                            let rect = Rect {
+                                origin,
+                                size: src.size(),
+                            };
+                            if let Some(clipped) = self.clip_rect(target, rect) {
+                                self.draw_in_target(target, clipped, |bitmap| {
+                                    bitmap.blt(&src, origin - clipped.origin, src.size().into());
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+
             svc::Function::Rand => {
                 return Ok(WasmValue::from(self.rng32.next()));
             }
@@ -359,42 +454,118 @@ impl ArleRuntime {
         Ok(WasmValue::I32(0))
     }
 
-    fn wait_key(&mut self, window: WindowHandle) -> Option<char> {
-        while let Some(message) = window.wait_message() {
-            self.process_message(window, message);
+    fn system_info() -> SystemInfo {
+        let mut info = SystemInfo::new();
+        info.version = System::version().as_u32();
+        info.platform = System::platform().into();
+        info.cpu_ver = System::cpu_ver().0;
+        info.total_memory = MemoryManager::total_memory_size() as u32;
+        info.free_memory = MemoryManager::free_memory_size() as u32;
+        if System::has_main_screen() {
+            let screen_size = System::main_screen_size();
+            info.screen_bpp = System::main_screen_color_mode() as u8;
+            info.screen_width = screen_size.width() as u16;
+            info.screen_height = screen_size.height() as u16;
+        }
+        info
+    }
 
-            if let Some(c) = self
-                .read_key_buffer()
-                .and_then(|v| v.key_data().map(|v| v.into_char()))
-            {
-                return Some(c);
-            }
+    #[inline]
+    fn target(&self, handle: usize) -> Option<DrawTarget> {
+        match self.windows.get(&handle) {
+            Some(window) => Some(*window)
+                .filter(|v| v.is_valid())
+                .map(DrawTarget::Window),
+            None => self
+                .surfaces
+                .get(&handle)
+                .map(|_| DrawTarget::Surface(handle)),
         }
-        None
     }
 
-    fn read_key(&mut self, window: WindowHandle) -> Option<char> {
-        while let Some(message) = window.read_message() {
-            self.process_message(window, message);
+    fn surface(&self, handle: usize) -> Option<Bitmap<'static>> {
+        self.surfaces
+            .get(&handle)
+            .and_then(|v| unsafe { v.get().as_mut() })
+            .map(|v| v.as_bitmap())
+    }
+
+    fn surface_const(&self, handle: usize) -> Option<ConstBitmap<'static>> {
+        self.surfaces
+            .get(&handle)
+            .and_then(|v| unsafe { v.get().as_ref() })
+            .map(|v| v.as_const())
+    }
+
+    /// Returns the size of the content area of the window or the surface
+    fn target_size(&self, target: DrawTarget) -> Size {
+        match target {
+            DrawTarget::Window(window) => window.content_rect().size(),
+            DrawTarget::Surface(handle) => self
+                .surface_const(handle)
+                .map(|v| v.size())
+                .unwrap_or_default(),
         }
-        self.read_key_buffer()
-            .and_then(|v| v.key_data().map(|v| v.into_char()))
     }
 
-    fn read_key_buffer(&mut self) -> Option<KeyEvent> {
-        if self.key_buffer.len() > 0 {
-            return Some(self.key_buffer.remove(0));
+    /// Clips the rectangle to the content area of the window or the surface
+    #[inline]
+    fn clip_rect(&self, target: DrawTarget, rect: Rect) -> Option<Rect> {
+        Rect::from(self.target_size(target)).intersection(rect)
+    }
+
+    /// Draws in the clipped rectangle of the window or the surface
+    fn draw_in_target<F>(&self, target: DrawTarget, rect: Rect, f: F)
+    where
+        F: FnOnce(&mut Bitmap) -> (),
+    {
+        match target {
+            DrawTarget::Window(window) => {
+                let _ = window.draw_in_rect(rect, f);
+                window.set_needs_display();
+            }
+            DrawTarget::Surface(handle) => {
+                if let Some(mut bitmap) = self.surface(handle) {
+                    bitmap.view(rect, |mut bitmap| f(&mut bitmap));
+                }
+            }
         }
-        None
     }
 
-    fn process_message(&mut self, window: WindowHandle, message: WindowMessage) {
-        match message {
-            WindowMessage::Key(event) => {
-                self.key_buffer.push(event);
+    /// Converts the result of `ReadChar` to its value in the ABI.
+    #[inline]
+    /// Returns whether a surface of the size can be allocated,
+    /// which must not be empty nor larger than the main screen.
+    fn is_valid_surface_size(size: Size, bytes_per_pixel: usize) -> bool {
+        let screen = System::main_screen_size();
+        if size.width() <= 0
+            || size.height() <= 0
+            || size.width() > screen.width()
+            || size.height() > screen.height()
+        {
+            return false;
+        }
+        (size.width() as usize)
+            .checked_mul(size.height() as usize)
+            .and_then(|v| v.checked_mul(bytes_per_pixel))
+            .is_some()
+    }
+
+    fn char_value(c: Option<char>) -> u32 {
+        c.map(|v| v as u32).unwrap_or(MyOsAbi::OPTION_CHAR_NONE)
+    }
+
+    /// Processes pending messages and reads a character without blocking.
+    fn read_char(window: WindowHandle) -> Option<char> {
+        while let Some(message) = window.read_message() {
+            match message {
+                WindowMessage::Draw => window.refresh(),
+                _ => {
+                    window.handle_default_message(message);
+                }
             }
-            _ => window.handle_default_message(message),
         }
+        window.read_char()
     }
 }
 
@@ -407,6 +578,8 @@ impl Personality for ArleRuntime {
         for window in self.windows.values() {
             window.close();
         }
+        self.files.clear();
+        self.surfaces.clear();
     }
 }
 
@@ -469,6 +642,15 @@ impl ParamsDecoder<'_> {
             .and_then(|v| core::str::from_utf8(v).ok())
     }
 
+    #[inline]
+    fn get_buffer_mut<'a>(
+        &mut self,
+        memory: &'a mut WasmMemory,
+    ) -> Result<&'a mut [u8], WasmRuntimeError> {
+        let memarg = self.get_memarg()?;
+        memory.slice_mut(memarg.base(), memarg.len())
+    }
+
     #[allow(dead_code)]
     #[inline]
     fn get_string16(&mut self, memory: &WasmMemory) -> Option<String> {
@@ -511,7 +693,7 @@ impl ParamsDecoder<'_> {
         let _stride = LE::read_u32(&array[8..12]) as usize;
         let base = LE::read_u32(&array[12..16]) as usize;
 
-        let len = width * height;
+        let len = Self::bitmap_len(width, height, width)?;
         let slice = memory.read_bytes(base, len)?;
 
         Ok(ConstBitmap8::from_bytes(
@@ -533,7 +715,7 @@ impl ParamsDecoder<'_> {
         let _stride = LE::read_u32(&array[8..12]) as usize;
         let base = LE::read_u32(&array[12..16]) as usize;
 
-        let len = width * height;
+        let len = Self::bitmap_len(width, height, width)?;
         let slice = memory.read_u32_array(base, len)?;
 
         Ok(ConstBitmap32::from_bytes(
@@ -542,18 +724,56 @@ impl ParamsDecoder<'_> {
         ))
     }
 
+    /// Returns the number of elements of the bitmap after validating its dimensions
+    #[inline]
+    fn bitmap_len(width: usize, height: usize, stride: usize) -> Result<usize, WasmRuntimeError> {
+        if width > i32::MAX as usize || height > i32::MAX as usize || stride > i32::MAX as usize {
+            return Err(WasmRuntimeError::InvalidParameter);
+        }
+        stride
+            .checked_mul(height)
+            .ok_or(WasmRuntimeError::OutOfBounds)
+    }
+
     fn get_bitmap1<'a>(
         &mut self,
         memory: &'a WasmMemory,
-    ) -> Result<OsBitmap1<'a>, WasmRuntimeError> {
-        let base = self.get_u32()?;
-        OsBitmap1::from_memory(memory, base)
+    ) -> Result<ConstBitmap1<'a>, WasmRuntimeError> {
+        const SIZE_OF_BITMAP: usize = 16;
+        let base = self.get_u32()? as usize;
+        let array = memory.read_bytes(base, SIZE_OF_BITMAP)?;
+
+        let width = LE::read_u32(&array[0..4]) as usize;
+        let height = LE::read_u32(&array[4..8]) as usize;
+        let stride = LE::read_u32(&array[8..12]) as usize;
+        let base = LE::read_u32(&array[12..16]) as usize;
+
+        if stride < (width + 7) / 8 {
+            return Err(WasmRuntimeError::InvalidParameter);
+        }
+        let len = Self::bitmap_len(width, height, stride)?;
+        let slice = memory.read_bytes(base, len)?;
+
+        Ok(ConstBitmap1::from_slice(
+            slice,
+            Size::new(width as isize, height as isize),
+            stride,
+        ))
+    }
+
+    #[inline]
+    fn get_target(&mut self, rt: &ArleRuntime) -> Result<Option<DrawTarget>, WasmRuntimeError> {
+        self.get_u32().map(|v| rt.target(v as usize))
     }
 
     #[inline]
     fn get_window(&mut self, rt: &ArleRuntime) -> Result<Option<WindowHandle>, WasmRuntimeError> {
-        self.get_u32()
-            .map(|v| rt.windows.get(&(v as usize)).map(|v| *v))
+        self.get_u32().map(|v| {
+            rt.windows
+                .get(&(v as usize))
+                .map(|v| *v)
+                .filter(|v| v.is_valid())
+        })
     }
 }
 
@@ -578,81 +798,3 @@ impl MemArg {
         self.len
     }
 }
-
-struct OsBitmap1<'a> {
-    slice: &'a [u8],
-    dim: Size,
-    stride: usize,
-}
-
-impl<'a> OsBitmap1<'a> {
-    fn from_memory(memory: &'a WasmMemory, base: u32) -> Result<Self, WasmRuntimeError> {
-        const SIZE_OF_BITMAP: usize = 16;
-        let array = memory.read_bytes(base as usize, SIZE_OF_BITMAP)?;
-
-        let width = LE::read_u32(&array[0..4]) as usize;
-        let height = LE::read_u32(&array[4..8]) as usize;
-        let stride = LE::read_u32(&array[8..12]) as usize;
-        let base = LE::read_u32(&array[12..16]) as usize;
-
-        let dim = Size::new(width as isize, height as isize);
-        let size = stride * height;
-        let slice = memory.read_bytes(base, size)?;
-
-        Ok(Self { slice, dim, stride })
-    }
-}
-
-impl OsBitmap1<'_> {
-    #[inline]
-    const fn rect(&self, origin: Point, mode: usize) -> Rect {
-        let scale = mode as isize;
-        Rect {
-            origin,
-            size: Size::new(self.dim.width * scale, self.dim.height * scale),
-        }
-    }
-
-    fn blt(&self, to: &mut Bitmap, origin: Point, color: AmbiguousColor, mode: usize) {
-        // TODO: clipping
-        let scale = mode as isize;
-        let stride = self.stride;
-        let mut cursor = 0;
-        let w8 = self.dim.width as usize / 8;
-        let w7 = self.dim.width as usize & 7;
-        for y in 0..self.dim.height {
-            for i in 0..w8 {
-                let data = unsafe { self.slice.get_unchecked(cursor + i) };
-                for j in 0..8 {
-                    let position = 0x80u8 >> j;
-                    if (data & position) != 0 {
-                        let x = scale * (i * 8 + j) as isize;
-                        let y = y * scale;
-                        for offset in &[(0, 0), (0, 1), (1, 0), (1, 1)] {
-                            let point =
-                                Point::new(origin.x + x + offset.0, origin.y + y + offset.1);
-                            unsafe { to.set_pixel_unchecked(point, color) };
-                        }
-                    }
-                }
-            }
-            if w7 > 0 {
-                let data = unsafe { self.slice.get_unchecked(cursor + w8) };
-                let base_x = w8 * 8;
-                for i in 0..w7 {
-                    let position = 0x80u8 >> i;
-                    if (data & position) != 0 {
-                        let x = scale * (i + base_x) as isize;
-                        let y = y * scale;
-                        for offset in &[(0, 0), (0, 1), (1, 0), (1, 1)] {
-                            let point =
-                                Point::new(origin.x + x + offset.0, origin.y + y + offset.1);
-                            unsafe { to.set_pixel_unchecked(point, color) };
-                        }
-                    }
-                }
-            }
-            cursor += stride;
-        }
-    }
-}
@@ -45,6 +45,9 @@ impl RuntimeEnvironment {
 
     pub fn exit(exit_code: usize) -> ! {
         let _ = exit_code;
+        if let Some(pid) = Scheduler::current_pid() {
+            Scheduler::exit_process(pid);
+        }
         Scheduler::exit();
     }
 }
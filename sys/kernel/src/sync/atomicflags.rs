@@ -36,6 +36,11 @@ impl<T: Into<usize>> AtomicBitflags<T> {
         Self::from_bits(value.into())
     }
 
+    #[inline]
+    pub fn bits(&self) -> usize {
+        self.repr.load(Ordering::Relaxed)
+    }
+
     #[inline]
     pub fn contains(&self, other: T) -> bool {
         let other = other.into();
@@ -83,4 +88,17 @@ impl<T: Into<usize>> AtomicBitflags<T> {
     pub fn test_and_clear(&self, bits: T) -> bool {
         Cpu::interlocked_test_and_clear(&self.repr, bits.into().trailing_zeros() as usize)
     }
+
+    /// Toggles the flag and returns whether it was set before.
+    #[inline]
+    pub fn test_and_toggle(&self, bits: T) -> bool {
+        let bits = bits.into();
+        (self.repr.fetch_xor(bits, Ordering::SeqCst) & bits) != 0
+    }
+
+    /// Replaces all flags and returns the previous ones.
+    #[inline]
+    pub fn replace(&self, value: T) -> Self {
+        Self::from_bits(self.repr.swap(value.into(), Ordering::SeqCst))
+    }
 }
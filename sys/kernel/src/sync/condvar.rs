@@ -0,0 +1,56 @@
+// Condition Variable
+
+use super::mutex::*;
+use super::semaphore::*;
+use crate::arch::cpu::Cpu;
+use core::sync::atomic::*;
+
+/// A condition variable used together with a `Mutex`.
+///
+/// A waiter may wake up spuriously, for example by a notification that was sent
+/// before it started waiting, so callers should check the condition in a loop.
+pub struct Condvar {
+    waiters: AtomicUsize,
+    sem: Semaphore,
+}
+
+impl Condvar {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            waiters: AtomicUsize::new(0),
+            sem: Semaphore::new(0),
+        }
+    }
+
+    /// Releases the lock and sleeps until notified, then acquires the lock again.
+    ///
+    /// A notification sent between releasing the lock and falling asleep is not lost.
+    pub fn wait<'a, T: ?Sized>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+        let mutex = guard.mutex();
+        self.enter();
+        drop(guard);
+        self.sem.wait();
+        mutex.lock()
+    }
+
+    /// Wakes up one waiting thread, if any.
+    pub fn notify_one(&self) {
+        if Cpu::interlocked_fetch_update(&self.waiters, |v| v.checked_sub(1)).is_ok() {
+            self.sem.signal();
+        }
+    }
+
+    /// Wakes up all waiting threads.
+    pub fn notify_all(&self) {
+        let waiters = Cpu::interlocked_swap(&self.waiters, 0);
+        for _ in 0..waiters {
+            self.sem.signal();
+        }
+    }
+
+    #[inline]
+    fn enter(&self) {
+        let _ = Cpu::interlocked_increment(&self.waiters);
+    }
+}
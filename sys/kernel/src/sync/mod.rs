@@ -1,3 +1,6 @@
 pub mod atomicflags;
-pub mod fifo;
+pub mod condvar;
+pub mod mutex;
 pub mod semaphore;
+
+pub use megstd::sync::fifo;
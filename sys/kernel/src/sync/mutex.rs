@@ -0,0 +1,109 @@
+// Mutual Exclusion
+
+use super::semaphore::*;
+use crate::task::scheduler::*;
+use core::cell::UnsafeCell;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::*;
+
+/// A mutual exclusion primitive that sleeps while waiting for the lock.
+pub struct Mutex<T: ?Sized> {
+    sem: Semaphore,
+    owner: AtomicUsize,
+    value: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for Mutex<T> {}
+
+unsafe impl<T: ?Sized + Send> Sync for Mutex<T> {}
+
+impl<T> Mutex<T> {
+    #[inline]
+    pub const fn new(value: T) -> Self {
+        Self {
+            sem: Semaphore::new(1),
+            owner: AtomicUsize::new(0),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<T: ?Sized> Mutex<T> {
+    /// Acquires the lock, sleeping until it is available.
+    #[inline]
+    pub fn lock(&self) -> MutexGuard<T> {
+        if !self.sem.try_to() {
+            let reason = ThreadHandle::new(self.owner.load(Ordering::SeqCst))
+                .map(WaitReason::Lock)
+                .unwrap_or(WaitReason::Semaphore);
+            Scheduler::set_wait_reason(reason);
+            self.sem.wait();
+            Scheduler::set_wait_reason(WaitReason::None);
+        }
+        self.acquired()
+    }
+
+    /// Acquires the lock only if it is available now.
+    #[inline]
+    pub fn try_lock(&self) -> Option<MutexGuard<T>> {
+        if self.sem.try_to() {
+            Some(self.acquired())
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn acquired(&self) -> MutexGuard<T> {
+        let owner = Scheduler::current_thread().map(|v| v.as_usize());
+        self.owner.store(owner.unwrap_or(0), Ordering::SeqCst);
+        MutexGuard { mutex: self }
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+
+#[must_use]
+pub struct MutexGuard<'a, T: ?Sized> {
+    mutex: &'a Mutex<T>,
+}
+
+impl<'a, T: ?Sized> MutexGuard<'a, T> {
+    /// Returns the mutex that this guard locks.
+    #[inline]
+    pub fn mutex(&self) -> &'a Mutex<T> {
+        self.mutex
+    }
+}
+
+impl<T: ?Sized> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.mutex.value.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for MutexGuard<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.mutex.value.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for MutexGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.mutex.owner.store(0, Ordering::SeqCst);
+        self.mutex.sem.signal();
+    }
+}
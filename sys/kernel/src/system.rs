@@ -1,13 +1,19 @@
 // A Computer System
 
 use crate::{
-    arch::cpu::Cpu,
+    arch::cpu::{Cpu, CpuFeatures},
+    arch::serial::SerialPort,
     io::emcon::*,
-    io::{null::Null, tty::Tty},
+    io::{null::Null, tty::*},
+    task::scheduler::{Scheduler, Timer},
     *,
 };
 use alloc::boxed::Box;
+use alloc::vec::Vec;
 use core::fmt;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::*;
+use core::time::Duration;
 use megstd::drawing::*;
 use toeboot::*;
 
@@ -74,27 +80,42 @@ impl fmt::Display for Version {
 
 pub struct System {
     main_screen: Option<OwnedBitmap<'static>>,
+    back_buffer: Option<BoxedBitmap<'static>>,
     em_console: EmConsole,
+    serial: Option<SerialPort>,
     stdout: Option<Box<dyn Tty>>,
 
     platform: Platform,
     cpu_ver: CpuVersion,
+    cpu_features: CpuFeatures,
     initrd_base: usize,
     initrd_size: usize,
+    last_reset_reason: ResetReason,
+    watchdog: bool,
+    reset_record: bool,
 }
 
 static mut SYSTEM: System = System::new();
 
+/// The owner of the main screen, or zero if it is not locked
+static SCREEN_LOCK: AtomicUsize = AtomicUsize::new(0);
+
 impl System {
     const fn new() -> Self {
         Self {
             main_screen: None,
+            back_buffer: None,
             em_console: EmConsole::new(fonts::FontManager::fixed_system_font()),
+            serial: None,
             stdout: None,
             platform: Platform::Unknown,
             cpu_ver: CpuVersion::UNSPECIFIED,
+            cpu_features: CpuFeatures::empty(),
             initrd_base: 0,
             initrd_size: 0,
+            last_reset_reason: ResetReason::PowerOn,
+            watchdog: false,
+            reset_record: false,
         }
     }
 
@@ -103,28 +124,26 @@ impl System {
         let shared = Self::shared();
         shared.platform = info.platform;
         shared.cpu_ver = info.cpu_ver;
+        shared.cpu_features = Cpu::detect_features();
         shared.initrd_base = info.initrd_base as usize;
         shared.initrd_size = info.initrd_size as usize;
         // shared.acpi_rsdptr = info.acpi_rsdptr as usize;
 
-        shared.main_screen = match info.screen_bpp {
-            32 => Some(
-                Bitmap32::from_static(
-                    info.vram_base as usize as *mut TrueColor,
-                    Size::new(info.screen_width as isize, info.screen_height as isize),
-                    info.screen_stride as usize,
-                )
-                .into(),
-            ),
-            _ => Some(
-                Bitmap8::from_static(
-                    info.vram_base as usize as *mut IndexedColor,
-                    Size::new(info.screen_width as isize, info.screen_height as isize),
-                    info.screen_stride as usize,
-                )
-                .into(),
-            ),
-        };
+        // A frame buffer above the addressable range is not available
+        shared.main_screen = info.vram_address().map(|vram_base| match info.screen_bpp {
+            32 => Bitmap32::from_static(
+                vram_base as *mut TrueColor,
+                Size::new(info.screen_width as isize, info.screen_height as isize),
+                info.screen_stride as usize,
+            )
+            .into(),
+            _ => Bitmap8::from_static(
+                vram_base as *mut IndexedColor,
+                Size::new(info.screen_width as isize, info.screen_height as isize),
+                info.screen_stride as usize,
+            )
+            .into(),
+        });
 
         mem::MemoryManager::init_first(&info);
 
@@ -140,12 +159,23 @@ impl System {
 
             fs::FileManager::init(shared.initrd_base, shared.initrd_size);
 
+            Self::apply_cmdline();
+
+            if shared.reset_record {
+                shared.last_reset_reason = ResetReason::decode(arch::Arch::reset_record());
+                Self::set_reset_reason(None);
+            }
+
             rt::RuntimeEnvironment::init();
 
             fonts::FontManager::init();
 
             window::WindowManager::init();
 
+            if shared.watchdog {
+                window::WindowManager::start_watchdog();
+            }
+
             io::hid::HidManager::init();
 
             arch::Arch::late_init();
@@ -155,6 +185,36 @@ impl System {
         }
     }
 
+    /// Applies the kernel command line in the `cmdline` file of the initrd.
+    unsafe fn apply_cmdline() {
+        let shared = Self::shared();
+        let cmdline = match fs::FileManager::open("cmdline")
+            .ok()
+            .and_then(|v| v.as_slice())
+            .and_then(|v| core::str::from_utf8(v).ok())
+        {
+            Some(v) => v,
+            None => return,
+        };
+        for option in cmdline.split_whitespace() {
+            shared.apply_option(option);
+        }
+    }
+
+    fn apply_option(&mut self, option: &str) {
+        match option {
+            "console=serial" => {
+                if self.platform == Platform::PcCompatible {
+                    self.serial = Some(SerialPort::com1());
+                }
+            }
+            "watchdog=on" => self.watchdog = true,
+            "watchdog=off" => self.watchdog = false,
+            "reset_record=on" => self.reset_record = true,
+            _ => (),
+        }
+    }
+
     /// Returns an internal shared instance
     #[inline]
     fn shared() -> &'static mut System {
@@ -197,20 +257,164 @@ impl System {
         shared.cpu_ver
     }
 
+    #[inline]
+    pub fn cpu_features() -> CpuFeatures {
+        let shared = Self::shared();
+        shared.cpu_features
+    }
+
+    /// Returns how the previous boot ended.
+    #[inline]
+    pub fn last_reset_reason() -> ResetReason {
+        let shared = Self::shared();
+        shared.last_reset_reason
+    }
+
+    /// Records the reason of the coming reset for the next boot, or `None` while running.
+    ///
+    /// The record lives in a vendor-specific location such as an unused CMOS byte,
+    /// so nothing is recorded unless `reset_record=on` is set.
+    pub(crate) fn set_reset_reason(reason: Option<ResetReason>) {
+        let shared = Self::shared();
+        if shared.reset_record {
+            arch::Arch::set_reset_record(ResetReason::encode(reason));
+        }
+    }
+
     /// SAFETY: IT DESTROYS EVERYTHING.
     pub unsafe fn reset() -> ! {
+        Self::set_reset_reason(Some(ResetReason::Reset));
         Cpu::reset();
     }
 
     /// SAFETY: IT DESTROYS EVERYTHING.
     pub unsafe fn shutdown() -> ! {
+        Self::set_reset_reason(Some(ResetReason::Shutdown));
         todo!();
     }
 
-    /// Get main screen
-    pub fn main_screen() -> Bitmap<'static> {
+    /// Locks the main screen for drawing, waiting for the other owner to release it.
+    ///
+    /// Panics if the main screen is not available or is already locked by the current thread.
+    #[track_caller]
+    pub fn lock_screen() -> ScreenGuard<'static> {
+        assert!(Self::has_main_screen(), "main screen is not available");
+        loop {
+            if let Some(guard) = Self::try_lock_screen() {
+                return guard;
+            }
+            if SCREEN_LOCK.load(Ordering::Relaxed) == Self::screen_owner() {
+                panic!("main screen is already locked by the current thread");
+            }
+            Timer::try_sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Locks the main screen for drawing if it is available and not locked.
+    pub fn try_lock_screen() -> Option<ScreenGuard<'static>> {
+        let shared = Self::shared();
+        let screen = shared.main_screen.as_mut()?;
+        ScreenGuard::try_new(&SCREEN_LOCK, Self::screen_owner(), || screen.as_bitmap())
+    }
+
+    /// Identifies the current thread as the owner of the main screen.
+    #[inline]
+    fn screen_owner() -> usize {
+        Scheduler::current_thread().map_or(usize::MAX, |v| v.as_usize())
+    }
+
+    /// Returns the off-screen buffer in the same format as the main screen, allocating it if needed.
+    ///
+    /// Drawings to it become visible by `present`.
+    pub fn back_buffer<'a>() -> Bitmap<'a> {
+        let shared = Self::shared();
+        if shared.back_buffer.is_none() {
+            let size = Self::main_screen_size();
+            let bg_color = IndexedColor::BLACK.into();
+            shared.back_buffer = Some(match Self::main_screen_color_mode() {
+                8 => BoxedBitmap8::new(size, bg_color).into(),
+                _ => BoxedBitmap32::new(size, bg_color).into(),
+            });
+        }
+        shared.back_buffer.as_mut().unwrap().as_bitmap()
+    }
+
+    /// Copies the dirty rectangles of the back buffer to the main screen.
+    pub fn present(dirty: &[Rect]) {
+        let shared = Self::shared();
+        let back_buffer = match shared.back_buffer.as_ref() {
+            Some(v) => v.as_const(),
+            None => return,
+        };
+        let mut screen = Self::lock_screen();
+        let bounds = screen.bounds();
+        Self::present_rects(dirty, bounds, |rect| {
+            screen.blt(&back_buffer, rect.origin(), rect)
+        });
+    }
+
+    /// Merges the overlapping dirty rectangles within the bounds, then copies each of them.
+    fn present_rects<F>(dirty: &[Rect], bounds: Rect, mut blt: F)
+    where
+        F: FnMut(Rect),
+    {
+        let mut rects: Vec<Rect> = dirty
+            .iter()
+            .filter_map(|rect| rect.intersection(bounds))
+            .collect();
+
+        let mut index = 0;
+        while index < rects.len() {
+            let current = rects[index];
+            match rects
+                .iter()
+                .skip(index + 1)
+                .position(|rect| rect.intersection(current).is_some())
+            {
+                Some(position) => {
+                    let other = rects.swap_remove(index + 1 + position);
+                    let lhs = Coordinates::from_rect(current).unwrap();
+                    let rhs = Coordinates::from_rect(other).unwrap();
+                    rects[index] = lhs.comprehensive(rhs).into();
+                    // The merged rectangle may overlap the ones already checked
+                    index = 0;
+                }
+                None => index += 1,
+            }
+        }
+
+        for rect in rects {
+            blt(rect);
+        }
+    }
+
+    /// Releases the lock of the main screen regardless of its owner.
+    ///
+    /// SAFETY: The current owner must never draw again, e.g. in a panic.
+    pub unsafe fn force_unlock_screen() {
+        SCREEN_LOCK.store(0, Ordering::Release);
+    }
+
+    /// Returns whether the main screen has been set up
+    #[inline]
+    pub fn has_main_screen() -> bool {
+        let shared = Self::shared();
+        shared.main_screen.is_some()
+    }
+
+    #[inline]
+    pub fn main_screen_size() -> Size {
+        let shared = Self::shared();
+        shared.main_screen.as_ref().unwrap().size()
+    }
+
+    #[inline]
+    pub fn main_screen_color_mode() -> usize {
         let shared = Self::shared();
-        shared.main_screen.as_mut().unwrap().as_bitmap()
+        match shared.main_screen.as_ref().unwrap() {
+            OwnedBitmap::Indexed(_) => 8,
+            OwnedBitmap::Argb32(_) => 32,
+        }
     }
 
     /// Get emergency console
@@ -219,19 +423,31 @@ impl System {
         &mut shared.em_console
     }
 
+    /// Get the serial port if it is selected by `console=serial`
+    pub fn serial<'a>() -> Option<&'a mut SerialPort> {
+        let shared = Self::shared();
+        shared.serial.as_mut()
+    }
+
+    /// Sets the standard output, which is echoed to the serial port if `console=serial` is set.
     pub fn set_stdout(stdout: Box<dyn Tty>) {
         let shared = Self::shared();
-        shared.stdout = Some(stdout);
+        shared.stdout = Some(match shared.serial.as_mut() {
+            Some(serial) => Box::new(TeeTty::new(stdout, serial)),
+            None => stdout,
+        });
     }
 
     /// Get standard output
+    ///
+    /// Falls back to the serial port or the emergency console until `set_stdout` is called.
     pub fn stdout<'a>() -> &'a mut dyn Tty {
         let shared = Self::shared();
-        shared
-            .stdout
-            .as_mut()
-            .map(|v| v.as_mut())
-            .unwrap_or(Null::null())
+        match (shared.stdout.as_mut(), shared.serial.as_mut()) {
+            (Some(v), _) => v.as_mut(),
+            (None, Some(serial)) => serial,
+            (None, None) => &mut shared.em_console,
+        }
     }
 
     /// Get standard input
@@ -246,8 +462,157 @@ impl System {
     // }
 }
 
+/// How the previous boot ended
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ResetReason {
+    /// Nothing was recorded, such as on power-on, without `reset_record=on`,
+    /// or on a platform without persistent storage
+    PowerOn,
+    /// `System::reset` was called
+    Reset,
+    /// `System::shutdown` was called
+    Shutdown,
+    /// The kernel panicked
+    Panic,
+    /// The system stopped while running without recording a reason, such as a hang or a triple fault
+    Unexpected,
+}
+
+impl ResetReason {
+    const MAGIC: u8 = 0xA0;
+    const MAGIC_MASK: u8 = 0xF0;
+    const RUNNING: u8 = 0x0F;
+
+    /// Encodes the reason into the persisted byte, `None` meaning the system is running.
+    pub const fn encode(reason: Option<Self>) -> u8 {
+        Self::MAGIC
+            | match reason {
+                Some(Self::PowerOn) => 0,
+                Some(Self::Reset) => 1,
+                Some(Self::Shutdown) => 2,
+                Some(Self::Panic) => 3,
+                Some(Self::Unexpected) | None => Self::RUNNING,
+            }
+    }
+
+    /// Decodes the persisted byte, which may be garbage if it has never been written.
+    pub const fn decode(value: Option<u8>) -> Self {
+        let value = match value {
+            Some(v) => v,
+            None => return Self::PowerOn,
+        };
+        if value & Self::MAGIC_MASK != Self::MAGIC {
+            return Self::PowerOn;
+        }
+        match value & !Self::MAGIC_MASK {
+            1 => Self::Reset,
+            2 => Self::Shutdown,
+            3 => Self::Panic,
+            Self::RUNNING => Self::Unexpected,
+            _ => Self::PowerOn,
+        }
+    }
+}
+
+/// Time elapsed since 1970-01-01T00:00:00 in the local time of the RTC
 #[derive(Debug, Copy, Clone)]
 pub struct SystemTime {
     pub secs: u64,
     pub nanos: u32,
 }
+
+impl SystemTime {
+    const SECS_PER_DAY: u64 = 86400;
+
+    /// Makes the time from the date in the proleptic Gregorian calendar and the time of day.
+    pub const fn from_ymd_hms(year: u32, month: u8, day: u8, hour: u8, min: u8, sec: u8) -> Self {
+        let year = (year as u64).saturating_sub(if month <= 2 { 1 } else { 0 });
+        let era = year / 400;
+        let yoe = year % 400;
+        let mp = (month as u64 + 9) % 12;
+        let doy = (153 * mp + 2) / 5 + (day as u64).saturating_sub(1);
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        // dates before the epoch are clamped to it
+        let days = (era * 146_097 + doe).saturating_sub(719_468);
+        let secs = days * Self::SECS_PER_DAY + hour as u64 * 3600 + min as u64 * 60 + sec as u64;
+        Self { secs, nanos: 0 }
+    }
+
+    /// Returns the time of day in hours, minutes and seconds.
+    #[inline]
+    pub const fn to_hms(&self) -> (u8, u8, u8) {
+        let tod = self.secs % Self::SECS_PER_DAY;
+        ((tod / 3600) as u8, (tod / 60 % 60) as u8, (tod % 60) as u8)
+    }
+
+    /// Returns the date in the proleptic Gregorian calendar as year, month and day.
+    pub const fn to_ymd(&self) -> (u32, u8, u8) {
+        // days since 0000-03-01, so that the leap day comes at the end of the year
+        let days = self.secs / Self::SECS_PER_DAY + 719_468;
+        let era = days / 146_097;
+        let doe = days % 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+        let year = (yoe + era * 400) as u32 + if month <= 2 { 1 } else { 0 };
+        (year, month, day)
+    }
+
+    /// Writes the time as `YYYY-MM-DDThh:mm:ss`.
+    pub fn format_iso8601<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        let (year, month, day) = self.to_ymd();
+        let (hour, min, sec) = self.to_hms();
+        write!(
+            w,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year, month, day, hour, min, sec
+        )
+    }
+}
+
+impl fmt::Display for SystemTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.format_iso8601(f)
+    }
+}
+
+/// Exclusive access to the main screen, released when dropped.
+pub struct ScreenGuard<'a> {
+    bitmap: Bitmap<'a>,
+    lock: &'a AtomicUsize,
+}
+
+impl<'a> ScreenGuard<'a> {
+    fn try_new<F>(lock: &'a AtomicUsize, owner: usize, f: F) -> Option<Self>
+    where
+        F: FnOnce() -> Bitmap<'a>,
+    {
+        lock.compare_exchange(0, owner, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| Self { bitmap: f(), lock })
+    }
+}
+
+impl<'a> Deref for ScreenGuard<'a> {
+    type Target = Bitmap<'a>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.bitmap
+    }
+}
+
+impl DerefMut for ScreenGuard<'_> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.bitmap
+    }
+}
+
+impl Drop for ScreenGuard<'_> {
+    fn drop(&mut self) {
+        self.lock.store(0, Ordering::Release);
+    }
+}
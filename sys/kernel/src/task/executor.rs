@@ -1,14 +1,71 @@
 // Task Executor
 
+use super::scheduler::*;
 use super::{Task, TaskId};
+use crate::arch::cpu::Cpu;
 use crate::sync::fifo::*;
 use crate::sync::semaphore::*;
+use alloc::boxed::Box;
 use alloc::collections::BTreeMap;
 use alloc::sync::Arc;
 use alloc::task::Wake;
+use core::future::Future;
+use core::sync::atomic::*;
 use core::task::Waker;
 use core::task::{Context, Poll};
 
+/// Runs the future to completion on the current thread, sleeping while it is pending.
+///
+/// This is a minimal executor for simple futures, not a replacement for `Executor`.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let thread = Scheduler::current_thread();
+    block_on_with(future, thread, || match thread {
+        Some(_) => Scheduler::sleep(),
+        None => Cpu::spin_wait(),
+    })
+}
+
+fn block_on_with<F, P>(future: F, thread: Option<ThreadHandle>, mut park: P) -> F::Output
+where
+    F: Future,
+    P: FnMut(),
+{
+    let mut future = Box::pin(future);
+    let thread_waker = Arc::new(ThreadWaker {
+        thread,
+        woken: AtomicBool::new(false),
+    });
+    let waker = Waker::from(thread_waker.clone());
+    let mut context = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(result) = future.as_mut().poll(&mut context) {
+            return result;
+        }
+        while !thread_waker.woken.swap(false, Ordering::SeqCst) {
+            park();
+        }
+    }
+}
+
+/// Wakes up the thread blocked in `block_on`
+struct ThreadWaker {
+    thread: Option<ThreadHandle>,
+    woken: AtomicBool,
+}
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.woken.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread {
+            thread.wake();
+        }
+    }
+}
+
 pub struct Executor {
     tasks: BTreeMap<TaskId, Task>,
     task_queue: Arc<TaskQueue>,
@@ -2,10 +2,11 @@
 pub mod executor;
 pub mod scheduler;
 pub mod waker;
+pub mod watchdog;
 
+pub use executor::block_on;
 pub use waker::AtomicWaker;
 
-use crate::arch::cpu::Cpu;
 use alloc::boxed::Box;
 use core::future::Future;
 use core::pin::Pin;
@@ -36,6 +37,6 @@ struct TaskId(u64);
 impl TaskId {
     fn new() -> Self {
         static NEXT_ID: AtomicU64 = AtomicU64::new(0);
-        TaskId(Cpu::interlocked_increment_u64(&NEXT_ID))
+        TaskId(NEXT_ID.fetch_add(1, Ordering::SeqCst))
     }
 }
@@ -13,10 +13,12 @@ use crate::{
     *,
 };
 use alloc::collections::btree_map::BTreeMap;
+use alloc::collections::btree_set::BTreeSet;
 use alloc::sync::Arc;
 use alloc::vec::*;
 use bitflags::*;
 use core::cell::UnsafeCell;
+use core::convert::TryFrom;
 use core::ffi::c_void;
 use core::fmt::Write;
 use core::num::NonZeroUsize;
@@ -28,6 +30,8 @@ static mut SCHEDULER: Option<Box<Scheduler>> = None;
 
 static SCHEDULER_ENABLED: AtomicBool = AtomicBool::new(false);
 
+static IDLE_WORKS: IdleWorks = IdleWorks::new();
+
 pub struct Scheduler {
     queue_realtime: ThreadQueue,
     queue_higher: ThreadQueue,
@@ -36,16 +40,24 @@ pub struct Scheduler {
     pool: ThreadPool,
 
     usage: AtomicUsize,
+    frozen_by: AtomicUsize,
+    voluntary_switches: AtomicUsize,
+    preemptions: AtomicUsize,
 
     timer_events: Vec<TimerEvent>,
+    dispatches: usize,
 
     idle: ThreadHandle,
     current: ThreadHandle,
     retired: Option<ThreadHandle>,
+    /// Copy of `current` for this processor, readable without disabling interrupts
+    current_cache: AtomicUsize,
 }
 
 impl Scheduler {
     const MAX_STATISTICS: usize = 1000;
+    /// Number of dispatches a thread can wait in a lower queue before its priority is boosted
+    const AGING_THRESHOLD: usize = 50;
 
     /// Start scheduler and sleep forever
     pub(crate) unsafe fn start(f: fn(usize) -> (), args: usize) -> ! {
@@ -72,10 +84,15 @@ impl Scheduler {
             queue_normal,
             queue_lower,
             timer_events: Vec::with_capacity(100),
+            dispatches: 0,
             idle,
             current: idle,
             retired: None,
+            current_cache: AtomicUsize::new(0),
             usage: AtomicUsize::new(0),
+            frozen_by: AtomicUsize::new(0),
+            voluntary_switches: AtomicUsize::new(0),
+            preemptions: AtomicUsize::new(0),
         }));
 
         SpawnOption::with_priority(Priority::Normal).spawn(f, args, "System");
@@ -86,9 +103,14 @@ impl Scheduler {
             "Statistics",
         );
 
-        SCHEDULER_ENABLED.store(true, Ordering::SeqCst);
+        Cpu::without_interrupts(|| {
+            let shared = Self::shared();
+            shared.set_current(shared.current);
+            SCHEDULER_ENABLED.store(true, Ordering::SeqCst);
+        });
 
         loop {
+            IDLE_WORKS.run(|| Self::shared().has_ready_threads());
             Cpu::halt();
         }
     }
@@ -110,6 +132,42 @@ impl Scheduler {
         Self::usage_per_cpu()
     }
 
+    /// Returns the number of processors the scheduler runs threads on.
+    #[inline]
+    pub const fn num_of_cpus() -> usize {
+        1
+    }
+
+    /// Returns the total number of context switches since the scheduler started.
+    #[inline]
+    pub fn switch_count() -> usize {
+        Self::shared().total_switches()
+    }
+
+    /// Returns the number of context switches caused by preemption.
+    #[inline]
+    pub fn preempt_count() -> usize {
+        Self::shared().preemptions.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    fn total_switches(&self) -> usize {
+        self.voluntary_switches.load(Ordering::Relaxed) + self.preemptions.load(Ordering::Relaxed)
+    }
+
+    /// Counts a context switch to `next` and returns whether the switch is actually needed.
+    fn count_switch(&self, next: ThreadHandle, preempted: bool) -> bool {
+        if self.current == next {
+            return false;
+        }
+        if preempted {
+            self.preemptions.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.voluntary_switches.fetch_add(1, Ordering::Relaxed);
+        }
+        true
+    }
+
     /// Measuring Statistics
     fn statistics_thread(_: usize) {
         let shared = Self::shared();
@@ -128,6 +186,18 @@ impl Scheduler {
             for thread in shared.pool.data.values() {
                 let thread = thread.clone();
                 let thread = unsafe { &mut (*thread.get()) };
+                if thread
+                    .attribute
+                    .test_and_clear(ThreadAttributes::OVER_BUDGET)
+                {
+                    println!(
+                        "warning: realtime thread {} exceeded its budget",
+                        thread
+                            .name()
+                            .as_ref()
+                            .map_or("(unnamed)", ThreadName::as_str)
+                    );
+                }
                 let load0 = thread.load0.swap(0, Ordering::SeqCst);
                 let load = usize::min(
                     load0 as usize * expect as usize / actual1000,
@@ -143,6 +213,9 @@ impl Scheduler {
                 .usage
                 .store(usize::min(usage, Self::MAX_STATISTICS), Ordering::SeqCst);
 
+            #[cfg(feature = "deadlock_detection")]
+            let _ = Self::detect_deadlock();
+
             measure = now;
         }
     }
@@ -199,6 +272,13 @@ impl Scheduler {
     /// Get the current thread running on the current processor
     #[inline]
     pub fn current_thread() -> Option<ThreadHandle> {
+        match unsafe { SCHEDULER.as_ref() }.and_then(|v| v.cached_current()) {
+            Some(current) => Some(current),
+            None => Self::current_thread_slow(),
+        }
+    }
+
+    fn current_thread_slow() -> Option<ThreadHandle> {
         unsafe {
             Cpu::without_interrupts(|| {
                 if Self::is_enabled() {
@@ -223,46 +303,86 @@ impl Scheduler {
             .map(|v| f(v))
     }
 
+    /// Records what the current thread is about to wait for.
+    #[inline]
+    pub fn set_wait_reason(reason: WaitReason) {
+        if let Some(thread) = Self::current_thread() {
+            thread.update(|thread| thread.wait_reason = reason);
+        }
+    }
+
+    /// Walks the wait-for graph and logs a cycle of blocked threads, if any.
+    pub fn detect_deadlock() -> Option<Vec<ThreadHandle>> {
+        let cycle = ThreadPool::synchronized(|| Self::shared().pool.wait_cycle())?;
+        print!("deadlock detected:");
+        for handle in cycle.iter().chain(cycle.first()) {
+            match handle.name() {
+                Some(name) => print!(" {}", name),
+                None => print!(" ({})", handle.as_usize()),
+            }
+        }
+        println!("");
+        Some(cycle)
+    }
+
+    /// Get the cancellation token attached to the current thread
+    #[inline]
+    pub fn current_cancel_token() -> Option<CancelToken> {
+        Self::current_thread().and_then(|thread| thread.cancel_token())
+    }
+
+    /// Returns whether the current thread has been asked to stop
+    #[inline]
+    pub fn is_cancelled() -> bool {
+        Self::current_thread()
+            .and_then(|thread| thread.get())
+            .and_then(|thread| thread.cancel_token.as_ref())
+            .map(|token| token.is_cancelled())
+            .unwrap_or(false)
+    }
+
     pub(crate) unsafe fn reschedule() {
         if Self::is_enabled() {
             Cpu::without_interrupts(|| {
                 let shared = Self::shared();
                 Self::process_timer_events();
+                watchdog::Watchdog::poll();
                 let current = shared.current;
                 current.update_statistics();
                 let priority = current.as_ref().priority;
-                if priority == Priority::Realtime {
+                if priority == Priority::Realtime || shared.is_frozen() {
                     return;
                 }
-                if let Some(next) = shared.queue_realtime.dequeue() {
-                    Self::switch_context(next);
+                shared.age_threads();
+                if let Some(next) = shared.queue_realtime.dequeue(&mut shared.pool) {
+                    Self::switch_context(next, true);
                 } else if let Some(next) = if priority < Priority::High {
-                    shared.queue_higher.dequeue()
+                    shared.queue_higher.dequeue(&mut shared.pool)
                 } else {
                     None
                 } {
-                    Self::switch_context(next);
+                    Self::switch_context(next, true);
                 } else if let Some(next) = if priority < Priority::Normal {
-                    shared.queue_normal.dequeue()
+                    shared.queue_normal.dequeue(&mut shared.pool)
                 } else {
                     None
                 } {
-                    Self::switch_context(next);
+                    Self::switch_context(next, true);
                 } else if let Some(next) = if priority < Priority::Low {
-                    shared.queue_lower.dequeue()
+                    shared.queue_lower.dequeue(&mut shared.pool)
                 } else {
                     None
                 } {
-                    Self::switch_context(next);
-                } else if current.update(|current| current.quantum.consume()) {
+                    Self::switch_context(next, true);
+                } else if current.update(|current| current.consume_quantum()) {
                     if let Some(next) = match priority {
                         Priority::Idle => None,
-                        Priority::Low => shared.queue_lower.dequeue(),
-                        Priority::Normal => shared.queue_normal.dequeue(),
-                        Priority::High => shared.queue_higher.dequeue(),
+                        Priority::Low => shared.queue_lower.dequeue(&mut shared.pool),
+                        Priority::Normal => shared.queue_normal.dequeue(&mut shared.pool),
+                        Priority::High => shared.queue_higher.dequeue(&mut shared.pool),
                         Priority::Realtime => None,
                     } {
-                        Self::switch_context(next);
+                        Self::switch_context(next, true);
                     }
                 }
             })
@@ -278,7 +398,7 @@ impl Scheduler {
                     current.update_statistics();
                     current.as_ref().attribute.insert(ThreadAttributes::ASLEEP);
                 }
-                Self::switch_context(Self::next());
+                Self::switch_context(Self::next(false), false);
             })
         }
     }
@@ -288,7 +408,7 @@ impl Scheduler {
             Cpu::without_interrupts(|| {
                 let shared = Self::shared();
                 shared.current.update_statistics();
-                Self::switch_context(Self::next());
+                Self::switch_context(Self::next(true), false);
             })
         }
     }
@@ -318,27 +438,146 @@ impl Scheduler {
         }
     }
 
-    /// Get the next executable thread from the thread queue
-    fn next() -> ThreadHandle {
+    /// Terminates all threads of the process and frees their resources.
+    ///
+    /// If the current thread belongs to the process, it also exits and this function does not return.
+    pub fn exit_process(pid: ProcessId) {
+        if pid == ProcessId(0) {
+            return;
+        }
+        let current = Self::current_thread();
+        let mut personalities = Vec::new();
+        ThreadPool::synchronized(|| {
+            let pool = ThreadPool::shared();
+            for handle in pool.threads_of(pid) {
+                if Some(handle) == current {
+                    continue;
+                }
+                if let Some(thread) = pool.get(&handle) {
+                    thread.sem.signal();
+                }
+                if let Some(personality) = pool.zombify(handle) {
+                    personalities.push(personality);
+                }
+            }
+        });
+        for mut personality in personalities {
+            personality.on_exit();
+        }
+
+        if let Some(current) = current {
+            if current.as_ref().pid == pid {
+                Self::exit();
+            }
+        }
+    }
+
+    /// Registers housekeeping work that the idle thread runs when no other thread is ready.
+    ///
+    /// The work should be short, because a ready thread has to wait until it returns.
+    pub fn add_idle_work(f: fn()) -> Result<(), fn()> {
+        IDLE_WORKS.add(f)
+    }
+
+    /// Returns whether any thread other than the idle thread is waiting to run.
+    fn has_ready_threads(&self) -> bool {
+        !(self.queue_realtime.is_empty()
+            && self.queue_higher.is_empty()
+            && self.queue_normal.is_empty()
+            && self.queue_lower.is_empty())
+    }
+
+    /// Sets the quantum of the priority in ticks, at least 1.
+    ///
+    /// Running threads pick up the new value when they re-arm their quantum.
+    pub fn set_quantum(priority: Priority, ticks: usize) {
+        let ticks = ticks.clamp(1, u8::MAX as usize) as u8;
+        QUANTUM_TICKS[priority as usize].store(ticks, Ordering::Relaxed);
+    }
+
+    /// Stops preempting the current thread until `unfreeze` is called.
+    ///
+    /// If the thread blocks or sleeps while frozen, other threads run until it is ready again.
+    pub fn freeze() {
+        let shared = Self::shared();
+        let current = Self::current_thread().map(|v| v.as_usize()).unwrap_or(0);
+        shared.frozen_by.store(current, Ordering::SeqCst);
+    }
+
+    /// Resumes normal scheduling.
+    pub fn unfreeze() {
         let shared = Self::shared();
-        // if shared.is_frozen.load(Ordering::SeqCst) {
-        //     return None;
-        // }
-        if let Some(next) = shared.queue_realtime.dequeue() {
+        shared.frozen_by.store(0, Ordering::SeqCst);
+    }
+
+    /// Returns whether the current thread is the one that froze the scheduler
+    #[inline]
+    fn is_frozen(&self) -> bool {
+        self.frozen_by.load(Ordering::SeqCst) == self.current.as_usize()
+    }
+
+    /// Get the next executable thread from the thread queue
+    #[inline]
+    fn next(current_is_runnable: bool) -> ThreadHandle {
+        Self::shared().next_thread(current_is_runnable)
+    }
+
+    fn next_thread(&mut self, current_is_runnable: bool) -> ThreadHandle {
+        if current_is_runnable && self.is_frozen() {
+            return self.current;
+        }
+        self.age_threads();
+        if let Some(next) = self.queue_realtime.dequeue(&mut self.pool) {
             next
-        } else if let Some(next) = shared.queue_higher.dequeue() {
+        } else if let Some(next) = self.queue_higher.dequeue(&mut self.pool) {
             next
-        } else if let Some(next) = shared.queue_normal.dequeue() {
+        } else if let Some(next) = self.queue_normal.dequeue(&mut self.pool) {
             next
-        } else if let Some(next) = shared.queue_lower.dequeue() {
+        } else if let Some(next) = self.queue_lower.dequeue(&mut self.pool) {
             next
         } else {
-            shared.idle
+            self.idle
         }
     }
 
+    /// Boosts the threads that have been waiting too long in the lower queues by one level,
+    /// so that they eventually run under continuous load of higher priority threads.
+    fn age_threads(&mut self) {
+        self.dispatches = self.dispatches.wrapping_add(1);
+        let now = self.dispatches;
+        if let Some(handle) = self.queue_normal.dequeue_aged(&mut self.pool, now) {
+            self.boost(handle, Priority::High);
+            self.queue_higher.enqueue(handle).unwrap();
+        }
+        if let Some(handle) = self.queue_lower.dequeue_aged(&mut self.pool, now) {
+            self.boost(handle, Priority::Normal);
+            self.queue_normal.enqueue(handle).unwrap();
+        }
+    }
+
+    fn boost(&mut self, handle: ThreadHandle, priority: Priority) {
+        let now = self.dispatches;
+        self.pool.get_mut(&handle, |thread| {
+            if thread.boosted_from.is_none() {
+                thread.boosted_from = Some(thread.priority);
+            }
+            thread.priority = priority;
+            thread.quantum = Quantum::from(priority);
+            thread.queued_at = now;
+        });
+    }
+
     fn enqueue(&mut self, handle: ThreadHandle) {
-        match handle.as_ref().priority {
+        let now = self.dispatches;
+        let priority = self
+            .pool
+            .get_mut(&handle, |thread| {
+                thread.decay_boost();
+                thread.queued_at = now;
+                thread.priority
+            })
+            .unwrap();
+        match priority {
             Priority::Realtime => self.queue_realtime.enqueue(handle).unwrap(),
             Priority::High => self.queue_higher.enqueue(handle).unwrap(),
             Priority::Normal => self.queue_normal.enqueue(handle).unwrap(),
@@ -409,24 +648,37 @@ impl Scheduler {
         }
     }
 
+    /// Returns the cached current thread, or `None` before the scheduler is enabled.
+    #[inline]
+    fn cached_current(&self) -> Option<ThreadHandle> {
+        ThreadHandle::new(self.current_cache.load(Ordering::Acquire))
+    }
+
+    /// MUST be called without interrupts.
+    #[inline]
+    fn set_current(&mut self, next: ThreadHandle) {
+        self.current = next;
+        self.current_cache.store(next.as_usize(), Ordering::Release);
+    }
+
     /// Returns whether or not the thread scheduler is working.
     fn is_enabled() -> bool {
         unsafe { &SCHEDULER }.is_some() && SCHEDULER_ENABLED.load(Ordering::SeqCst)
     }
 
     #[track_caller]
-    unsafe fn switch_context(next: ThreadHandle) {
+    unsafe fn switch_context(next: ThreadHandle, preempted: bool) {
         Cpu::assert_without_interrupt();
 
         let shared = Self::shared();
-        let current = shared.current;
-        if current == next {
+        if !shared.count_switch(next, preempted) {
             return;
         }
+        let current = shared.current;
 
         //-//-//-//-//
         shared.retired = Some(current);
-        shared.current = next;
+        shared.set_current(next);
 
         {
             let current = current.unsafe_weak().unwrap();
@@ -454,12 +706,15 @@ impl Scheduler {
         name: &str,
         options: SpawnOption,
     ) -> Option<ThreadHandle> {
+        if !options.affinity.is_valid_for(Self::num_of_cpus()) {
+            return None;
+        }
         let pid = if options.raise_pid {
             ProcessId::next()
         } else {
             Self::current_pid().unwrap_or(ProcessId(0))
         };
-        let thread = RawThread::new(
+        let mut thread = RawThread::new(
             pid,
             options.priority,
             name,
@@ -467,6 +722,16 @@ impl Scheduler {
             args,
             options.personality,
         );
+        thread.cancel_token = options.cancel_token;
+        if let Some((budget, period)) = options.realtime_budget {
+            if options.priority == Priority::Realtime {
+                thread.budget = Some(RealtimeBudget::new(
+                    Timer::from_duration(budget),
+                    Timer::from_duration(period),
+                    Timer::measure(),
+                ));
+            }
+        }
         let thread = {
             let handle = thread.handle;
             ThreadPool::shared().add(Box::new(thread));
@@ -538,6 +803,79 @@ impl ThreadPool {
             .map(|thread| unsafe { &(*thread) })
     }
 
+    /// Returns the threads that belong to the process.
+    fn threads_of(&self, pid: ProcessId) -> Vec<ThreadHandle> {
+        self.data
+            .iter()
+            .filter(|(_, thread)| unsafe { &*thread.get() }.pid == pid)
+            .map(|(handle, _)| *handle)
+            .collect()
+    }
+
+    /// Makes the thread a zombie and returns its personality to clean up.
+    ///
+    /// The thread is removed immediately unless it is waiting in a run queue,
+    /// in which case it is removed when dequeued.
+    fn zombify(&mut self, handle: ThreadHandle) -> Option<Box<dyn Personality>> {
+        let thread = unsafe { &mut *self.data.get(&handle)?.get() };
+        thread.attribute.insert(ThreadAttributes::ZOMBIE);
+        let personality = thread.personality.take();
+        if !thread.attribute.contains(ThreadAttributes::QUEUED) {
+            self.data.remove(&handle);
+        }
+        personality
+    }
+
+    /// Returns the threads waiting for each other in a cycle, if any.
+    fn wait_cycle(&self) -> Option<Vec<ThreadHandle>> {
+        let edges = self
+            .data
+            .iter()
+            .filter_map(|(handle, thread)| {
+                let thread = unsafe { &*thread.get() };
+                thread
+                    .wait_reason
+                    .blocker()
+                    .map(|blocker| (*handle, blocker))
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        let mut checked = BTreeSet::new();
+        for start in edges.keys() {
+            let mut path = Vec::new();
+            let mut current = *start;
+            while !checked.contains(&current) {
+                if let Some(index) = path.iter().position(|v| *v == current) {
+                    return Some(path.split_off(index));
+                }
+                path.push(current);
+                match edges.get(&current) {
+                    Some(next) => current = *next,
+                    None => break,
+                }
+            }
+            checked.extend(path);
+        }
+        None
+    }
+
+    /// Removes the thread if it is a zombie.
+    fn remove_if_zombie(&mut self, handle: ThreadHandle) -> bool {
+        let is_zombie = self
+            .data
+            .get(&handle)
+            .map(|thread| {
+                unsafe { &*thread.get() }
+                    .attribute
+                    .contains(ThreadAttributes::ZOMBIE)
+            })
+            .unwrap_or(false);
+        if is_zombie {
+            self.data.remove(&handle);
+        }
+        is_zombie
+    }
+
     #[inline]
     fn get_mut<F, R>(&mut self, key: &ThreadHandle, f: F) -> Option<R>
     where
@@ -549,12 +887,23 @@ impl ThreadPool {
             f(&mut *thread)
         })
     }
+
+    #[inline]
+    fn rename(&mut self, key: &ThreadHandle, name: &str) {
+        self.get_mut(key, |thread| {
+            Self::synchronized(|| thread.set_name(name));
+        });
+    }
 }
 
 pub struct SpawnOption {
     pub priority: Priority,
     pub raise_pid: bool,
     pub personality: Option<Box<dyn Personality>>,
+    /// CPU time a realtime thread may use in each period, and the period
+    pub realtime_budget: Option<(Duration, Duration)>,
+    pub cancel_token: Option<CancelToken>,
+    pub affinity: CpuAffinity,
 }
 
 impl SpawnOption {
@@ -564,6 +913,9 @@ impl SpawnOption {
             priority: Priority::Normal,
             raise_pid: false,
             personality: None,
+            realtime_budget: None,
+            cancel_token: None,
+            affinity: CpuAffinity::Any,
         }
     }
 
@@ -573,6 +925,9 @@ impl SpawnOption {
             priority,
             raise_pid: false,
             personality: None,
+            realtime_budget: None,
+            cancel_token: None,
+            affinity: CpuAffinity::Any,
         }
     }
 
@@ -582,6 +937,28 @@ impl SpawnOption {
         self
     }
 
+    /// Limits a realtime thread to `budget` of CPU time in each `period`.
+    /// Beyond that, the thread runs as `High` until the next period.
+    #[inline]
+    pub fn realtime_budget(mut self, budget: Duration, period: Duration) -> Self {
+        self.realtime_budget = Some((budget, period));
+        self
+    }
+
+    /// Attaches a token that the thread polls to stop cooperatively.
+    #[inline]
+    pub fn cancel_token(mut self, token: CancelToken) -> Self {
+        self.cancel_token = Some(token);
+        self
+    }
+
+    /// Pins the thread to a processor. Spawning fails if the processor does not exist.
+    #[inline]
+    pub fn affinity(mut self, affinity: CpuAffinity) -> Self {
+        self.affinity = affinity;
+        self
+    }
+
     #[inline]
     pub fn spawn_f(self, start: fn(usize), args: usize, name: &str) -> Option<ThreadHandle> {
         Scheduler::spawn_f(start, args, name, self)
@@ -594,14 +971,155 @@ impl SpawnOption {
     }
 }
 
+/// Processors a thread is allowed to run on
+///
+/// There is only one processor for now, so the affinity is only validated when spawning
+/// and is not kept by the thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuAffinity {
+    Any,
+    /// The index of the processor
+    Cpu(usize),
+}
+
+impl CpuAffinity {
+    /// Returns whether the affinity can be satisfied on a system with `num_of_cpus` processors.
+    #[inline]
+    pub const fn is_valid_for(&self, num_of_cpus: usize) -> bool {
+        match *self {
+            Self::Any => true,
+            Self::Cpu(index) => index < num_of_cpus,
+        }
+    }
+}
+
+impl Default for CpuAffinity {
+    #[inline]
+    fn default() -> Self {
+        Self::Any
+    }
+}
+
+/// A flag to ask a thread to stop cooperatively
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    #[inline]
+    pub fn new() -> Self {
+        Self(Arc::new(AtomicBool::new(false)))
+    }
+
+    /// Requests the threads polling this token to stop.
+    #[inline]
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
 static mut TIMER_SOURCE: Option<&'static dyn TimerSource> = None;
 
+static TIMER_CALIBRATION: AtomicU32 = AtomicU32::new(Calibration::IDENTITY.0);
+
 pub trait TimerSource {
     fn measure(&self) -> TimeSpec;
 
     fn from_duration(&self, val: Duration) -> TimeSpec;
 
     fn to_duration(&self, val: TimeSpec) -> Duration;
+
+    /// Returns the monotonic time in nanoseconds, as precise as the source allows.
+    fn monotonic_nanos(&self) -> u64 {
+        self.to_duration(self.measure()).as_nanos() as u64
+    }
+
+    /// Measures `periods` periods of a reference clock running at `reference_hz`.
+    ///
+    /// `wait_edge` must block until the next tick of the reference clock.
+    fn calibrate(
+        &self,
+        reference_hz: u64,
+        periods: u64,
+        wait_edge: &mut dyn FnMut(),
+    ) -> Calibration {
+        let periods = periods.max(1);
+        wait_edge();
+        let start = self.monotonic_nanos();
+        for _ in 0..periods {
+            wait_edge();
+        }
+        let elapsed = self.monotonic_nanos().saturating_sub(start);
+        let resolution = self.to_duration(TimeSpec::EPSILON).as_nanos() as u64;
+        Calibration::new(
+            elapsed,
+            periods * 1_000_000_000 / reference_hz.max(1),
+            resolution,
+        )
+    }
+}
+
+/// Correction factor of a timer source, in real time per nominal time (16.16 fixed point).
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Calibration(u32);
+
+impl Calibration {
+    pub const IDENTITY: Self = Self(1 << Self::SHIFT);
+
+    const SHIFT: u32 = 16;
+
+    /// Creates a correction from the nominal nanoseconds measured over a known interval.
+    ///
+    /// Both ends of the measurement are quantized to `resolution_nanos`, so smaller deviations
+    /// are not corrected. Measurements off by more than a quarter are rejected as unreliable.
+    pub fn new(measured_nanos: u64, expected_nanos: u64, resolution_nanos: u64) -> Self {
+        if measured_nanos == 0
+            || expected_nanos == 0
+            || measured_nanos.abs_diff(expected_nanos) <= resolution_nanos * 2
+        {
+            return Self::IDENTITY;
+        }
+        let scale = ((expected_nanos as u128) << Self::SHIFT) / measured_nanos as u128;
+        let tolerance = (Self::IDENTITY.0 / 4) as u128;
+        let identity = Self::IDENTITY.0 as u128;
+        if scale < identity - tolerance || scale > identity + tolerance {
+            Self::IDENTITY
+        } else {
+            Self(scale as u32)
+        }
+    }
+
+    /// Converts a duration measured by the source into real time.
+    #[inline]
+    pub fn to_real(&self, val: Duration) -> Duration {
+        Self::nanos_to_duration((val.as_nanos() * self.0 as u128) >> Self::SHIFT)
+    }
+
+    /// Converts a real duration into the time the source will measure, rounding up.
+    #[inline]
+    pub fn to_nominal(&self, val: Duration) -> Duration {
+        let scale = self.0 as u128;
+        Self::nanos_to_duration(((val.as_nanos() << Self::SHIFT) + scale - 1) / scale)
+    }
+
+    #[inline]
+    fn nanos_to_duration(nanos: u128) -> Duration {
+        const NANOS_PER_SEC: u128 = 1_000_000_000;
+        let secs = u64::try_from(nanos / NANOS_PER_SEC).unwrap_or(u64::MAX);
+        Duration::new(secs, (nanos % NANOS_PER_SEC) as u32)
+    }
+}
+
+impl Default for Calibration {
+    #[inline]
+    fn default() -> Self {
+        Self::IDENTITY
+    }
 }
 
 #[derive(Debug, Copy, Clone, Default)]
@@ -663,6 +1181,22 @@ impl Timer {
         unsafe { TIMER_SOURCE.unwrap() }
     }
 
+    /// Calibrates the timer source over `periods` ticks of a reference clock at `reference_hz`.
+    pub(crate) fn calibrate(
+        reference_hz: u64,
+        periods: u64,
+        wait_edge: &mut dyn FnMut(),
+    ) -> Calibration {
+        let calibration = Self::timer_source().calibrate(reference_hz, periods, wait_edge);
+        TIMER_CALIBRATION.store(calibration.0, Ordering::SeqCst);
+        calibration
+    }
+
+    #[inline]
+    pub fn calibration() -> Calibration {
+        Calibration(TIMER_CALIBRATION.load(Ordering::SeqCst))
+    }
+
     #[track_caller]
     pub fn sleep(duration: Duration) {
         if Scheduler::is_enabled() {
@@ -675,6 +1209,30 @@ impl Timer {
         }
     }
 
+    /// Sleeps if the scheduler is available. Otherwise, busy-waits and returns `false`.
+    pub fn try_sleep(duration: Duration) -> bool {
+        Self::try_sleep_with(duration, Scheduler::is_enabled(), Self::sleep)
+    }
+
+    fn try_sleep_with<F>(duration: Duration, scheduler_enabled: bool, sleep: F) -> bool
+    where
+        F: FnOnce(Duration),
+    {
+        if scheduler_enabled {
+            sleep(duration);
+            true
+        } else {
+            if unsafe { TIMER_SOURCE.is_some() } {
+                let duration = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+                let deadline = Self::measure_nanos().saturating_add(duration);
+                while Self::measure_nanos() < deadline {
+                    Cpu::spin_wait();
+                }
+            }
+            false
+        }
+    }
+
     #[inline]
     pub fn usleep(us: u64) {
         Self::sleep(Duration::from_micros(us));
@@ -695,14 +1253,22 @@ impl Timer {
         Self::measure().into()
     }
 
+    /// Returns the monotonic time in nanoseconds without truncating to the tick resolution.
+    #[inline]
+    pub fn measure_nanos() -> u64 {
+        let nanos = Self::timer_source().monotonic_nanos();
+        let real = Self::calibration().to_real(Duration::from_nanos(nanos));
+        u64::try_from(real.as_nanos()).unwrap_or(u64::MAX)
+    }
+
     #[inline]
     fn timespec_to_duration(val: TimeSpec) -> Duration {
-        Self::timer_source().to_duration(val)
+        Self::calibration().to_real(Self::timer_source().to_duration(val))
     }
 
     #[inline]
     fn duration_to_timespec(val: Duration) -> TimeSpec {
-        Self::timer_source().from_duration(val)
+        Self::timer_source().from_duration(Self::calibration().to_nominal(val))
     }
 }
 
@@ -745,6 +1311,7 @@ pub struct TimerEvent {
 pub enum TimerType {
     OneShot(ThreadHandle),
     Window(WindowHandle, usize),
+    Callback(fn(usize), usize),
 }
 
 #[allow(dead_code)]
@@ -763,6 +1330,14 @@ impl TimerEvent {
         }
     }
 
+    /// Calls the function with the argument when the timer expires, in interrupt context.
+    pub fn callback(timer: Timer, f: fn(usize), arg: usize) -> Self {
+        Self {
+            timer,
+            timer_type: TimerType::Callback(f, arg),
+        }
+    }
+
     pub fn until(&self) -> bool {
         self.timer.until()
     }
@@ -771,8 +1346,10 @@ impl TimerEvent {
         match self.timer_type {
             TimerType::OneShot(thread) => thread.wake(),
             TimerType::Window(window, timer_id) => {
-                window.post(WindowMessage::Timer(timer_id)).unwrap()
+                // The window may have been closed
+                let _ = window.post(WindowMessage::Timer(timer_id));
             }
+            TimerType::Callback(f, arg) => f(arg),
         }
     }
 }
@@ -784,7 +1361,12 @@ impl ProcessId {
     #[inline]
     fn next() -> Self {
         static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
-        Self(Cpu::interlocked_increment(&NEXT_ID))
+        Self(NEXT_ID.fetch_add(1, Ordering::SeqCst))
+    }
+
+    #[inline]
+    pub const fn as_usize(&self) -> usize {
+        self.0
     }
 }
 
@@ -801,7 +1383,7 @@ impl ThreadHandle {
     #[inline]
     fn next() -> Self {
         static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
-        Self::new(Cpu::interlocked_increment(&NEXT_ID)).unwrap()
+        Self::new(NEXT_ID.fetch_add(1, Ordering::SeqCst)).unwrap()
     }
 
     #[inline]
@@ -839,22 +1421,54 @@ impl ThreadHandle {
     }
 
     #[inline]
-    pub fn name(&self) -> Option<&str> {
+    pub fn name(&self) -> Option<ThreadName> {
         self.get().and_then(|v| v.name())
     }
 
+    /// Renames the thread. Names longer than `THREAD_NAME_LENGTH - 1` bytes are truncated.
+    #[inline]
+    pub fn set_name(&self, name: &str) {
+        ThreadPool::shared().rename(self, name);
+    }
+
+    #[inline]
+    pub fn cancel_token(&self) -> Option<CancelToken> {
+        self.get().and_then(|v| v.cancel_token())
+    }
+
+    /// Asks the thread to stop if it has a cancellation token.
+    #[inline]
+    pub fn cancel(&self) {
+        if let Some(thread) = self.get() {
+            thread.cancel();
+        }
+    }
+
     #[inline]
     pub fn wake(&self) {
-        self.as_ref().attribute.insert(ThreadAttributes::AWAKE);
-        Scheduler::add(*self);
+        if let Some(thread) = self.get() {
+            thread.attribute.insert(ThreadAttributes::AWAKE);
+            Scheduler::add(*self);
+        }
     }
 
     #[inline]
     pub fn join(&self) -> usize {
-        self.get().map(|t| t.sem.wait());
+        if let Some(thread) = self.get() {
+            Scheduler::set_wait_reason(WaitReason::Join(*self));
+            thread.sem.wait();
+            Scheduler::set_wait_reason(WaitReason::None);
+        }
         0
     }
 
+    #[inline]
+    pub fn wait_reason(&self) -> WaitReason {
+        self.get()
+            .map(|v| v.wait_reason)
+            .unwrap_or(WaitReason::None)
+    }
+
     fn update_statistics(&self) {
         self.update(|thread| {
             let now = Timer::measure().0;
@@ -862,10 +1476,34 @@ impl ThreadHandle {
             let diff = now - then;
             thread.cpu_time.fetch_add(diff, Ordering::SeqCst);
             thread.load0.fetch_add(diff as u32, Ordering::SeqCst);
+            thread.charge_budget(diff, TimeSpec(now));
         });
     }
 }
 
+/// What a blocked thread is waiting for
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WaitReason {
+    None,
+    /// Waiting for the thread to exit
+    Join(ThreadHandle),
+    /// Waiting for the lock held by the thread
+    Lock(ThreadHandle),
+    /// Waiting for a semaphore that no thread owns
+    Semaphore,
+}
+
+impl WaitReason {
+    /// Returns the thread that must proceed first to end this wait.
+    #[inline]
+    pub const fn blocker(self) -> Option<ThreadHandle> {
+        match self {
+            WaitReason::Join(thread) | WaitReason::Lock(thread) => Some(thread),
+            WaitReason::None | WaitReason::Semaphore => None,
+        }
+    }
+}
+
 #[repr(u8)]
 #[non_exhaustive]
 #[derive(Debug, Copy, Clone, PartialEq, Ord, PartialOrd, Eq)]
@@ -917,19 +1555,72 @@ impl Quantum {
     }
 }
 
+/// CPU budget of a realtime thread
+struct RealtimeBudget {
+    budget: usize,
+    period: usize,
+    period_start: usize,
+    used: usize,
+}
+
+impl RealtimeBudget {
+    #[inline]
+    const fn new(budget: TimeSpec, period: TimeSpec, now: TimeSpec) -> Self {
+        Self {
+            budget: budget.0,
+            period: period.0,
+            period_start: now.0,
+            used: 0,
+        }
+    }
+
+    /// Adds the CPU time used and returns `true` if the budget for the current period is exceeded.
+    fn charge(&mut self, used: usize, now: TimeSpec) -> bool {
+        if now.0.wrapping_sub(self.period_start) >= self.period {
+            self.period_start = now.0;
+            self.used = 0;
+        }
+        self.used = self.used.saturating_add(used);
+        self.used > self.budget
+    }
+}
+
+/// Quantum of each priority in ticks
+static QUANTUM_TICKS: [AtomicU8; 5] = [
+    AtomicU8::new(1),
+    AtomicU8::new(1),
+    AtomicU8::new(5),
+    AtomicU8::new(10),
+    AtomicU8::new(1),
+];
+
 impl From<Priority> for Quantum {
     fn from(priority: Priority) -> Self {
-        match priority {
-            Priority::High => Quantum::new(10),
-            Priority::Normal => Quantum::new(5),
-            Priority::Low => Quantum::new(1),
-            _ => Quantum::new(1),
-        }
+        Quantum::new(QUANTUM_TICKS[priority as usize].load(Ordering::Relaxed))
     }
 }
 
 const THREAD_NAME_LENGTH: usize = 32;
 
+/// A copy of the name of a thread, which stays intact even if the thread is renamed
+#[derive(Clone, Copy)]
+pub struct ThreadName([u8; THREAD_NAME_LENGTH]);
+
+impl ThreadName {
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        let len = self.0[0] as usize;
+        core::str::from_utf8(&self.0[1..1 + len]).unwrap_or("")
+    }
+}
+
+impl fmt::Display for ThreadName {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 type ThreadStart = fn(usize) -> ();
 
 #[allow(dead_code)]
@@ -948,6 +1639,12 @@ struct RawThread {
     attribute: AtomicBitflags<ThreadAttributes>,
     priority: Priority,
     quantum: Quantum,
+    budget: Option<RealtimeBudget>,
+    cancel_token: Option<CancelToken>,
+    wait_reason: WaitReason,
+    /// The original priority while the thread is temporarily boosted by aging
+    boosted_from: Option<Priority>,
+    queued_at: usize,
 
     // Statistics
     measure: AtomicUsize,
@@ -968,6 +1665,7 @@ bitflags! {
         const ASLEEP    = 0b0000_0000_0000_0010;
         const AWAKE     = 0b0000_0000_0000_0100;
         const ZOMBIE    = 0b0000_0000_0000_1000;
+        const OVER_BUDGET = 0b0000_0000_0001_0000;
     }
 }
 
@@ -1023,6 +1721,11 @@ impl RawThread {
             attribute: AtomicBitflags::empty(),
             priority,
             quantum: Quantum::from(priority),
+            budget: None,
+            cancel_token: None,
+            wait_reason: WaitReason::None,
+            boosted_from: None,
+            queued_at: 0,
             measure: AtomicUsize::new(0),
             cpu_time: AtomicUsize::new(0),
             load0: AtomicU32::new(0),
@@ -1060,29 +1763,84 @@ impl RawThread {
         unreachable!()
     }
 
+    #[inline]
+    fn cancel_token(&self) -> Option<CancelToken> {
+        self.cancel_token.clone()
+    }
+
+    #[inline]
+    fn cancel(&self) {
+        if let Some(token) = self.cancel_token.as_ref() {
+            token.cancel();
+        }
+    }
+
     #[inline]
     fn set_name_array(array: &mut [u8; THREAD_NAME_LENGTH], name: &str) {
-        let mut i = 1;
-        for c in name.bytes() {
-            if i >= THREAD_NAME_LENGTH {
+        let mut len = 0;
+        for c in name.chars() {
+            let next = len + c.len_utf8();
+            if next >= THREAD_NAME_LENGTH {
                 break;
             }
-            array[i] = c;
-            i += 1;
+            c.encode_utf8(&mut array[1 + len..]);
+            len = next;
         }
-        array[0] = i as u8 - 1;
+        array[0] = len as u8;
     }
 
-    // fn set_name(&mut self, name: &str) {
-    //     RawThread::set_name_array(&mut self.name, name);
-    // }
+    /// Demotes a realtime thread that exceeds its budget, and promotes it again in the next period.
+    fn charge_budget(&mut self, used: usize, now: TimeSpec) {
+        let budget = match self.budget.as_mut() {
+            Some(v) => v,
+            None => return,
+        };
+        let over_budget = budget.charge(used, now);
+        let priority = if over_budget {
+            Priority::High
+        } else {
+            Priority::Realtime
+        };
+        if self.priority != priority {
+            if over_budget {
+                self.attribute.insert(ThreadAttributes::OVER_BUDGET);
+            }
+            self.priority = priority;
+            self.quantum = Quantum::from(priority);
+        }
+    }
 
-    fn name<'a>(&self) -> Option<&'a str> {
-        let len = self.name[0] as usize;
-        match len {
-            0 => None,
-            _ => core::str::from_utf8(unsafe { core::slice::from_raw_parts(&self.name[1], len) })
-                .ok(),
+    /// Consumes a tick and returns `true` when the quantum expires,
+    /// re-arming it with the current quantum of the priority.
+    #[inline]
+    fn consume_quantum(&mut self) -> bool {
+        let expired = self.quantum.consume();
+        if expired {
+            self.quantum = Quantum::from(self.priority);
+        }
+        expired
+    }
+
+    /// Restores the priority boosted by aging after the thread has run.
+    #[inline]
+    fn decay_boost(&mut self) {
+        if let Some(priority) = self.boosted_from.take() {
+            self.priority = priority;
+            self.quantum = Quantum::from(priority);
+        }
+    }
+
+    #[inline]
+    fn set_name(&mut self, name: &str) {
+        RawThread::set_name_array(&mut self.name, name);
+    }
+
+    /// Copies the name, so that renaming the thread cannot tear it.
+    fn name(&self) -> Option<ThreadName> {
+        let name = ThreadName(ThreadPool::synchronized(|| self.name));
+        match name.as_str() {
+            "" => None,
+            _ => Some(name),
         }
     }
 }
@@ -1094,11 +1852,90 @@ impl ThreadQueue {
         Self(Fifo::new(capacity))
     }
 
-    fn dequeue(&mut self) -> Option<ThreadHandle> {
-        unsafe { self.0.dequeue().and_then(|v| ThreadHandle::new(v)) }
+    /// Dequeues the next thread, discarding zombies left in the queue.
+    fn dequeue(&mut self, pool: &mut ThreadPool) -> Option<ThreadHandle> {
+        while let Some(handle) = unsafe { self.0.dequeue() }.and_then(|v| ThreadHandle::new(v)) {
+            if !pool.remove_if_zombie(handle) {
+                return Some(handle);
+            }
+        }
+        None
+    }
+
+    /// Dequeues the head thread only if it has been waiting longer than the aging threshold.
+    fn dequeue_aged(&mut self, pool: &mut ThreadPool, now: usize) -> Option<ThreadHandle> {
+        while let Some(handle) = unsafe { self.0.peek() }.and_then(|v| ThreadHandle::new(v)) {
+            if pool.remove_if_zombie(handle) {
+                unsafe { self.0.dequeue() };
+                continue;
+            }
+            let queued_at = pool.get_mut(&handle, |thread| thread.queued_at)?;
+            if now.wrapping_sub(queued_at) > Scheduler::AGING_THRESHOLD {
+                unsafe { self.0.dequeue() };
+                return Some(handle);
+            }
+            break;
+        }
+        None
     }
 
     fn enqueue(&mut self, data: ThreadHandle) -> Result<(), ()> {
         unsafe { self.0.enqueue(data.as_usize()).map_err(|_| ()) }
     }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Housekeeping works run by the idle thread
+struct IdleWorks {
+    works: [AtomicUsize; Self::MAX_WORKS],
+}
+
+impl IdleWorks {
+    const MAX_WORKS: usize = 8;
+
+    const fn new() -> Self {
+        const EMPTY: AtomicUsize = AtomicUsize::new(0);
+        Self {
+            works: [EMPTY; Self::MAX_WORKS],
+        }
+    }
+
+    fn add(&self, f: fn()) -> Result<(), fn()> {
+        for work in self.works.iter() {
+            if work
+                .compare_exchange(0, f as usize, Ordering::SeqCst, Ordering::Relaxed)
+                .is_ok()
+            {
+                return Ok(());
+            }
+        }
+        Err(f)
+    }
+
+    /// Runs each work once in order, stopping as soon as `is_preempted` returns `true`.
+    ///
+    /// Returns the number of works that ran.
+    fn run<F>(&self, is_preempted: F) -> usize
+    where
+        F: Fn() -> bool,
+    {
+        let mut count = 0;
+        for work in self.works.iter() {
+            let f = match work.load(Ordering::SeqCst) {
+                0 => break,
+                v => v,
+            };
+            if is_preempted() {
+                break;
+            }
+            let f: fn() = unsafe { core::mem::transmute(f) };
+            f();
+            count += 1;
+        }
+        count
+    }
 }
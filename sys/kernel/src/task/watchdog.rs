@@ -0,0 +1,106 @@
+// Watchdog Timer
+
+use super::scheduler::*;
+use crate::system::System;
+use core::sync::atomic::*;
+use core::time::Duration;
+
+static mut WATCHDOG: Watchdog = Watchdog::new(Watchdog::default_reset);
+
+/// Resets the system if it is not kicked within the timeout.
+pub struct Watchdog {
+    enabled: AtomicBool,
+    timeout: AtomicUsize,
+    last_kick: AtomicUsize,
+    on_expire: fn(),
+}
+
+impl Watchdog {
+    #[inline]
+    pub const fn new(on_expire: fn()) -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            timeout: AtomicUsize::new(0),
+            last_kick: AtomicUsize::new(0),
+            on_expire,
+        }
+    }
+
+    #[inline]
+    fn shared<'a>() -> &'a Self {
+        unsafe { &WATCHDOG }
+    }
+
+    fn default_reset() {
+        unsafe {
+            System::reset();
+        }
+    }
+
+    /// Starts the system watchdog. The caller must call `kick` periodically.
+    pub fn start(timeout: Duration) {
+        Self::shared().arm(timeout.into(), Timer::measure());
+    }
+
+    /// Stops the system watchdog.
+    #[inline]
+    pub fn stop() {
+        Self::shared().disarm();
+    }
+
+    /// Notifies the system watchdog that the system is healthy.
+    #[inline]
+    pub fn kick() {
+        Self::shared().kick_at(Timer::measure());
+    }
+
+    #[inline]
+    pub fn is_enabled() -> bool {
+        Self::shared().is_armed()
+    }
+
+    /// Checks the system watchdog, called from the timer interrupt.
+    #[inline]
+    pub(crate) fn poll() {
+        let shared = Self::shared();
+        if shared.is_armed() {
+            shared.check(Timer::measure());
+        }
+    }
+
+    pub fn arm(&self, timeout: TimeSpec, now: TimeSpec) {
+        self.timeout.store(timeout.0, Ordering::SeqCst);
+        self.last_kick.store(now.0, Ordering::SeqCst);
+        self.enabled.store(true, Ordering::SeqCst);
+    }
+
+    #[inline]
+    pub fn disarm(&self) {
+        self.enabled.store(false, Ordering::SeqCst);
+    }
+
+    #[inline]
+    pub fn is_armed(&self) -> bool {
+        self.enabled.load(Ordering::SeqCst)
+    }
+
+    #[inline]
+    pub fn kick_at(&self, now: TimeSpec) {
+        self.last_kick.store(now.0, Ordering::SeqCst);
+    }
+
+    /// Calls the reset callback and returns `true` if the watchdog has expired.
+    pub fn check(&self, now: TimeSpec) -> bool {
+        if !self.is_armed() {
+            return false;
+        }
+        let elapsed = now.0.wrapping_sub(self.last_kick.load(Ordering::SeqCst));
+        if elapsed >= self.timeout.load(Ordering::SeqCst) {
+            self.disarm();
+            (self.on_expire)();
+            true
+        } else {
+            false
+        }
+    }
+}
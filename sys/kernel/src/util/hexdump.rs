@@ -0,0 +1,32 @@
+// Hex Dump
+
+use core::fmt::{self, Write};
+
+const BYTES_PER_LINE: usize = 16;
+
+/// Writes the data in the classic `offset  hex bytes  |ascii|` format, 16 bytes per line.
+pub fn hexdump<W: Write>(data: &[u8], out: &mut W) -> fmt::Result {
+    for (index, chunk) in data.chunks(BYTES_PER_LINE).enumerate() {
+        write!(out, "{:08x} ", index * BYTES_PER_LINE)?;
+        for i in 0..BYTES_PER_LINE {
+            if i == BYTES_PER_LINE / 2 {
+                out.write_char(' ')?;
+            }
+            match chunk.get(i) {
+                Some(byte) => write!(out, " {:02x}", byte)?,
+                None => out.write_str("   ")?,
+            }
+        }
+        out.write_str("  |")?;
+        for byte in chunk {
+            let c = if byte.is_ascii_graphic() || *byte == b' ' {
+                *byte as char
+            } else {
+                '.'
+            };
+            out.write_char(c)?;
+        }
+        writeln!(out, "|\r")?;
+    }
+    Ok(())
+}
@@ -1,2 +1,6 @@
+mod hexdump;
+pub mod panic;
 pub mod rng;
 pub mod text;
+
+pub use hexdump::*;
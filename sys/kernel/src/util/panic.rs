@@ -0,0 +1,48 @@
+// Kernel Panic
+
+use crate::{
+    arch::cpu::Cpu,
+    system::{ResetReason, System},
+};
+use core::fmt::{self, Write};
+use core::panic::PanicInfo;
+use core::sync::atomic::*;
+
+static PANICKING: AtomicBool = AtomicBool::new(false);
+
+/// Reports the panic on the screen and the serial port, then stops the system.
+///
+/// It does not allocate, and a nested panic just stops the system.
+pub fn panic_stop(info: &PanicInfo) -> ! {
+    if !PANICKING.swap(true, Ordering::SeqCst) {
+        System::set_reset_reason(Some(ResetReason::Panic));
+        let message: &dyn fmt::Display = match info.message() {
+            Some(v) => v,
+            None => &"explicit panic",
+        };
+        let location = info.location().map(|v| (v.file(), v.line(), v.column()));
+
+        unsafe { System::force_unlock_screen() };
+        if let Some(serial) = System::serial() {
+            let _ = write_panic_report(serial, message, location);
+        }
+        let console = System::em_console();
+        console.enter_panic_mode();
+        let _ = write_panic_report(console, message, location);
+    }
+    unsafe { Cpu::stop() };
+}
+
+/// Formats the panic message and its location.
+pub fn write_panic_report<W: Write>(
+    w: &mut W,
+    message: &dyn fmt::Display,
+    location: Option<(&str, u32, u32)>,
+) -> fmt::Result {
+    writeln!(w, "KERNEL PANIC\r")?;
+    writeln!(w, "{}\r", message)?;
+    if let Some((file, line, column)) = location {
+        writeln!(w, "  at {}:{}:{}\r", file, line, column)?;
+    }
+    Ok(())
+}
@@ -55,7 +55,7 @@ impl AttributedString<'_> {
     }
 
     #[inline]
-    pub fn draw_text(&self, bitmap: &mut Bitmap, rect: Rect, max_lines: usize) {
+    pub fn draw_text(&self, bitmap: &mut Bitmap, rect: Rect, max_lines: usize) -> Rect {
         TextProcessing::draw_text(
             bitmap,
             self.text,
@@ -66,7 +66,7 @@ impl AttributedString<'_> {
             self.line_break_mode,
             self.align,
             self.valign,
-        );
+        )
     }
 }
 
@@ -210,6 +210,10 @@ impl LineStatus {
 }
 
 impl TextProcessing {
+    /// Three periods in place of the horizontal ellipsis (U+2026),
+    /// since the bitmap fonts only have ASCII glyphs
+    pub const ELLIPSIS: &'static str = "...";
+
     pub fn line_statuses(
         font: FontDescriptor,
         s: &str,
@@ -308,10 +312,100 @@ impl TextProcessing {
             LineBreakMode::default(),
             TextAlignment::default(),
             VerticalAlignment::default(),
-        )
+        );
+    }
+
+    /// Write a single line of string to bitmap, skipping glyphs beyond the right edge
+    ///
+    /// Returns the pen position after the whole string, so that its `x` exceeds the width of the bitmap if it did not fit.
+    pub fn write_str_checked(
+        to: &mut Bitmap,
+        s: &str,
+        font: FontDescriptor,
+        origin: Point,
+        color: AmbiguousColor,
+    ) -> Point {
+        let limit = to.width() as isize;
+        let mut cursor = origin;
+        for c in s.chars() {
+            let width = font.width_of(c);
+            if cursor.x + width <= limit {
+                font.draw_char(c, to, cursor, color);
+            }
+            cursor.x += width;
+        }
+        cursor
+    }
+
+    /// Write a single line of string to bitmap from right to left
+    ///
+    /// The first character is placed just left of `origin`, and each following character to the left of the previous one.
+    /// Returns the pen position after the whole string, which may be beyond the left edge of the bitmap.
+    pub fn write_str_rtl(
+        to: &mut Bitmap,
+        s: &str,
+        font: FontDescriptor,
+        origin: Point,
+        color: AmbiguousColor,
+    ) -> Point {
+        let mut cursor = origin;
+        for c in s.chars() {
+            let width = font.width_of(c);
+            cursor.x -= width;
+            if cursor.x + width > 0 {
+                font.draw_char(c, to, cursor, color);
+            }
+        }
+        cursor
+    }
+
+    /// Returns the length in bytes of the prefix of the string that fits in the width along with the ellipsis,
+    /// or `None` if the whole string fits.
+    pub fn ellipsized_len(font: FontDescriptor, s: &str, width: isize) -> Option<usize> {
+        let full_width = s.chars().fold(0, |v, c| v + font.width_of(c));
+        if full_width <= width {
+            return None;
+        }
+        let limit = width - Self::ELLIPSIS.chars().fold(0, |v, c| v + font.width_of(c));
+        let mut current = 0;
+        for (index, c) in s.char_indices() {
+            current += font.width_of(c);
+            if current > limit {
+                return Some(index);
+            }
+        }
+        Some(s.len())
+    }
+
+    /// Write a single line of text to bitmap, replacing the overflowing tail with an ellipsis
+    ///
+    /// Returns the pen position after drawing.
+    pub fn draw_text_ellipsized(
+        to: &mut Bitmap,
+        s: &str,
+        font: FontDescriptor,
+        rect: Rect,
+        color: AmbiguousColor,
+    ) -> Point {
+        let mut cursor = rect.origin();
+        let (head, tail) = match Self::ellipsized_len(font, s, rect.width()) {
+            Some(len) => (&s[..len], Self::ELLIPSIS),
+            None => (s, ""),
+        };
+        for c in head.chars().chain(tail.chars()) {
+            let width = font.width_of(c);
+            if cursor.x + width > rect.x() + rect.width() {
+                break;
+            }
+            font.draw_char(c, to, cursor, color);
+            cursor.x += width;
+        }
+        cursor
     }
 
     /// Write text to bitmap
+    ///
+    /// Returns the bounding rect of all drawn lines, or an empty rect at the origin if nothing was drawn.
     pub fn draw_text(
         to: &mut Bitmap,
         s: &str,
@@ -322,11 +416,12 @@ impl TextProcessing {
         line_break: LineBreakMode,
         align: TextAlignment,
         valign: VerticalAlignment,
-    ) {
+    ) -> Rect {
         let coords = match Coordinates::from_rect(rect) {
             Ok(v) => v,
-            Err(_) => return,
+            Err(_) => return Rect::new(rect.x(), rect.y(), 0, 0),
         };
+        let mut bounds: Option<Coordinates> = None;
 
         let lines = Self::line_statuses(font, s, rect.size(), max_lines, line_break);
         let mut chars = s.chars();
@@ -343,7 +438,7 @@ impl TextProcessing {
             ),
             VerticalAlignment::Bottom => isize::max(coords.top, coords.bottom - perferred_height),
         };
-        for line in lines {
+        'lines: for line in lines {
             for _ in prev_position..line.start_position {
                 let _ = chars.next();
             }
@@ -354,18 +449,29 @@ impl TextProcessing {
                     TextAlignment::Trailing | TextAlignment::Right => coords.right - line.width,
                     TextAlignment::Center => coords.left + (rect.width() - line.width) / 2,
                 };
+                let left = cursor.x;
                 for _ in line.start_position..line.end_position {
                     let c = match chars.next() {
                         Some(c) => c,
-                        None => return,
+                        None => break 'lines,
                     };
                     font.draw_char(c, to, cursor, color);
                     cursor.x += font.width_of(c);
                 }
+                let line_coords =
+                    Coordinates::new(left, cursor.y, cursor.x, cursor.y + line.height);
+                bounds = Some(match bounds {
+                    Some(v) => v + line_coords,
+                    None => line_coords,
+                });
             }
 
             prev_position = line.end_position;
             cursor.y += line.height;
         }
+
+        bounds
+            .map(|v| v.into())
+            .unwrap_or(Rect::new(coords.left, coords.top, 0, 0))
     }
 }
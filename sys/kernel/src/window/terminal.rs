@@ -5,6 +5,7 @@ use alloc::boxed::Box;
 use megstd::drawing::*;
 // use alloc::sync::Arc;
 use core::{
+    cell::Cell,
     fmt::Write,
     future::Future,
     pin::Pin,
@@ -58,32 +59,38 @@ pub struct Terminal {
     font: FontDescriptor,
     cols: usize,
     rows: usize,
-    insets: EdgeInsets,
     x: usize,
     y: usize,
     attribute: u8,
     fg_color: AmbiguousColor,
     bg_color: AmbiguousColor,
     is_cursor_enabled: bool,
+    /// The content size that the text area is currently centered in
+    layout_size: Cell<Size>,
 }
 
 impl Terminal {
     pub fn new(cols: usize, rows: usize) -> Self {
-        let font = if false {
-            FontManager::system_font()
-        } else {
-            FontDescriptor::new(FontFamily::SmallFixed, 0).unwrap()
-        };
         let insets = DEFAULT_INSETS;
         let attribute = DEFAULT_ATTRIBUTE;
         let (fg_color, bg_color) = Self::split_attr(attribute);
 
         let n_instances = TerminalAgent::next_instance();
         let screen_insets = WindowManager::screen_insets();
-        let window_size = Size::new(
-            font.width_of(' ') * cols as isize,
-            font.line_height() * rows as isize,
-        ) + insets;
+        let window_size_for = |font: &FontDescriptor| {
+            Size::new(
+                font.width_of(' ') * cols as isize,
+                font.line_height() * rows as isize,
+            ) + insets
+        };
+        let mut font = FontManager::default_ui_font_for(System::platform());
+        let mut window_size = window_size_for(&font);
+        let screen_size = WindowManager::user_screen_bounds().size();
+        if window_size.width() > screen_size.width() || window_size.height() > screen_size.height()
+        {
+            font = FontDescriptor::new(FontFamily::SmallFixed, 0).unwrap();
+            window_size = window_size_for(&font);
+        }
 
         let window = WindowBuilder::new("Terminal")
             .style_add(WindowStyle::NAKED)
@@ -96,19 +103,20 @@ impl Terminal {
             .bg_color(bg_color)
             .build();
         window.make_active();
+        let layout_size = Cell::new(window.content_size());
 
         Self {
             window,
             font,
             cols,
             rows,
-            insets,
             x: 0,
             y: 0,
             attribute,
             fg_color,
             bg_color,
             is_cursor_enabled: true,
+            layout_size,
         }
     }
 
@@ -119,10 +127,81 @@ impl Terminal {
         )
     }
 
+    #[inline]
+    fn text_size(&self) -> Size {
+        Size::new(
+            self.font.width_of(' ') * self.cols as isize,
+            self.font.line_height() * self.rows as isize,
+        )
+    }
+
+    /// Returns the insets that center the text area in the content area.
+    #[inline]
+    fn insets(&self) -> EdgeInsets {
+        Self::insets_for(self.layout_size.get(), self.text_size())
+    }
+
+    fn insets_for(content_size: Size, text_size: Size) -> EdgeInsets {
+        let left = isize::max(0, (content_size.width - text_size.width) / 2);
+        let top = isize::max(0, (content_size.height - text_size.height) / 2);
+        EdgeInsets::new(
+            top,
+            left,
+            content_size.height - text_size.height - top,
+            content_size.width - text_size.width - left,
+        )
+    }
+
+    /// Moves the text area to the center of the resized content area.
+    fn relayout(&self) {
+        let text_size = self.text_size();
+        let old_insets = self.insets();
+        let content_size = self.window.content_size();
+        self.layout_size.set(content_size);
+        let insets = self.insets();
+        if insets == old_insets {
+            return;
+        }
+
+        let content = Rect::from(content_size);
+        let text = Rect::new(insets.left, insets.top, text_size.width, text_size.height);
+        let margins = [
+            Rect::new(0, 0, content.width(), text.y()),
+            Rect::new(0, text.y(), text.x(), text.height()),
+            Rect::new(
+                text.x() + text.width(),
+                text.y(),
+                content.width() - text.x() - text.width(),
+                text.height(),
+            ),
+            Rect::new(
+                0,
+                text.y() + text.height(),
+                content.width(),
+                content.height() - text.y() - text.height(),
+            ),
+        ];
+        let _ = self.window.draw_in_rect(content, |bitmap| {
+            bitmap.blt_itself(
+                text.origin(),
+                Rect::new(
+                    old_insets.left,
+                    old_insets.top,
+                    text_size.width,
+                    text_size.height,
+                ),
+            );
+            for margin in margins.iter().filter(|v| v.width() > 0 && v.height() > 0) {
+                bitmap.fill_rect(*margin, self.bg_color);
+            }
+        });
+        self.window.set_needs_display();
+    }
+
     fn scroll_up(&mut self) {
         let h = self.font.line_height();
 
-        let frame = Rect::from(self.window.content_size()).insets_by(self.insets);
+        let frame = Rect::from(self.window.content_size()).insets_by(self.insets());
         let rect = Rect::new(0, h, frame.width(), frame.height() - h);
         let rect2 = Rect::new(0, frame.height() - h, frame.width(), h);
         self.window
@@ -164,9 +243,10 @@ impl Terminal {
                     self.y = self.rows - 1;
                 }
 
+                let insets = self.insets();
                 let rect = Rect::new(
-                    self.insets.left + self.x as isize * w,
-                    self.insets.top + self.y as isize * h,
+                    insets.left + self.x as isize * w,
+                    insets.top + self.y as isize * h,
                     w,
                     h,
                 );
@@ -204,9 +284,10 @@ impl Terminal {
     fn set_needs_update_cursor(&mut self) {
         let w = self.font.width_of(' ');
         let h = self.font.line_height();
+        let insets = self.insets();
         let rect = Rect::new(
-            self.insets.left + w * self.x as isize,
-            self.insets.top + h * self.y as isize,
+            insets.left + w * self.x as isize,
+            insets.top + h * self.y as isize,
             w,
             h,
         );
@@ -238,9 +319,7 @@ impl TtyRead for Terminal {
     fn read_async(
         &self,
     ) -> core::pin::Pin<Box<dyn core::future::Future<Output = TtyReadResult> + '_>> {
-        Box::pin(TerminalReader {
-            window: self.window,
-        })
+        Box::pin(TerminalReader { terminal: self })
     }
 }
 
@@ -299,18 +378,23 @@ impl TtyWrite for Terminal {
 
 impl Tty for Terminal {}
 
-struct TerminalReader {
-    window: WindowHandle,
+struct TerminalReader<'a> {
+    terminal: &'a Terminal,
 }
 
-impl Future for TerminalReader {
+impl Future for TerminalReader<'_> {
     type Output = TtyReadResult;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        while let Some(message) = self.window.poll_message(cx) {
+        let terminal = self.terminal;
+        while let Some(message) = terminal.window.poll_message(cx) {
             match message {
                 WindowMessage::Char(c) => return Poll::Ready(Ok(c)),
-                _ => self.window.handle_default_message(message),
+                WindowMessage::Draw => terminal.window.refresh(),
+                WindowMessage::Resized { .. } => terminal.relayout(),
+                _ => {
+                    terminal.window.handle_default_message(message);
+                }
             }
         }
         Poll::Pending
@@ -2,7 +2,8 @@
 
 use crate::{
     arch::cpu::Cpu, fonts::*, io::hid::*, sync::atomicflags::AtomicBitflags, sync::fifo::*,
-    sync::semaphore::*, system::System, task::scheduler::*, task::AtomicWaker, util::text::*, *,
+    sync::semaphore::*, system::System, task::scheduler::*, task::watchdog::Watchdog,
+    task::AtomicWaker, util::text::*, *,
 };
 use alloc::boxed::Box;
 use alloc::collections::btree_map::BTreeMap;
@@ -25,9 +26,13 @@ static mut WM: Option<Box<WindowManager<'_>>> = None;
 
 const MAX_WINDOWS: usize = 255;
 const WINDOW_TITLE_LENGTH: usize = 32;
+const WINDOW_CHAR_QUEUE_SIZE: usize = 32;
 
 const WINDOW_BORDER_PADDING: isize = 1;
 const WINDOW_TITLE_HEIGHT: isize = 20;
+const WINDOW_SHADOW_OFFSET: isize = 4;
+const WINDOW_SHADOW_COLOR: TrueColor = TrueColor::from_argb(0x40000000);
+const WINDOW_ANCHOR_MARGIN: isize = 8;
 
 const WINDOW_DEFAULT_KEY_COLOR: IndexedColor = IndexedColor::DEFAULT_KEY;
 const WINDOW_BORDER_COLOR: AmbiguousColor = AmbiguousColor::from_rgb(0x666666);
@@ -36,6 +41,11 @@ const WINDOW_ACTIVE_TITLE_FG_COLOR: AmbiguousColor = AmbiguousColor::from_rgb(0x
 const WINDOW_INACTIVE_TITLE_BG_COLOR: AmbiguousColor = AmbiguousColor::from_rgb(0xFFFFFF);
 const WINDOW_INACTIVE_TITLE_FG_COLOR: AmbiguousColor = AmbiguousColor::from_rgb(0x999999);
 
+const WATCHDOG_TIMEOUT: Duration = Duration::from_secs(10);
+const WATCHDOG_HEARTBEAT: Duration = Duration::from_secs(1);
+
+const DOUBLE_CLICK_DISTANCE: isize = 4;
+
 const MOUSE_POINTER_WIDTH: usize = 12;
 const MOUSE_POINTER_HEIGHT: usize = 20;
 const MOUSE_POINTER_SOURCE: [u8; MOUSE_POINTER_WIDTH * MOUSE_POINTER_HEIGHT] = [
@@ -57,11 +67,12 @@ const MOUSE_POINTER_SOURCE: [u8; MOUSE_POINTER_WIDTH * MOUSE_POINTER_HEIGHT] = [
 ];
 
 pub struct WindowManager<'a> {
-    main_screen: Bitmap<'static>,
-    screen_insets: EdgeInsets,
+    screen_insets: ScreenInsets,
 
     window_pool: BTreeMap<WindowHandle, Arc<UnsafeCell<Box<RawWindow<'a>>>>>,
     window_orders: Vec<WindowHandle>,
+    /// Closed windows, freed by the window thread when none of its borrows are outstanding
+    retired: Vec<Arc<UnsafeCell<Box<RawWindow<'a>>>>>,
     sem_winthread: Semaphore,
     attributes: AtomicBitflags<WindowManagerAttributes>,
 
@@ -75,9 +86,12 @@ pub struct WindowManager<'a> {
     pointer: WindowHandle,
 
     active: Option<WindowHandle>,
+    /// The window that receives all input while it runs as a modal dialog
+    modal: Option<WindowHandle>,
     captured: Option<WindowHandle>,
     captured_origin: Point,
     entered: Option<WindowHandle>,
+    double_click: DoubleClickDetector,
 }
 
 bitflags! {
@@ -85,6 +99,7 @@ bitflags! {
         const MOUSE_MOVE    = 0b0000_0001;
         const NEEDS_REDRAW  = 0b0000_0010;
         const MOVING        = 0b0000_0100;
+        const HEARTBEAT     = 0b0000_1000;
     }
 }
 
@@ -97,10 +112,13 @@ impl Into<usize> for WindowManagerAttributes {
 impl WindowManager<'static> {
     pub const DEFAULT_BGCOLOR: AmbiguousColor = AmbiguousColor::from_rgb(0xFFFFFF);
 
+    /// Maximum interval between two clicks to be recognized as a double click.
+    pub const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+
     pub(crate) unsafe fn init() {
-        let main_screen = System::main_screen();
-        let pointer_x = AtomicIsize::new(main_screen.width() as isize / 2);
-        let pointer_y = AtomicIsize::new(main_screen.height() as isize / 2);
+        let screen_size = System::main_screen_size();
+        let pointer_x = AtomicIsize::new(screen_size.width() / 2);
+        let pointer_y = AtomicIsize::new(screen_size.height() / 2);
 
         let mut window_pool = BTreeMap::new();
         let mut window_orders = Vec::with_capacity(MAX_WINDOWS);
@@ -109,7 +127,7 @@ impl WindowManager<'static> {
             let window = WindowBuilder::new("Root")
                 .style(WindowStyle::NAKED)
                 .level(WindowLevel::ROOT)
-                .frame(main_screen.bounds())
+                .frame(screen_size.into())
                 .bg_color(IndexedColor::BLACK.into())
                 .without_bitmap()
                 .without_message_queue()
@@ -145,10 +163,10 @@ impl WindowManager<'static> {
         };
 
         WM = Some(Box::new(Self {
-            screen_insets: EdgeInsets::default(),
-            main_screen,
+            screen_insets: ScreenInsets::new(),
             window_pool,
             window_orders,
+            retired: Vec::new(),
             sem_winthread: Semaphore::new(0),
             attributes: AtomicBitflags::EMPTY,
             pointer_x,
@@ -160,6 +178,8 @@ impl WindowManager<'static> {
             captured: None,
             captured_origin: Point::default(),
             entered: None,
+            modal: None,
+            double_click: DoubleClickDetector::new(),
             root,
             pointer,
         }));
@@ -167,9 +187,33 @@ impl WindowManager<'static> {
         SpawnOption::with_priority(Priority::High).spawn(Self::window_thread, 0, "Window Manager");
     }
 
+    /// Starts the system watchdog, which is kicked by the window thread on each heartbeat.
+    pub(crate) fn start_watchdog() {
+        Watchdog::start(WATCHDOG_TIMEOUT);
+        Self::schedule_heartbeat();
+    }
+
     #[inline]
-    pub fn main_screen<'a>(&self) -> &'a mut Bitmap<'static> {
-        &mut WindowManager::shared_mut().main_screen
+    fn schedule_heartbeat() {
+        let timer = Timer::new(WATCHDOG_HEARTBEAT);
+        let _ = Scheduler::schedule_timer(TimerEvent::callback(timer, Self::heartbeat, 0));
+    }
+
+    /// Wakes the window thread to prove that it is still responsive.
+    fn heartbeat(_: usize) {
+        if let Some(shared) = Self::shared_opt() {
+            shared.attributes.insert(WindowManagerAttributes::HEARTBEAT);
+            shared.sem_winthread.signal();
+        }
+    }
+
+    #[inline]
+    /// Creates a bitmap in the same format as the main screen.
+    fn screen_compatible_bitmap(size: Size, bg_color: AmbiguousColor) -> BoxedBitmap<'static> {
+        match System::main_screen_color_mode() {
+            8 => BoxedBitmap8::new(size, bg_color.into()).into(),
+            _ => BoxedBitmap32::new(size, bg_color.into()).into(),
+        }
     }
 
     fn add(window: Box<RawWindow<'static>>) {
@@ -184,9 +228,23 @@ impl WindowManager<'static> {
         }
     }
 
-    #[allow(dead_code)]
-    fn remove(_window: &WindowHandle) {
-        // TODO:
+    fn remove(window: &WindowHandle) {
+        let shared = WindowManager::shared_mut();
+        unsafe {
+            Cpu::without_interrupts(|| shared.retire(window));
+        }
+        shared.sem_winthread.signal();
+    }
+
+    /// Frees the closed windows, called only from the window thread.
+    fn free_retired(&mut self) {
+        let retired = unsafe { Cpu::without_interrupts(|| core::mem::take(&mut self.retired)) };
+        drop(retired);
+    }
+
+    #[inline]
+    fn get_shared(&self, key: &WindowHandle) -> Option<Arc<UnsafeCell<Box<RawWindow<'static>>>>> {
+        unsafe { Cpu::without_interrupts(|| self.window_pool.get(key).map(|v| v.clone())) }
     }
 
     #[inline]
@@ -198,6 +256,30 @@ impl WindowManager<'static> {
         }
     }
 
+    /// Removes the window from the pool and wakes up the threads waiting for its messages.
+    ///
+    /// The window is kept in the retired list until the window thread frees it.
+    fn retire(&mut self, window: &WindowHandle) {
+        if self.entered.contains(window) {
+            self.entered = None;
+        }
+        if self.captured.contains(window) {
+            self.captured = None;
+        }
+        if self.active.contains(window) {
+            self.active = None;
+        }
+        if self.modal.contains(window) {
+            self.modal = None;
+        }
+        if let Some(raw) = self.window_pool.remove(window) {
+            let raw_window = unsafe { &*raw.get() };
+            raw_window.waker.wake();
+            raw_window.sem.signal();
+            self.retired.push(raw);
+        }
+    }
+
     fn get_mut<F, R>(&mut self, key: &WindowHandle, f: F) -> Option<R>
     where
         F: FnOnce(&mut RawWindow) -> R,
@@ -235,6 +317,15 @@ impl WindowManager<'_> {
         loop {
             shared.sem_winthread.wait();
 
+            shared.free_retired();
+
+            if shared
+                .attributes
+                .test_and_clear(WindowManagerAttributes::HEARTBEAT)
+            {
+                Watchdog::kick();
+                Self::schedule_heartbeat();
+            }
             if shared
                 .attributes
                 .test_and_clear(WindowManagerAttributes::NEEDS_REDRAW)
@@ -260,7 +351,7 @@ impl WindowManager<'_> {
                     if current_buttons.contains(MouseButton::LEFT) {
                         if shared.attributes.contains(WindowManagerAttributes::MOVING) {
                             let top = if captured.as_ref().level < WindowLevel::FLOATING {
-                                shared.screen_insets.top
+                                shared.screen_insets.total().top
                             } else {
                                 0
                             };
@@ -304,7 +395,14 @@ impl WindowManager<'_> {
                         }
                     }
                 } else {
-                    let target = Self::window_at_point(position);
+                    let target =
+                        match Self::input_target(shared.modal, Self::window_at_point(position)) {
+                            Some(v) => v,
+                            None => {
+                                shared.pointer.move_to(position);
+                                continue;
+                            }
+                        };
 
                     if buttons_down.contains(MouseButton::LEFT) {
                         if let Some(active) = shared.active {
@@ -384,6 +482,23 @@ impl WindowManager<'_> {
                 Ok(_) => (),
                 Err(err) => errors = Some(err),
             };
+            if down.contains(MouseButton::LEFT)
+                && Self::shared_mut().double_click.press(
+                    target,
+                    position,
+                    Timer::measure(),
+                    Self::DOUBLE_CLICK_INTERVAL.into(),
+                )
+            {
+                match target.post(WindowMessage::DoubleClick(MouseEvent::new(
+                    point,
+                    buttons,
+                    MouseButton::LEFT,
+                ))) {
+                    Ok(_) => (),
+                    Err(err) => errors = Some(err),
+                };
+            }
         }
         if !up.is_empty() {
             match target.post(WindowMessage::MouseUp(MouseEvent::new(point, buttons, up))) {
@@ -412,22 +527,24 @@ impl WindowManager<'_> {
         let shared = WindowManager::shared_mut();
         Self::remove_hierarchy(window.handle);
 
-        let mut insert_position = None;
-        for (index, lhs) in shared.window_orders.iter().enumerate() {
-            if lhs.as_ref().level > window.level {
-                insert_position = Some(index);
-                break;
-            }
-        }
-        if let Some(insert_position) = insert_position {
-            shared.window_orders.insert(insert_position, window.handle);
-        } else {
-            shared.window_orders.push(window.handle);
-        }
+        let insert_position =
+            Self::insert_position(&shared.window_orders, window.level, |v| v.as_ref().level);
+        shared.window_orders.insert(insert_position, window.handle);
 
         window.attributes.insert(WindowAttributes::VISIBLE);
     }
 
+    /// Returns the position above all windows at the same or lower level.
+    fn insert_position<T, F>(orders: &[T], level: WindowLevel, level_of: F) -> usize
+    where
+        F: Fn(&T) -> WindowLevel,
+    {
+        orders
+            .iter()
+            .position(|v| level_of(v) > level)
+            .unwrap_or(orders.len())
+    }
+
     /// SAFETY: MUST lock window_orders
     unsafe fn remove_hierarchy(window: WindowHandle) {
         let window = match window.get() {
@@ -452,30 +569,59 @@ impl WindowManager<'_> {
 
     #[inline]
     pub fn main_screen_bounds() -> Rect {
-        match WindowManager::shared_opt() {
-            Some(shared) => shared.main_screen.bounds(),
-            None => System::main_screen().size().into(),
-        }
+        System::main_screen_size().into()
+    }
+
+    /// Copies the main screen, or a part of it, into a bitmap in the same format.
+    ///
+    /// Returns `None` if the screen is locked or the rect is outside of the screen.
+    pub fn capture(rect: Option<Rect>) -> Option<BoxedBitmap<'static>> {
+        let screen = System::try_lock_screen()?;
+        let rect = rect.unwrap_or_else(|| screen.bounds());
+        BoxedBitmap::from_region(screen.as_ref(), rect)
     }
 
     #[inline]
     pub fn user_screen_bounds() -> Rect {
         match WindowManager::shared_opt() {
-            Some(shared) => shared.main_screen.bounds().insets_by(shared.screen_insets),
-            None => System::main_screen().size().into(),
+            Some(shared) => Self::main_screen_bounds().deflate(shared.screen_insets.total()),
+            None => Self::main_screen_bounds(),
         }
     }
 
     #[inline]
     pub fn screen_insets() -> EdgeInsets {
         let shared = WindowManager::shared();
-        shared.screen_insets
+        shared.screen_insets.total()
     }
 
-    #[inline]
-    pub fn add_screen_insets(insets: EdgeInsets) {
+    /// Reserves an area of the screen, such as a status bar.
+    ///
+    /// The area is released when the returned reservation is dropped.
+    pub fn add_screen_insets(insets: EdgeInsets) -> InsetReservation {
         let shared = WindowManager::shared_mut();
-        shared.screen_insets += insets;
+        let old_bounds = Self::user_screen_bounds();
+        let id = shared.screen_insets.reserve(insets);
+        shared.reposition_windows(old_bounds, Self::user_screen_bounds());
+        InsetReservation { id, insets }
+    }
+
+    fn remove_screen_insets(id: usize) {
+        let shared = WindowManager::shared_mut();
+        let old_bounds = Self::user_screen_bounds();
+        if shared.screen_insets.release(id).is_some() {
+            shared.reposition_windows(old_bounds, Self::user_screen_bounds());
+        }
+    }
+
+    /// Makes the normal windows follow the change of the user screen.
+    fn reposition_windows(&self, old_bounds: Rect, new_bounds: Rect) {
+        let windows = unsafe {
+            Cpu::without_interrupts(|| self.window_pool.values().cloned().collect::<Vec<_>>())
+        };
+        for window in windows {
+            unsafe { &mut *window.get() }.reposition(old_bounds, new_bounds);
+        }
     }
 
     pub(crate) fn post_key_event(event: KeyEvent) {
@@ -490,7 +636,14 @@ impl WindowManager<'_> {
             unsafe {
                 System::reset();
             }
-        } else if let Some(window) = shared.active {
+        } else if let Some(window) = shared.modal.or(shared.active) {
+            if let Some(c) = event
+                .key_data()
+                .map(|v| v.into_char())
+                .filter(|c| *c != '\0')
+            {
+                let _ = window.post_char(c);
+            }
             let _ = window.post(WindowMessage::Key(event));
         }
     }
@@ -564,8 +717,7 @@ impl WindowManager<'_> {
         let shared = Self::shared();
         let _ = shared.root.update_opt(|root| {
             if root.bitmap.is_none() {
-                root.bitmap = Some(UnsafeCell::new(BoxedBitmap::same_format(
-                    &shared.main_screen,
+                root.bitmap = Some(UnsafeCell::new(Self::screen_compatible_bitmap(
                     root.frame.size(),
                     root.bg_color,
                 )));
@@ -576,6 +728,54 @@ impl WindowManager<'_> {
         });
     }
 
+    /// Returns the window to receive the mouse input, or `None` if a modal dialog blocks it.
+    fn input_target(modal: Option<WindowHandle>, target: WindowHandle) -> Option<WindowHandle> {
+        match modal {
+            Some(modal) if modal != target => None,
+            _ => Some(target),
+        }
+    }
+
+    /// Result code of a modal dialog closed without an explicit result
+    pub const MODAL_CANCEL: usize = 0;
+
+    /// Runs the window as a modal dialog on the calling thread, and returns its result code.
+    ///
+    /// Until it ends, all keyboard input goes to the window and mouse input to other windows is ignored.
+    /// `f` handles each message and returns `Some` to end the dialog with the code,
+    /// otherwise the message gets the default handling.
+    /// Closing the window ends the dialog with `MODAL_CANCEL`.
+    pub fn run_modal<F>(window: WindowHandle, mut f: F) -> usize
+    where
+        F: FnMut(WindowMessage) -> Option<usize>,
+    {
+        let shared = WindowManager::shared_mut();
+        let prev_modal = shared.modal.replace(window);
+        shared.captured = None;
+        shared.attributes.remove(WindowManagerAttributes::MOVING);
+        window.make_active();
+
+        let result = loop {
+            let message = match window.wait_message() {
+                Some(v) => v,
+                None => break Self::MODAL_CANCEL,
+            };
+            if let WindowMessage::Close = message {
+                break Self::MODAL_CANCEL;
+            }
+            match f(message) {
+                Some(result) => break result,
+                None => {
+                    window.handle_default_message(message);
+                }
+            }
+        };
+
+        shared.modal = prev_modal;
+        window.close();
+        result
+    }
+
     fn window_at_point(point: Point) -> WindowHandle {
         unsafe {
             Cpu::without_interrupts(|| {
@@ -682,6 +882,7 @@ struct RawWindow<'a> {
     waker: AtomicWaker,
     sem: Semaphore,
     queue: Option<InterlockedFifo<WindowMessage>>,
+    char_queue: Option<InterlockedFifo<char>>,
 }
 
 bitflags! {
@@ -692,12 +893,27 @@ bitflags! {
         const TRANSPARENT        = 0b0000_1000;
         const PINCHABLE     = 0b0001_0000;
         const FLOATING      = 0b0010_0000;
+        const SHADOW        = 0b0100_0000;
+        /// Keeps the window above all other windows, including `FLOATING` ones.
+        /// `FLOATING` is implied, so combining the two is the same as `ALWAYS_ON_TOP` alone.
+        const ALWAYS_ON_TOP = 0b1000_0000;
 
         const DEFAULT = Self::BORDER.bits | Self::TITLE.bits;
     }
 }
 
 impl WindowStyle {
+    /// Returns the window level for this style, or `default` if the style does not specify one.
+    fn level(self, default: WindowLevel) -> WindowLevel {
+        if self.contains(Self::ALWAYS_ON_TOP) {
+            WindowLevel::TOPMOST
+        } else if self.contains(Self::FLOATING) {
+            WindowLevel::FLOATING
+        } else {
+            default
+        }
+    }
+
     fn as_content_insets(self) -> EdgeInsets {
         let mut insets = if self.contains(Self::BORDER) {
             EdgeInsets::padding_each(WINDOW_BORDER_PADDING)
@@ -709,12 +925,28 @@ impl WindowStyle {
         }
         insets
     }
+
+    /// Returns the area of the screen affected by a window with this style, including its shadow.
+    fn visible_frame(self, frame: Rect) -> Rect {
+        if self.contains(Self::SHADOW) {
+            Rect::new(
+                frame.x(),
+                frame.y(),
+                frame.width() + WINDOW_SHADOW_OFFSET,
+                frame.height() + WINDOW_SHADOW_OFFSET,
+            )
+        } else {
+            frame
+        }
+    }
 }
 
 bitflags! {
     struct WindowAttributes: usize {
         const NEEDS_REDRAW  = 0b0000_0001;
         const VISIBLE       = 0b0000_0010;
+        /// Cleared to `bg_color` by the default `Draw` handler
+        const CLEAR_ON_DRAW = 0b0000_0100;
     }
 }
 
@@ -732,6 +964,7 @@ impl WindowLevel {
     pub const DESKTOP_ITEMS: WindowLevel = WindowLevel(1);
     pub const NORMAL: WindowLevel = WindowLevel(32);
     pub const FLOATING: WindowLevel = WindowLevel(64);
+    pub const TOPMOST: WindowLevel = WindowLevel(80);
     pub const POPUP_BARRIER: WindowLevel = WindowLevel(96);
     pub const POPUP: WindowLevel = WindowLevel(97);
     pub const POINTER: WindowLevel = WindowLevel(127);
@@ -743,6 +976,11 @@ impl RawWindow<'_> {
         self.frame.size().into()
     }
 
+    #[inline]
+    fn visible_frame(&self) -> Rect {
+        self.style.visible_frame(self.frame)
+    }
+
     #[inline]
     fn is_visible(&self) -> bool {
         self.attributes.contains(WindowAttributes::VISIBLE)
@@ -760,12 +998,12 @@ impl RawWindow<'_> {
                 WindowManager::add_hierarchy(self.handle);
             })
         }
-        WindowManager::invalidate_screen(self.frame);
+        WindowManager::invalidate_screen(self.visible_frame());
     }
 
     fn hide(&self) {
         let shared = WindowManager::shared_mut();
-        let frame = self.frame;
+        let frame = self.visible_frame();
         let handle = self.handle;
         let new_active = if shared.active.contains(&handle) {
             unsafe {
@@ -797,14 +1035,18 @@ impl RawWindow<'_> {
         let old_frame = self.frame;
         if old_frame != new_frame {
             self.frame = new_frame;
+            if let Some(message) = Self::resized_message(old_frame, new_frame) {
+                self.resize_bitmap(new_frame.size());
+                let _ = self.post(message);
+            }
             if self.attributes.contains(WindowAttributes::VISIBLE) {
                 self.draw_frame();
 
-                let c1 = match Coordinates::from_rect(old_frame) {
+                let c1 = match Coordinates::from_rect(self.style.visible_frame(old_frame)) {
                     Ok(v) => v,
                     Err(_) => return,
                 };
-                let c2 = match Coordinates::from_rect(new_frame) {
+                let c2 = match Coordinates::from_rect(self.visible_frame()) {
                     Ok(v) => v,
                     Err(_) => return,
                 };
@@ -813,6 +1055,56 @@ impl RawWindow<'_> {
         }
     }
 
+    /// Follows the change of the user screen.
+    ///
+    /// A window filling the user screen keeps filling it, and the others are moved out of
+    /// the reserved area at the top.
+    fn reposition(&mut self, old_bounds: Rect, new_bounds: Rect) {
+        if self.level <= WindowLevel::ROOT || self.level >= WindowLevel::FLOATING {
+            return;
+        }
+        let frame = self.frame;
+        if frame == old_bounds {
+            self.set_frame(new_bounds);
+        } else if frame.y() < new_bounds.y() {
+            self.set_frame(Rect::new(
+                frame.x(),
+                new_bounds.y(),
+                frame.width(),
+                frame.height(),
+            ));
+        }
+    }
+
+    /// Returns the message to notify the window of the new size, if it was resized.
+    fn resized_message(old_frame: Rect, new_frame: Rect) -> Option<WindowMessage> {
+        if old_frame.size() != new_frame.size() {
+            Some(WindowMessage::Resized {
+                new_size: new_frame.size(),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Reallocates the bitmap for the new size, keeping the content area that still fits.
+    fn resize_bitmap(&mut self, size: Size) {
+        let old = match self.bitmap.take() {
+            Some(v) => v,
+            None => return,
+        };
+        let src = unsafe { &*old.get() }.as_const();
+        let bitmap: BoxedBitmap = match src {
+            ConstBitmap::Indexed(_) => BoxedBitmap8::new(size, self.bg_color.into()).into(),
+            ConstBitmap::Argb32(_) => BoxedBitmap32::new(size, self.bg_color.into()).into(),
+        };
+        self.bitmap = Some(UnsafeCell::new(bitmap));
+        if let Some(mut bitmap) = self.bitmap() {
+            let content = src.bounds().insets_by(self.content_insets);
+            bitmap.blt(&src, content.origin, content);
+        }
+    }
+
     fn set_bg_color(&mut self, color: AmbiguousColor) {
         self.bg_color = color;
         if let Some(mut bitmap) = self.bitmap() {
@@ -878,8 +1170,8 @@ impl RawWindow<'_> {
     fn draw_to_screen(&self, rect: Rect) {
         let mut frame = rect;
         frame.origin += self.frame.origin;
-        let main_screen = WindowManager::shared().main_screen();
-        self.draw_into(main_screen, frame);
+        let mut main_screen = System::lock_screen();
+        self.draw_into(&mut main_screen, frame);
         // main_screen.draw_rect(frame, AmbiguousColor::Indexed(IndexedColor::RED));
     }
 
@@ -902,54 +1194,83 @@ impl RawWindow<'_> {
         };
 
         for handle in &shared.window_orders[first_index..] {
-            handle.update(|window| {
-                let coords2 = match Coordinates::from_rect(window.frame) {
-                    Ok(v) => v,
-                    Err(_) => return,
+            handle.update(|window| window.compose_into(target_bitmap, frame, coords1));
+        }
+
+        true
+    }
+
+    /// Composites the window over the area of `frame` in the target bitmap.
+    fn compose_into(&self, target_bitmap: &mut Bitmap, frame: Rect, coords1: Coordinates) {
+        if self.style.contains(WindowStyle::SHADOW)
+            && !self.style.contains(WindowStyle::TRANSPARENT)
+        {
+            let mut shadow = self.frame;
+            shadow.origin += Point::new(WINDOW_SHADOW_OFFSET, WINDOW_SHADOW_OFFSET);
+            if let Some(rect) = shadow.intersection(frame) {
+                Self::draw_shadow(target_bitmap, rect);
+            }
+        }
+        let coords2 = match Coordinates::from_rect(self.frame) {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+        if frame.is_within_rect(self.frame) {
+            let blt_origin = Point::new(
+                cmp::max(coords1.left, coords2.left),
+                cmp::max(coords1.top, coords2.top),
+            );
+            let x = if coords1.left > coords2.left {
+                coords1.left - coords2.left
+            } else {
+                0
+            };
+            let y = if coords1.top > coords2.top {
+                coords1.top - coords2.top
+            } else {
+                0
+            };
+            let blt_rect = Rect::new(
+                x,
+                y,
+                cmp::min(coords1.right, coords2.right) - cmp::max(coords1.left, coords2.left),
+                cmp::min(coords1.bottom, coords2.bottom) - cmp::max(coords1.top, coords2.top),
+            );
+
+            if let Some(bitmap) = self.bitmap_const() {
+                if self.style.contains(WindowStyle::TRANSPARENT) {
+                    target_bitmap.blt_transparent(&bitmap, blt_origin, blt_rect, self.key_color);
+                } else {
+                    target_bitmap.blt(bitmap.as_ref(), blt_origin, blt_rect);
+                }
+            } else if !self.style.contains(WindowStyle::TRANSPARENT) {
+                target_bitmap.fill_rect(blt_rect, self.bg_color.into());
+            }
+        }
+    }
+
+    /// Darkens the area under a window, stippled on indexed bitmaps.
+    fn draw_shadow(target_bitmap: &mut Bitmap, rect: Rect) {
+        match target_bitmap {
+            Bitmap::Indexed(bitmap) => {
+                let coords = match rect
+                    .intersection(bitmap.bounds())
+                    .and_then(|v| Coordinates::from_rect(v).ok())
+                {
+                    Some(v) => v,
+                    None => return,
                 };
-                if frame.is_within_rect(window.frame) {
-                    let blt_origin = Point::new(
-                        cmp::max(coords1.left, coords2.left),
-                        cmp::max(coords1.top, coords2.top),
-                    );
-                    let x = if coords1.left > coords2.left {
-                        coords1.left - coords2.left
-                    } else {
-                        0
-                    };
-                    let y = if coords1.top > coords2.top {
-                        coords1.top - coords2.top
-                    } else {
-                        0
-                    };
-                    let blt_rect = Rect::new(
-                        x,
-                        y,
-                        cmp::min(coords1.right, coords2.right)
-                            - cmp::max(coords1.left, coords2.left),
-                        cmp::min(coords1.bottom, coords2.bottom)
-                            - cmp::max(coords1.top, coords2.top),
-                    );
-
-                    if let Some(bitmap) = window.bitmap_const() {
-                        if window.style.contains(WindowStyle::TRANSPARENT) {
-                            target_bitmap.blt_transparent(
-                                &bitmap,
-                                blt_origin,
-                                blt_rect,
-                                window.key_color,
-                            );
-                        } else {
-                            target_bitmap.blt(bitmap.as_ref(), blt_origin, blt_rect);
+                for y in coords.top..coords.bottom {
+                    let left = coords.left + ((coords.left + y) & 1);
+                    for x in (left..coords.right).step_by(2) {
+                        unsafe {
+                            bitmap.set_pixel_unchecked(Point::new(x, y), IndexedColor::BLACK);
                         }
-                    } else {
-                        target_bitmap.fill_rect(blt_rect, window.bg_color.into());
                     }
                 }
-            })
+            }
+            Bitmap::Argb32(bitmap) => bitmap.blend_rect(rect, WINDOW_SHADOW_COLOR),
         }
-
-        true
     }
 
     #[inline]
@@ -966,6 +1287,68 @@ impl RawWindow<'_> {
         }
     }
 
+    /// Marks the window as needing a redraw and returns `false` if a redraw was already pending,
+    /// so that multiple requests within one frame are coalesced into a single `Draw`.
+    #[inline]
+    fn request_redraw(attributes: &AtomicBitflags<WindowAttributes>) -> bool {
+        !attributes.test_and_set(WindowAttributes::NEEDS_REDRAW)
+    }
+
+    /// Dequeues the next message, delivering a pending redraw once the queue is empty.
+    fn next_message(
+        queue: &InterlockedFifo<WindowMessage>,
+        attributes: &AtomicBitflags<WindowAttributes>,
+    ) -> Option<WindowMessage> {
+        queue.dequeue().or_else(|| {
+            if attributes.test_and_clear(WindowAttributes::NEEDS_REDRAW) {
+                Some(WindowMessage::Draw)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Clears the content area to `bg_color` if the window was built with `clear_on_draw`.
+    fn clear_if_needed(&self) {
+        if self.attributes.contains(WindowAttributes::CLEAR_ON_DRAW) {
+            let bounds = Rect::from(self.frame.size()).insets_by(self.content_insets);
+            let bg_color = self.bg_color;
+            let _ = self.draw_in_rect(bounds.size().into(), |bitmap| {
+                bitmap.fill_rect(bitmap.bounds(), bg_color)
+            });
+        }
+    }
+
+    /// Enqueues a message and wakes up the thread waiting for it.
+    fn post(&self, message: WindowMessage) -> Result<(), WindowPostError> {
+        let queue = self.queue.as_ref().ok_or(WindowPostError::NotFound)?;
+        match message {
+            WindowMessage::Draw => {
+                if !Self::request_redraw(&self.attributes) {
+                    return Ok(());
+                }
+            }
+            _ => queue.enqueue(message).map_err(|_| WindowPostError::Full)?,
+        }
+        self.waker.wake();
+        self.sem.signal();
+        Ok(())
+    }
+
+    /// Enqueues a character if the window was built with a character queue.
+    fn post_char(&self, c: char) -> Result<(), WindowPostError> {
+        let char_queue = self.char_queue.as_ref().ok_or(WindowPostError::NotFound)?;
+        char_queue.enqueue(c).map_err(|_| WindowPostError::Full)?;
+        self.waker.wake();
+        self.sem.signal();
+        Ok(())
+    }
+
+    #[inline]
+    fn read_char(&self) -> Option<char> {
+        self.char_queue.as_ref().and_then(|v| v.dequeue())
+    }
+
     #[inline]
     fn invalidate_rect(&mut self, rect: Rect) {
         if self.attributes.contains(WindowAttributes::VISIBLE) {
@@ -1056,28 +1439,73 @@ impl<'a> RawWindow<'a> {
     }
 }
 
+/// Preset positions of a window in the usable area of the screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// Returns the origin of a window of the given size anchored in `bounds`.
+    pub fn origin(self, bounds: Rect, size: Size) -> Point {
+        let margin = WINDOW_ANCHOR_MARGIN;
+        let left = bounds.x() + margin;
+        let center = bounds.x() + (bounds.width() - size.width()) / 2;
+        let right = bounds.x() + bounds.width() - size.width() - margin;
+        let top = bounds.y() + margin;
+        let middle = bounds.y() + (bounds.height() - size.height()) / 2;
+        let bottom = bounds.y() + bounds.height() - size.height() - margin;
+        let (x, y) = match self {
+            Anchor::TopLeft => (left, top),
+            Anchor::Top => (center, top),
+            Anchor::TopRight => (right, top),
+            Anchor::Left => (left, middle),
+            Anchor::Center => (center, middle),
+            Anchor::Right => (right, middle),
+            Anchor::BottomLeft => (left, bottom),
+            Anchor::Bottom => (center, bottom),
+            Anchor::BottomRight => (right, bottom),
+        };
+        Point::new(x, isize::max(bounds.y(), y))
+    }
+}
+
 pub struct WindowBuilder {
     frame: Rect,
+    anchor: Option<Anchor>,
     style: WindowStyle,
     level: WindowLevel,
     bg_color: AmbiguousColor,
     key_color: IndexedColor,
     title: [u8; WINDOW_TITLE_LENGTH],
     queue_size: usize,
+    char_queue_size: usize,
     bitmap_strategy: BitmapStrategy,
+    clear_on_draw: bool,
 }
 
 impl WindowBuilder {
     pub fn new(title: &str) -> Self {
         let window = Self {
             frame: Rect::new(isize::MIN, isize::MIN, 300, 300),
+            anchor: None,
             level: WindowLevel::NORMAL,
             style: WindowStyle::DEFAULT,
             bg_color: WindowManager::DEFAULT_BGCOLOR,
             key_color: WINDOW_DEFAULT_KEY_COLOR,
             title: [0; WINDOW_TITLE_LENGTH],
             queue_size: 32,
+            char_queue_size: 0,
             bitmap_strategy: BitmapStrategy::default(),
+            clear_on_draw: false,
         };
         window.title(title).style(WindowStyle::DEFAULT)
     }
@@ -1090,13 +1518,15 @@ impl WindowBuilder {
         handle
     }
 
-    fn build_inner<'a>(mut self) -> Box<RawWindow<'a>> {
-        let screen_bounds = WindowManager::user_screen_bounds();
-        let window_insets = self.style.as_content_insets();
-        let content_insets = window_insets;
+    /// Resolves the special coordinates and the anchor of the frame against `screen_bounds`.
+    fn resolve_frame(&self, screen_bounds: Rect) -> Rect {
         let mut frame = self.frame;
         if self.style.contains(WindowStyle::NAKED) {
-            frame.size += window_insets;
+            frame.size += self.style.as_content_insets();
+        }
+        if let Some(anchor) = self.anchor {
+            frame.origin = anchor.origin(screen_bounds, frame.size());
+            return frame;
         }
         if frame.x() == isize::MIN {
             frame.origin.x = (screen_bounds.width() - frame.width()) / 2;
@@ -1111,21 +1541,49 @@ impl WindowBuilder {
         } else if frame.y() < 0 {
             frame.origin.y += screen_bounds.y() + screen_bounds.height();
         }
+        frame
+    }
 
-        if self.style.contains(WindowStyle::FLOATING) {
-            self.level = WindowLevel::FLOATING;
-        }
+    #[inline]
+    fn build_inner<'a>(self) -> Box<RawWindow<'a>> {
+        self.build_in(WindowManager::user_screen_bounds())
+    }
+
+    fn build_in<'a>(mut self, screen_bounds: Rect) -> Box<RawWindow<'a>> {
+        let content_insets = self.style.as_content_insets();
+        let frame = self.resolve_frame(screen_bounds);
 
-        let attributes = if self.level == WindowLevel::ROOT {
-            AtomicBitflags::new(WindowAttributes::VISIBLE)
+        self.level = self.style.level(self.level);
+
+        let mut attributes = if self.level == WindowLevel::ROOT {
+            WindowAttributes::VISIBLE
         } else {
-            AtomicBitflags::empty()
+            WindowAttributes::empty()
         };
+        if self.clear_on_draw {
+            attributes |= WindowAttributes::CLEAR_ON_DRAW;
+        }
+        let attributes = AtomicBitflags::new(attributes);
 
         let queue = match self.queue_size {
             0 => None,
             _ => Some(InterlockedFifo::new(self.queue_size)),
         };
+        let char_queue = match self.char_queue_size {
+            0 => None,
+            _ => Some(InterlockedFifo::new(self.char_queue_size)),
+        };
+
+        if self.style.contains(WindowStyle::TRANSPARENT)
+            && self.bg_color == AmbiguousColor::TRANSPARENT
+        {
+            let is_indexed = match self.bitmap_strategy {
+                BitmapStrategy::Native => System::main_screen_color_mode() == 8,
+                BitmapStrategy::Compact => true,
+                BitmapStrategy::NonBitmap | BitmapStrategy::Expressive => false,
+            };
+            self.bg_color = Self::transparent_bg_color(is_indexed, self.key_color);
+        }
 
         let handle = WindowHandle::next();
         let mut window = Box::new(RawWindow {
@@ -1140,6 +1598,7 @@ impl WindowBuilder {
             title: self.title,
             attributes,
             queue,
+            char_queue,
             sem: Semaphore::new(0),
             waker: AtomicWaker::new(),
         });
@@ -1147,8 +1606,7 @@ impl WindowBuilder {
         match self.bitmap_strategy {
             BitmapStrategy::NonBitmap => (),
             BitmapStrategy::Native => {
-                window.bitmap = Some(UnsafeCell::new(BoxedBitmap::same_format(
-                    WindowManager::shared().main_screen(),
+                window.bitmap = Some(UnsafeCell::new(WindowManager::screen_compatible_bitmap(
                     frame.size(),
                     self.bg_color,
                 )));
@@ -1180,6 +1638,12 @@ impl WindowBuilder {
         self
     }
 
+    #[inline]
+    pub fn style_remove(mut self, style: WindowStyle) -> Self {
+        self.style -= style;
+        self
+    }
+
     #[inline]
     pub fn title(mut self, title: &str) -> Self {
         RawWindow::set_title_array(&mut self.title, title);
@@ -1201,6 +1665,20 @@ impl WindowBuilder {
     #[inline]
     pub const fn position(mut self, position: Point) -> Self {
         self.frame.origin = position;
+        self.anchor = None;
+        self
+    }
+
+    /// Places the window in the center of the usable area of the screen.
+    #[inline]
+    pub const fn centered(self) -> Self {
+        self.anchored(Anchor::Center)
+    }
+
+    /// Places the window at a preset position in the usable area of the screen.
+    #[inline]
+    pub const fn anchored(mut self, anchor: Anchor) -> Self {
+        self.anchor = Some(anchor);
         self
     }
 
@@ -1222,6 +1700,28 @@ impl WindowBuilder {
         self
     }
 
+    /// Makes the window transparent.
+    ///
+    /// The background of the window is not filled,
+    /// so the windows behind it show through the pixels left transparent or in the key color.
+    #[inline]
+    pub fn transparent(mut self) -> Self {
+        self.style |= WindowStyle::TRANSPARENT;
+        self.bg_color = AmbiguousColor::TRANSPARENT;
+        self
+    }
+
+    /// The background color of a transparent window,
+    /// the key color for indexed bitmaps or the transparent color for ARGB bitmaps.
+    #[inline]
+    const fn transparent_bg_color(is_indexed: bool, key_color: IndexedColor) -> AmbiguousColor {
+        if is_indexed {
+            AmbiguousColor::Indexed(key_color)
+        } else {
+            AmbiguousColor::TRANSPARENT
+        }
+    }
+
     #[inline]
     pub const fn message_queue_size(mut self, queue_size: usize) -> Self {
         self.queue_size = queue_size;
@@ -1234,6 +1734,20 @@ impl WindowBuilder {
         self
     }
 
+    /// Makes the default `Draw` handler clear the window to its `bg_color`.
+    #[inline]
+    pub const fn clear_on_draw(mut self) -> Self {
+        self.clear_on_draw = true;
+        self
+    }
+
+    /// Allocates the character queue read by `WindowHandle::read_char` and `wait_char`.
+    #[inline]
+    pub const fn with_char_queue(mut self) -> Self {
+        self.char_queue_size = WINDOW_CHAR_QUEUE_SIZE;
+        self
+    }
+
     #[inline]
     pub const fn without_bitmap(mut self) -> Self {
         self.bitmap_strategy = BitmapStrategy::NonBitmap;
@@ -1261,6 +1775,100 @@ impl Default for BitmapStrategy {
     }
 }
 
+/// Reserved areas of the screen.
+struct ScreenInsets {
+    reserved: BTreeMap<usize, EdgeInsets>,
+    next_id: usize,
+}
+
+impl ScreenInsets {
+    #[inline]
+    fn new() -> Self {
+        Self {
+            reserved: BTreeMap::new(),
+            next_id: 1,
+        }
+    }
+
+    #[inline]
+    fn reserve(&mut self, insets: EdgeInsets) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.reserved.insert(id, insets);
+        id
+    }
+
+    #[inline]
+    fn release(&mut self, id: usize) -> Option<EdgeInsets> {
+        self.reserved.remove(&id)
+    }
+
+    fn total(&self) -> EdgeInsets {
+        self.reserved
+            .values()
+            .fold(EdgeInsets::default(), |acc, &insets| acc + insets)
+    }
+}
+
+/// A handle to a reserved area of the screen, released when dropped.
+#[must_use]
+pub struct InsetReservation {
+    id: usize,
+    insets: EdgeInsets,
+}
+
+impl InsetReservation {
+    #[inline]
+    pub const fn insets(&self) -> EdgeInsets {
+        self.insets
+    }
+
+    /// Releases the reserved area.
+    #[inline]
+    pub fn remove(self) {
+        drop(self);
+    }
+}
+
+impl Drop for InsetReservation {
+    fn drop(&mut self) {
+        WindowManager::remove_screen_insets(self.id);
+    }
+}
+
+/// Synthesizes double clicks from successive presses of the mouse button.
+struct DoubleClickDetector {
+    last: Option<(WindowHandle, Point, TimeSpec)>,
+}
+
+impl DoubleClickDetector {
+    #[inline]
+    const fn new() -> Self {
+        Self { last: None }
+    }
+
+    /// Returns `true` if this press completes a double click.
+    fn press(
+        &mut self,
+        target: WindowHandle,
+        position: Point,
+        now: TimeSpec,
+        interval: TimeSpec,
+    ) -> bool {
+        if let Some((last_target, last_position, last_time)) = self.last.take() {
+            if last_target == target
+                && now.0.wrapping_sub(last_time.0) <= interval.0
+                && (position.x - last_position.x).abs() <= DOUBLE_CLICK_DISTANCE
+                && (position.y - last_position.y).abs() <= DOUBLE_CLICK_DISTANCE
+            {
+                return true;
+            }
+        }
+        self.last = Some((target, position, now));
+        false
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct WindowHandle(NonZeroUsize);
 
@@ -1279,7 +1887,7 @@ impl WindowHandle {
     #[inline]
     fn next() -> Self {
         static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
-        Self::new(Cpu::interlocked_increment(&NEXT_ID)).unwrap()
+        Self::new(NEXT_ID.fetch_add(1, Ordering::SeqCst)).unwrap()
     }
 
     #[inline]
@@ -1403,10 +2011,25 @@ impl WindowHandle {
         self.update(|window| window.hide());
     }
 
+    /// Asks the window to close by posting `WindowMessage::Close`.
     #[inline]
+    pub fn request_close(&self) -> Result<(), WindowPostError> {
+        self.post(WindowMessage::Close)
+    }
+
+    /// Destroys the window and frees its resources. The handle becomes invalid.
     pub fn close(&self) {
-        // TODO: remove window
+        if self.get().is_none() {
+            return;
+        }
         self.hide();
+        WindowManager::remove(self);
+    }
+
+    /// Returns whether the window has not been closed yet.
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.get().is_some()
     }
 
     #[inline]
@@ -1443,10 +2066,16 @@ impl WindowHandle {
             .attributes
             .test_and_clear(WindowAttributes::NEEDS_REDRAW)
         {
-            let _ = self.draw(|_| {});
+            self.refresh();
         }
     }
 
+    /// Redraws the window with its current contents.
+    #[inline]
+    pub fn refresh(&self) {
+        let _ = self.draw(|_| {});
+    }
+
     #[inline]
     pub fn draw<F>(&self, f: F) -> Result<(), WindowDrawingError>
     where
@@ -1480,27 +2109,7 @@ impl WindowHandle {
 
     /// Post a window message.
     pub fn post(&self, message: WindowMessage) -> Result<(), WindowPostError> {
-        match self.update_opt(|window| {
-            if let Some(queue) = window.queue.as_mut() {
-                match message {
-                    WindowMessage::Draw => {
-                        window.attributes.insert(WindowAttributes::NEEDS_REDRAW);
-                        window.waker.wake();
-                        window.sem.signal();
-                        Ok(())
-                    }
-                    _ => queue
-                        .enqueue(message)
-                        .map_err(|_| WindowPostError::Full)
-                        .map(|_| {
-                            window.waker.wake();
-                            window.sem.signal();
-                        }),
-                }
-            } else {
-                Err(WindowPostError::NotFound)
-            }
-        }) {
+        match self.update_opt(|window| window.post(message)) {
             Some(v) => v,
             None => Err(WindowPostError::NotFound),
         }
@@ -1509,23 +2118,10 @@ impl WindowHandle {
     /// Read a window message from the message queue.
     pub fn read_message(&self) -> Option<WindowMessage> {
         self.update_opt(|window| {
-            if let Some(queue) = window.queue.as_mut() {
-                match queue.dequeue() {
-                    Some(v) => Some(v),
-                    _ => {
-                        if window
-                            .attributes
-                            .test_and_clear(WindowAttributes::NEEDS_REDRAW)
-                        {
-                            Some(WindowMessage::Draw)
-                        } else {
-                            None
-                        }
-                    }
-                }
-            } else {
-                None
-            }
+            window
+                .queue
+                .as_ref()
+                .and_then(|queue| RawWindow::next_message(queue, &window.attributes))
         })
         .and_then(|v| v)
     }
@@ -1533,22 +2129,22 @@ impl WindowHandle {
     /// Wait for window messages to be read.
     pub fn wait_message(&self) -> Option<WindowMessage> {
         loop {
-            let window = match self.get() {
-                Some(window) => window,
-                None => return None,
-            };
+            // Keeps the window alive even if it is closed while waiting
+            let window = WindowManager::shared().get_shared(self)?;
             match self.read_message() {
                 Some(message) => return Some(message),
-                None => window.sem.wait(),
+                None => unsafe { (*window.get()).sem.wait() },
             }
         }
     }
 
     /// Supports asynchronous reading of window messages.
     pub fn poll_message(&self, cx: &mut Context<'_>) -> Option<WindowMessage> {
-        self.as_ref().waker.register(cx.waker());
+        let shared_window = WindowManager::shared().get_shared(self)?;
+        let window = unsafe { &*shared_window.get() };
+        window.waker.register(cx.waker());
         self.read_message().map(|message| {
-            self.as_ref().waker.take();
+            window.waker.take();
             message
         })
     }
@@ -1559,17 +2155,73 @@ impl WindowHandle {
     }
 
     /// Process window messages that are not handled.
-    pub fn handle_default_message(&self, message: WindowMessage) {
+    ///
+    /// * `Close` closes the window.
+    /// * `Draw` redraws the window, clearing it to its `bg_color` first if it was built with
+    ///   `clear_on_draw`.
+    /// * `Activated` and `Deactivated` request a redraw.
+    /// * `Key` is translated into `Char` and posted to the window.
+    /// * `Timer` and any other messages are dropped.
+    ///
+    /// Returns `true` if the message was consumed.
+    pub fn handle_default_message(&self, message: WindowMessage) -> bool {
         match message {
+            WindowMessage::Close => {
+                self.close();
+                true
+            }
             WindowMessage::Draw => {
-                self.draw(|_bitmap| {}).unwrap();
+                self.as_ref().clear_if_needed();
+                self.refresh();
+                true
+            }
+            WindowMessage::Activated
+            | WindowMessage::Deactivated
+            | WindowMessage::Resized { .. } => {
+                self.set_needs_display();
+                true
             }
             WindowMessage::Key(key) => {
                 if let Some(c) = key.key_data().map(|v| v.into_char()) {
                     let _ = self.post(WindowMessage::Char(c));
                 }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Post a character to the input queue of the window.
+    pub fn post_char(&self, c: char) -> Result<(), WindowPostError> {
+        match self.update_opt(|window| window.post_char(c)) {
+            Some(v) => v,
+            None => Err(WindowPostError::NotFound),
+        }
+    }
+
+    /// Read a character from the input queue without blocking.
+    pub fn read_char(&self) -> Option<char> {
+        self.get().and_then(|window| window.read_char())
+    }
+
+    /// Wait for a character to arrive in the input queue.
+    ///
+    /// Other messages received while waiting are processed by the default handler.
+    /// Returns `None` if the window has no character queue.
+    pub fn wait_char(&self) -> Option<char> {
+        if self.get()?.char_queue.is_none() {
+            return None;
+        }
+        loop {
+            if let Some(c) = self.read_char() {
+                return Some(c);
+            }
+            match self.wait_message()? {
+                WindowMessage::Draw => self.refresh(),
+                message => {
+                    self.handle_default_message(message);
+                }
             }
-            _ => (),
         }
     }
 
@@ -1588,6 +2240,9 @@ impl Future for WindowMessageConsumer {
     type Output = Option<WindowMessage>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if !self.handle.is_valid() {
+            return Poll::Ready(None);
+        }
         match self.handle.poll_message(cx) {
             Some(v) => Poll::Ready(Some(v)),
             None => Poll::Pending,
@@ -1629,8 +2284,14 @@ pub enum WindowMessage {
     MouseMove(MouseEvent),
     MouseDown(MouseEvent),
     MouseUp(MouseEvent),
+    /// The left button was pressed twice in a short time
+    DoubleClick(MouseEvent),
     MouseEnter,
     MouseLeave,
+    /// The window was resized
+    Resized {
+        new_size: Size,
+    },
     /// Timer event
     Timer(usize),
     /// User Defined
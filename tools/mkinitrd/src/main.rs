@@ -9,7 +9,10 @@ fn usage() -> ! {
     let arg = args.next().unwrap();
     let path = Path::new(&arg);
     let lpc = path.file_name().unwrap();
-    eprintln!("{} [OPTIONS] OUTPUT [FILES...]", lpc.to_str().unwrap());
+    eprintln!(
+        "{} [OPTIONS] OUTPUT [FILES or ALIAS=TARGET...]",
+        lpc.to_str().unwrap()
+    );
     process::exit(1);
 }
 
@@ -44,6 +47,12 @@ fn main() {
     println!("CREATING archive: {}", path_output);
 
     for arg in args {
+        if let Some((alias, target)) = arg.split_once('=') {
+            println!("LINKING: {} => {}", alias, target);
+            let dir_ent = DirEnt::new(alias).expect("file name");
+            fs.append_alias(&dir_ent, target).expect("alias target");
+            continue;
+        }
         let path = Path::new(&arg);
         let lpc = path.file_name().unwrap();
         let basename = lpc.to_str().unwrap();
@@ -67,7 +76,9 @@ fn main() {
 pub struct DirEnt {
     flag: u8,
     name: [u8; Self::MAX_NANE_LEN],
-    _reserved: (u32, u32),
+    /// Inode of the target entry if this is an alias, or 0
+    link: u32,
+    _reserved: u32,
     offset: u32,
     file_size: u32,
 }
@@ -88,12 +99,17 @@ impl DirEnt {
         Some(Self {
             flag,
             name: array,
-            _reserved: (0, 0),
+            link: 0,
+            _reserved: 0,
             offset: 0,
             file_size: 0,
         })
     }
 
+    pub fn name(&self) -> &[u8] {
+        &self.name[..self.flag as usize]
+    }
+
     pub fn as_bytes(&self) -> &[u8; 32] {
         unsafe { core::mem::transmute(self) }
     }
@@ -131,6 +147,21 @@ impl InitRamfs {
         }
     }
 
+    /// Appends an entry that shares the data of the file named `target` added before.
+    pub fn append_alias(&mut self, dir_ent: &DirEnt, target: &str) -> Option<()> {
+        let index = self
+            .dir
+            .iter()
+            .position(|v| v.name() == target.as_bytes())?;
+        let target = self.dir[index];
+        let mut dir_ent = *dir_ent;
+        dir_ent.link = index as u32 + 1;
+        dir_ent.offset = target.offset;
+        dir_ent.file_size = target.file_size;
+        self.dir.push(dir_ent);
+        Some(())
+    }
+
     pub fn flush(&self, os: &mut dyn Write) -> Result<(), VirtualDiskError> {
         let mut dir = Vec::with_capacity(self.dir.len());
         for dir_ent in &self.dir {